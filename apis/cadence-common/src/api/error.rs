@@ -163,6 +163,7 @@ impl IntoResponse for APIResponseError {
                     EntityError::InvalidState(_) => StatusCode::BAD_REQUEST, // 400 (or 409 Conflict sometimes)
                     EntityError::InvalidTransition(_) => StatusCode::BAD_REQUEST, // 400
                     EntityError::InvalidUniqueConstraint(_) => StatusCode::CONFLICT, // 409
+                    EntityError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS, // 429
                     // Consider other specific mappings
                     _ => StatusCode::INTERNAL_SERVER_ERROR, // Default for unexpected entity/db issues
                 }
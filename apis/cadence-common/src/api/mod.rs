@@ -1,5 +1,6 @@
 pub mod error;
 pub mod response;
+pub mod responses;
 pub mod requests;
 pub mod state;
 pub mod service;
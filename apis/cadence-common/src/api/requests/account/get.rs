@@ -1,8 +1,10 @@
+use chrono::Datelike;
 use serde::Deserialize;
 use utoipa::{IntoParams, ToSchema};
 
 use crate::api::error::APIResponseErrorDetail;
 use crate::api::requests::traits::Validation;
+use crate::entities::usage::billing_period::BillingPeriod;
 
 #[derive(Debug, Deserialize, IntoParams, ToSchema)]
 pub struct GetAccountQuery {
@@ -87,3 +89,35 @@ impl Validation<Vec<uuid::Uuid>> for GetAccountsQuery {
         Ok(ids)
     }
 }
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct GetUsageSummaryQuery {
+    /// Year of the billing month, the current month when both are omitted.
+    #[schema(example = 2025)]
+    pub year: Option<i32>,
+    /// Month of the billing month, from 1 to 12.
+    #[schema(example = 6)]
+    pub month: Option<u32>,
+}
+
+impl Validation<(i32, u32)> for GetUsageSummaryQuery {
+    fn validate(&self) -> Result<(i32, u32), Vec<APIResponseErrorDetail>> {
+        match (self.year, self.month) {
+            (None, None) => {
+                let now = chrono::Utc::now();
+                Ok((now.year(), now.month()))
+            }
+            (Some(year), Some(month)) if BillingPeriod::month(year, month).is_some() => {
+                Ok((year, month))
+            }
+            (Some(year), Some(month)) => Err(vec![APIResponseErrorDetail::body(
+                "month",
+                format!("Invalid billing month: {}-{}", year, month),
+            )]),
+            _ => Err(vec![APIResponseErrorDetail::body(
+                "month",
+                "Year and month must be provided together.".to_string(),
+            )]),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod account;
 pub mod auth;
+pub mod room;
 pub mod traits;
 #[cfg(test)]
 pub mod tests;
\ No newline at end of file
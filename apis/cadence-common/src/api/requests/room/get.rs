@@ -0,0 +1,414 @@
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::api::error::APIResponseErrorDetail;
+use crate::api::requests::traits::Validation;
+use crate::entities::room::room::RoomType;
+use crate::entities::room::search::{MAX_SEARCH_LIMIT, MAX_SEARCH_OFFSET};
+use crate::api::requests::room::post::{parse_ticket_priority, parse_ticket_status};
+use crate::entities::services::moderation::MAX_REVIEW_QUEUE_LIMIT;
+use crate::entities::services::room::RoomActivityCursor;
+use crate::entities::services::ticket::{AssigneeFilter, MAX_TICKET_QUEUE_LIMIT, TicketQueueFilter};
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct GetRoomUnreadCountQuery {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+}
+
+impl Validation<uuid::Uuid> for GetRoomUnreadCountQuery {
+    fn validate(&self) -> Result<uuid::Uuid, Vec<APIResponseErrorDetail>> {
+        uuid::Uuid::parse_str(&self.room_id).map_err(|_| {
+            vec![APIResponseErrorDetail::query(
+                "room_id",
+                format!("Invalid room ID format: {}", self.room_id),
+            )]
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct GetUnreadRoomsQuery {
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+impl Validation<(u64, u64)> for GetUnreadRoomsQuery {
+    fn validate(&self) -> Result<(u64, u64), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let limit = self.limit.unwrap_or(20);
+        let offset = self.offset.unwrap_or(0);
+
+        if limit == 0 || limit > 100 {
+            details.push(APIResponseErrorDetail::query(
+                "limit",
+                "Limit must be between 1 and 100.".to_string(),
+            ));
+        }
+
+        if offset > 1000 {
+            details.push(APIResponseErrorDetail::query(
+                "offset",
+                "Offset must be at most 1000.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+
+        Ok((limit, offset))
+    }
+}
+
+/// Page of the rooms of the caller, most recently active first. `cursor` is the
+/// `next_cursor` of the previous page.
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ListRoomsQuery {
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+    #[schema(example = "1735689600000_123e4567-e89b-12d3-a456-426614174000")]
+    pub cursor: Option<String>,
+}
+
+impl Validation<(u64, Option<RoomActivityCursor>)> for ListRoomsQuery {
+    fn validate(&self) -> Result<(u64, Option<RoomActivityCursor>), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let limit = self.limit.unwrap_or(20);
+        if limit == 0 || limit > 100 {
+            details.push(APIResponseErrorDetail::query(
+                "limit",
+                "Limit must be between 1 and 100.".to_string(),
+            ));
+        }
+
+        let mut cursor = None;
+        if let Some(ref value) = self.cursor {
+            cursor = RoomActivityCursor::parse(value);
+            if cursor.is_none() {
+                details.push(APIResponseErrorDetail::query(
+                    "cursor",
+                    "Invalid cursor.".to_string(),
+                ));
+            }
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+
+        Ok((limit, cursor))
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct SearchPublicRoomsQuery {
+    /// Text matched against the room name and description.
+    #[schema(example = "rust")]
+    pub q: Option<String>,
+    #[schema(example = "group")]
+    pub room_type: Option<String>,
+    #[schema(example = "gpt-4o")]
+    pub model_tag: Option<String>,
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+impl Validation<(Option<RoomType>, u64, u64)> for SearchPublicRoomsQuery {
+    fn validate(&self) -> Result<(Option<RoomType>, u64, u64), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_type = match self.room_type.as_deref() {
+            None => None,
+            Some("alone") => Some(RoomType::Alone),
+            Some("group") => Some(RoomType::Group),
+            Some("support") => Some(RoomType::Support),
+            Some(other) => {
+                details.push(APIResponseErrorDetail::query(
+                    "room_type",
+                    format!("Unknown room type: {}", other),
+                ));
+                None
+            }
+        };
+
+        if self.q.as_ref().is_some_and(|q| q.len() > 100) {
+            details.push(APIResponseErrorDetail::query(
+                "q",
+                "Search query must be at most 100 characters long.".to_string(),
+            ));
+        }
+
+        let limit = self.limit.unwrap_or(20);
+        let offset = self.offset.unwrap_or(0);
+
+        if limit == 0 || limit > 100 {
+            details.push(APIResponseErrorDetail::query(
+                "limit",
+                "Limit must be between 1 and 100.".to_string(),
+            ));
+        }
+
+        if offset > 1000 {
+            details.push(APIResponseErrorDetail::query(
+                "offset",
+                "Offset must be at most 1000.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+
+        Ok((room_type, limit, offset))
+    }
+}
+
+/// Full-text search of messages, in a single room when `room_id` is given, otherwise
+/// across every room of the caller.
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct SearchMessagesQuery {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: Option<String>,
+    /// Words matched against the message content, the last one as a prefix.
+    #[schema(example = "deploy fri")]
+    pub q: String,
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+impl Validation<(Option<uuid::Uuid>, String, u64, u64)> for SearchMessagesQuery {
+    fn validate(
+        &self,
+    ) -> Result<(Option<uuid::Uuid>, String, u64, u64), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = match self.room_id.as_deref() {
+            None => None,
+            Some(room_id) => match uuid::Uuid::parse_str(room_id) {
+                Ok(room_id) => Some(room_id),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::query(
+                        "room_id",
+                        format!("Invalid room ID format: {}", room_id),
+                    ));
+                    None
+                }
+            },
+        };
+
+        let q = self.q.trim();
+        if q.is_empty() {
+            details.push(APIResponseErrorDetail::query(
+                "q",
+                "Search query cannot be empty.".to_string(),
+            ));
+        } else if q.len() > 100 {
+            details.push(APIResponseErrorDetail::query(
+                "q",
+                "Search query must be at most 100 characters long.".to_string(),
+            ));
+        }
+
+        let limit = self.limit.unwrap_or(20);
+        let offset = self.offset.unwrap_or(0);
+
+        if limit == 0 || limit > MAX_SEARCH_LIMIT {
+            details.push(APIResponseErrorDetail::query(
+                "limit",
+                format!("Limit must be between 1 and {}.", MAX_SEARCH_LIMIT),
+            ));
+        }
+
+        if offset > MAX_SEARCH_OFFSET {
+            details.push(APIResponseErrorDetail::query(
+                "offset",
+                format!("Offset must be at most {}.", MAX_SEARCH_OFFSET),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+
+        Ok((room_id, q.to_string(), limit, offset))
+    }
+}
+
+/// Page of the moderation review queue, of a single room when `room_id` is given,
+/// otherwise of every room the caller moderates.
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct GetReviewQueueQuery {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: Option<String>,
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+impl Validation<(Option<uuid::Uuid>, u64, u64)> for GetReviewQueueQuery {
+    fn validate(&self) -> Result<(Option<uuid::Uuid>, u64, u64), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = match self.room_id.as_deref() {
+            None => None,
+            Some(room_id) => match uuid::Uuid::parse_str(room_id) {
+                Ok(room_id) => Some(room_id),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::query(
+                        "room_id",
+                        format!("Invalid room ID format: {}", room_id),
+                    ));
+                    None
+                }
+            },
+        };
+
+        let limit = self.limit.unwrap_or(20);
+        if limit == 0 || limit > MAX_REVIEW_QUEUE_LIMIT {
+            details.push(APIResponseErrorDetail::query(
+                "limit",
+                format!("Limit must be between 1 and {}.", MAX_REVIEW_QUEUE_LIMIT),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+
+        Ok((room_id, limit, self.offset.unwrap_or(0)))
+    }
+}
+
+/// Page of the staff queue of support tickets.
+///
+/// - `status`: comma separated statuses, `open,pending` by default.
+/// - `assignee`: `unassigned`, or the ID of a staff account.
+/// - `breached`: only the tickets that missed a deadline.
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct GetTicketQueueQuery {
+    #[schema(example = "open,pending")]
+    pub status: Option<String>,
+    #[schema(example = "urgent")]
+    pub priority: Option<String>,
+    #[schema(example = "unassigned")]
+    pub assignee: Option<String>,
+    #[schema(example = false)]
+    pub breached: Option<bool>,
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+impl Validation<(TicketQueueFilter, u64, u64)> for GetTicketQueueQuery {
+    fn validate(&self) -> Result<(TicketQueueFilter, u64, u64), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+        let mut filter = TicketQueueFilter {
+            breached: self.breached.unwrap_or(false),
+            ..Default::default()
+        };
+
+        if let Some(status) = self.status.as_deref() {
+            for status in status.split(',').filter(|s| !s.trim().is_empty()) {
+                match parse_ticket_status(status) {
+                    Some(status) => filter.statuses.push(status),
+                    None => {
+                        details.push(APIResponseErrorDetail::query(
+                            "status",
+                            format!("Unknown ticket status: {}", status.trim()),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Some(priority) = self.priority.as_deref() {
+            filter.priority = parse_ticket_priority(priority);
+            if filter.priority.is_none() {
+                details.push(APIResponseErrorDetail::query(
+                    "priority",
+                    "Must be one of low, normal, high or urgent.".to_string(),
+                ));
+            }
+        }
+
+        if let Some(assignee) = self.assignee.as_deref() {
+            if assignee.eq_ignore_ascii_case("unassigned") {
+                filter.assignee = Some(AssigneeFilter::Unassigned);
+            } else {
+                match uuid::Uuid::parse_str(assignee) {
+                    Ok(account_id) => filter.assignee = Some(AssigneeFilter::Account(account_id)),
+                    Err(_) => {
+                        details.push(APIResponseErrorDetail::query(
+                            "assignee",
+                            "Must be unassigned or a valid UUID.".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let limit = self.limit.unwrap_or(20);
+        if limit == 0 || limit > MAX_TICKET_QUEUE_LIMIT {
+            details.push(APIResponseErrorDetail::query(
+                "limit",
+                format!("Limit must be between 1 and {}.", MAX_TICKET_QUEUE_LIMIT),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+
+        Ok((filter, limit, self.offset.unwrap_or(0)))
+    }
+}
+
+/// Query of a signed attachment download URL, as issued by
+/// `AttachmentService::create_download_url`.
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct GetAttachmentDownloadQuery {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub account: String,
+    /// Expiry of the URL, in milliseconds since the Unix epoch.
+    #[schema(example = 1767225600000_i64)]
+    pub expires: i64,
+    #[schema(example = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")]
+    pub signature: String,
+}
+
+impl Validation<uuid::Uuid> for GetAttachmentDownloadQuery {
+    fn validate(&self) -> Result<uuid::Uuid, Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let account_id = uuid::Uuid::parse_str(&self.account);
+        if account_id.is_err() {
+            details.push(APIResponseErrorDetail::query(
+                "account",
+                format!("Invalid account ID format: {}", self.account),
+            ));
+        }
+
+        if self.signature.len() != 64 || !self.signature.chars().all(|c| c.is_ascii_hexdigit()) {
+            details.push(APIResponseErrorDetail::query(
+                "signature",
+                "Signature must be 64 hexadecimal characters.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+        Ok(account_id.unwrap())
+    }
+}
@@ -0,0 +1,1087 @@
+use axum::extract::Multipart;
+use axum::extract::multipart::MultipartError;
+use hyper::body::Bytes;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::api::error::APIResponseErrorDetail;
+use crate::api::requests::traits::Validation;
+use crate::entities::room::export::ExportFormat;
+use crate::entities::room::member::MemberRole;
+use crate::entities::room::report::{ModerationAction, ReportReason};
+use crate::entities::room::scheduled_message::MAX_MESSAGE_TTL_MS;
+use crate::entities::room::settings::{
+    MAX_MESSAGE_LENGTH, MAX_PINNED_MESSAGES, MAX_SLOW_MODE_INTERVAL_MS,
+};
+use crate::entities::room::ticket::{TicketPriority, TicketStatus};
+use crate::entities::services::room::{
+    BulkMessageAction, BulkMessageFilter, MAX_BULK_MESSAGES, RoomSettingsUpdateSchema,
+};
+use crate::entities::services::moderation::MAX_REPORT_DETAILS_LENGTH;
+use crate::export::import::ImportFormat;
+use crate::input_validation::string_to_uuid;
+
+// --- Room Related Requests ---
+
+/// Represents the data required to advance the read marker of the caller in a room.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MarkRoomAsReadRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// Last message read. Omit to mark the whole room as read.
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub message_id: Option<String>,
+}
+
+impl Validation<(uuid::Uuid, Option<uuid::Uuid>)> for MarkRoomAsReadRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, Option<uuid::Uuid>), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let mut message_id = None;
+        if let Some(ref id) = self.message_id {
+            match string_to_uuid(id) {
+                Ok(uuid) => message_id = Some(uuid),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "message_id",
+                        "Must be a valid UUID.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+        Ok((room_id.unwrap(), message_id))
+    }
+}
+
+/// Represents the data required to invite an account to a room, or to create an invite link
+/// when `invitee_account_id` is omitted.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateRoomInviteRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub invitee_account_id: Option<String>,
+    /// Maximum number of times an invite link can be accepted. Unlimited when omitted.
+    #[schema(example = 10, nullable = true)]
+    pub max_uses: Option<i32>,
+    /// Expiry of the invite, in milliseconds since the Unix epoch.
+    #[schema(example = 1767225600000_i64, nullable = true)]
+    pub expires_at: Option<i64>,
+}
+
+impl Validation<(uuid::Uuid, Option<uuid::Uuid>)> for CreateRoomInviteRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, Option<uuid::Uuid>), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let mut invitee_account_id = None;
+        if let Some(ref id) = self.invitee_account_id {
+            match string_to_uuid(id) {
+                Ok(uuid) => invitee_account_id = Some(uuid),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "invitee_account_id",
+                        "Must be a valid UUID.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if self.max_uses.is_some_and(|max_uses| max_uses <= 0) {
+            details.push(APIResponseErrorDetail::body(
+                "max_uses",
+                "Max uses must be greater than 0.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+        Ok((room_id.unwrap(), invitee_account_id))
+    }
+}
+
+/// Represents the data required to accept an invite, either a direct invite by its id
+/// or an invite link by its token. Exactly one of them must be provided.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AcceptRoomInviteRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub invite_id: Option<String>,
+    #[schema(example = "9f1c2d3e4b5a69788796a5b4c3d2e1f0", nullable = true)]
+    pub token: Option<String>,
+    /// Join the room with an anonymized identity.
+    #[schema(example = false)]
+    pub anonymize: Option<bool>,
+}
+
+impl Validation<Option<uuid::Uuid>> for AcceptRoomInviteRequest {
+    fn validate(&self) -> Result<Option<uuid::Uuid>, Vec<APIResponseErrorDetail>> {
+        match (&self.invite_id, &self.token) {
+            (Some(id), None) => string_to_uuid(id).map(Some).map_err(|_| {
+                vec![APIResponseErrorDetail::body(
+                    "invite_id",
+                    "Must be a valid UUID.".to_string(),
+                )]
+            }),
+            (None, Some(token)) if !token.trim().is_empty() => Ok(None),
+            _ => Err(vec![APIResponseErrorDetail::body(
+                "invite_id",
+                "Either invite_id or token must be provided.".to_string(),
+            )]),
+        }
+    }
+}
+
+/// Represents the data required to decline a direct invite.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DeclineRoomInviteRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub invite_id: String,
+}
+
+impl Validation<uuid::Uuid> for DeclineRoomInviteRequest {
+    fn validate(&self) -> Result<uuid::Uuid, Vec<APIResponseErrorDetail>> {
+        string_to_uuid(&self.invite_id).map_err(|_| {
+            vec![APIResponseErrorDetail::body(
+                "invite_id",
+                "Must be a valid UUID.".to_string(),
+            )]
+        })
+    }
+}
+
+/// Represents the data required to join a public room, or to leave any room.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RoomMembershipRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// Join the room with an anonymized identity. Ignored when leaving.
+    #[schema(example = false)]
+    pub anonymize: Option<bool>,
+}
+
+impl Validation<uuid::Uuid> for RoomMembershipRequest {
+    fn validate(&self) -> Result<uuid::Uuid, Vec<APIResponseErrorDetail>> {
+        string_to_uuid(&self.room_id).map_err(|_| {
+            vec![APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            )]
+        })
+    }
+}
+
+/// Represents the account to open a direct conversation with. The existing direct room
+/// of the two accounts is returned when there is one.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OpenDirectRoomRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub account_id: String,
+}
+
+impl Validation<uuid::Uuid> for OpenDirectRoomRequest {
+    fn validate(&self) -> Result<uuid::Uuid, Vec<APIResponseErrorDetail>> {
+        string_to_uuid(&self.account_id).map_err(|_| {
+            vec![APIResponseErrorDetail::body(
+                "account_id",
+                "Must be a valid UUID.".to_string(),
+            )]
+        })
+    }
+}
+
+/// Represents the data required to create a room from a template. The latest published
+/// version of the template is used when `template_version_id` is omitted.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateRoomFromTemplateRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub template_id: String,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub template_version_id: Option<String>,
+    /// Name of the room. Defaults to the name of the template.
+    #[schema(example = "My Room", nullable = true)]
+    pub name: Option<String>,
+    /// Join the room with an anonymized identity.
+    #[schema(example = false)]
+    pub anonymize: Option<bool>,
+}
+
+impl Validation<(uuid::Uuid, Option<uuid::Uuid>)> for CreateRoomFromTemplateRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, Option<uuid::Uuid>), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let template_id = string_to_uuid(&self.template_id);
+        if template_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "template_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let mut template_version_id = None;
+        if let Some(ref id) = self.template_version_id {
+            match string_to_uuid(id) {
+                Ok(uuid) => template_version_id = Some(uuid),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "template_version_id",
+                        "Must be a valid UUID.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            details.push(APIResponseErrorDetail::body(
+                "name",
+                "Name cannot be empty.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+        Ok((template_id.unwrap(), template_version_id))
+    }
+}
+
+/// Represents the posting rules of a room to edit. Omitted fields are left as they are,
+/// and `0` turns the slow mode or the message length limit off.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UpdateRoomSettingsRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// Minimum time between two messages of a member, in seconds.
+    #[schema(example = 30, nullable = true)]
+    pub slow_mode_seconds: Option<i64>,
+    #[schema(example = 2000, nullable = true)]
+    pub max_message_length: Option<i32>,
+    #[schema(example = true, nullable = true)]
+    pub allow_replies: Option<bool>,
+    #[schema(example = false, nullable = true)]
+    pub allow_attachments: Option<bool>,
+    /// Only the owners can post.
+    #[schema(example = false, nullable = true)]
+    pub announcement_mode: Option<bool>,
+    /// Lowest role allowed to pin messages: `owner`, `admin`, `moderator` or `member`.
+    #[schema(example = "moderator", nullable = true)]
+    pub pin_role: Option<String>,
+    #[schema(example = 50, nullable = true)]
+    pub max_pinned_messages: Option<i32>,
+}
+
+/// Parses a member role that can be given the right to pin messages.
+pub(crate) fn parse_pin_role(role: &str) -> Option<MemberRole> {
+    match role.trim().to_ascii_lowercase().as_str() {
+        "owner" => Some(MemberRole::Owner),
+        "admin" => Some(MemberRole::Admin),
+        "moderator" => Some(MemberRole::Moderator),
+        "member" => Some(MemberRole::Member),
+        _ => None,
+    }
+}
+
+impl Validation<(uuid::Uuid, RoomSettingsUpdateSchema)> for UpdateRoomSettingsRequest {
+    fn validate(
+        &self,
+    ) -> Result<(uuid::Uuid, RoomSettingsUpdateSchema), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        if let Some(seconds) = self.slow_mode_seconds
+            && !(0..=MAX_SLOW_MODE_INTERVAL_MS / 1000).contains(&seconds)
+        {
+            details.push(APIResponseErrorDetail::body(
+                "slow_mode_seconds",
+                format!(
+                    "Must be between 0 and {}.",
+                    MAX_SLOW_MODE_INTERVAL_MS / 1000
+                ),
+            ));
+        }
+
+        if let Some(max_length) = self.max_message_length
+            && !(0..=MAX_MESSAGE_LENGTH).contains(&max_length)
+        {
+            details.push(APIResponseErrorDetail::body(
+                "max_message_length",
+                format!("Must be between 0 and {}.", MAX_MESSAGE_LENGTH),
+            ));
+        }
+
+        let pin_role = self.pin_role.as_deref().map(parse_pin_role);
+        if pin_role == Some(None) {
+            details.push(APIResponseErrorDetail::body(
+                "pin_role",
+                "Must be one of owner, admin, moderator or member.".to_string(),
+            ));
+        }
+
+        if let Some(max_pinned) = self.max_pinned_messages
+            && !(1..=MAX_PINNED_MESSAGES).contains(&max_pinned)
+        {
+            details.push(APIResponseErrorDetail::body(
+                "max_pinned_messages",
+                format!("Must be between 1 and {}.", MAX_PINNED_MESSAGES),
+            ));
+        }
+
+        match room_id {
+            Ok(room_id) if details.is_empty() => Ok((
+                room_id,
+                RoomSettingsUpdateSchema {
+                    slow_mode_interval_ms: self.slow_mode_seconds.map(|s| s * 1000),
+                    max_message_length: self.max_message_length,
+                    allow_replies: self.allow_replies,
+                    allow_attachments: self.allow_attachments,
+                    announcement_mode: self.announcement_mode,
+                    pin_role: pin_role.flatten(),
+                    max_pinned_messages: self.max_pinned_messages,
+                },
+            )),
+            _ => Err(details),
+        }
+    }
+}
+
+// --- Message Related Requests ---
+
+/// Represents a message to post later. `scheduled_for` is a timestamp in milliseconds, and
+/// the posted message is deleted `ttl_seconds` after it is posted.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ScheduleMessageRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    #[schema(example = "Happy new year!", nullable = true)]
+    pub content: Option<String>,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub attachment_id: Option<String>,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub reply_to: Option<String>,
+    #[schema(example = 1767225600000_i64)]
+    pub scheduled_for: i64,
+    #[schema(example = 3600, nullable = true)]
+    pub ttl_seconds: Option<i64>,
+}
+
+impl Validation<(uuid::Uuid, Option<uuid::Uuid>, Option<uuid::Uuid>)> for ScheduleMessageRequest {
+    fn validate(
+        &self,
+    ) -> Result<(uuid::Uuid, Option<uuid::Uuid>, Option<uuid::Uuid>), Vec<APIResponseErrorDetail>>
+    {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let mut attachment_id = None;
+        if let Some(ref id) = self.attachment_id {
+            match string_to_uuid(id) {
+                Ok(uuid) => attachment_id = Some(uuid),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "attachment_id",
+                        "Must be a valid UUID.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let mut reply_to = None;
+        if let Some(ref id) = self.reply_to {
+            match string_to_uuid(id) {
+                Ok(uuid) => reply_to = Some(uuid),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "reply_to",
+                        "Must be a valid UUID.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if self.content.as_deref().is_none_or(|c| c.trim().is_empty())
+            && self.attachment_id.is_none()
+        {
+            details.push(APIResponseErrorDetail::body(
+                "content",
+                "Content cannot be empty without an attachment.".to_string(),
+            ));
+        }
+
+        if self.scheduled_for <= 0 {
+            details.push(APIResponseErrorDetail::body(
+                "scheduled_for",
+                "Must be a timestamp in milliseconds.".to_string(),
+            ));
+        }
+
+        if let Some(ttl_seconds) = self.ttl_seconds
+            && !(1..=MAX_MESSAGE_TTL_MS / 1000).contains(&ttl_seconds)
+        {
+            details.push(APIResponseErrorDetail::body(
+                "ttl_seconds",
+                format!("Must be between 1 and {}.", MAX_MESSAGE_TTL_MS / 1000),
+            ));
+        }
+
+        match room_id {
+            Ok(room_id) if details.is_empty() => Ok((room_id, attachment_id, reply_to)),
+            _ => Err(details),
+        }
+    }
+}
+
+/// Represents a bulk operation on the messages of a room. The messages must match every
+/// given criterion: `message_ids`, the `member_id` of their author, and the inclusive
+/// `from`/`to` range of creation times in milliseconds. With `dry_run`, only the number of
+/// messages that would change is returned.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BulkMessagesRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// One of `hide`, `unhide`, `delete` or `purge`.
+    #[schema(example = "delete")]
+    pub action: String,
+    #[schema(nullable = true)]
+    pub message_ids: Option<Vec<String>>,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub member_id: Option<String>,
+    #[schema(example = 1735689600000_i64, nullable = true)]
+    pub from: Option<i64>,
+    #[schema(example = 1735776000000_i64, nullable = true)]
+    pub to: Option<i64>,
+    #[schema(example = true)]
+    pub dry_run: Option<bool>,
+}
+
+impl Validation<(uuid::Uuid, BulkMessageAction, BulkMessageFilter)> for BulkMessagesRequest {
+    fn validate(
+        &self,
+    ) -> Result<(uuid::Uuid, BulkMessageAction, BulkMessageFilter), Vec<APIResponseErrorDetail>>
+    {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let action = match self.action.to_ascii_lowercase().as_str() {
+            "hide" => Some(BulkMessageAction::Hide),
+            "unhide" => Some(BulkMessageAction::Unhide),
+            "delete" => Some(BulkMessageAction::Delete),
+            "purge" => Some(BulkMessageAction::Purge),
+            _ => {
+                details.push(APIResponseErrorDetail::body(
+                    "action",
+                    "Must be one of hide, unhide, delete or purge.".to_string(),
+                ));
+                None
+            }
+        };
+
+        let mut filter = BulkMessageFilter {
+            from: self.from,
+            to: self.to,
+            ..Default::default()
+        };
+
+        if let Some(ref ids) = self.message_ids {
+            if ids.is_empty() || ids.len() as u64 > MAX_BULK_MESSAGES {
+                details.push(APIResponseErrorDetail::body(
+                    "message_ids",
+                    format!("Must hold between 1 and {} ids.", MAX_BULK_MESSAGES),
+                ));
+            }
+            match ids
+                .iter()
+                .map(|id| string_to_uuid(id))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(ids) => filter.message_ids = Some(ids),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "message_ids",
+                        "Must only hold valid UUIDs.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if let Some(ref id) = self.member_id {
+            match string_to_uuid(id) {
+                Ok(uuid) => filter.member_id = Some(uuid),
+                Err(_) => {
+                    details.push(APIResponseErrorDetail::body(
+                        "member_id",
+                        "Must be a valid UUID.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && from > to
+        {
+            details.push(APIResponseErrorDetail::body(
+                "to",
+                "Must not be before from.".to_string(),
+            ));
+        }
+
+        if self.message_ids.is_none()
+            && self.member_id.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+        {
+            details.push(APIResponseErrorDetail::body(
+                "message_ids",
+                "At least one of message_ids, member_id, from or to is required.".to_string(),
+            ));
+        }
+
+        match (room_id, action) {
+            (Ok(room_id), Some(action)) if details.is_empty() => Ok((room_id, action, filter)),
+            _ => Err(details),
+        }
+    }
+}
+
+// --- Attachment Related Requests ---
+
+/// Represents a `multipart/form-data` attachment upload: a `room_id` text field and a
+/// `file` field carrying the file name and content type of the upload.
+#[derive(Debug, Clone, Default)]
+pub struct UploadAttachmentRequest {
+    pub room_id: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Bytes,
+}
+
+impl UploadAttachmentRequest {
+    /// Reads the upload from a multipart body, failing as soon as the file grows past
+    /// `max_size_bytes` instead of buffering it whole. Unknown fields are ignored.
+    pub async fn from_multipart(
+        mut multipart: Multipart,
+        max_size_bytes: usize,
+    ) -> Result<Self, Vec<APIResponseErrorDetail>> {
+        let malformed = |e: MultipartError| {
+            vec![APIResponseErrorDetail::body(
+                "file",
+                format!("Malformed multipart body: {}", e),
+            )]
+        };
+
+        let mut request = UploadAttachmentRequest::default();
+        let mut has_file = false;
+
+        while let Some(mut field) = multipart.next_field().await.map_err(malformed)? {
+            match field.name() {
+                Some("room_id") => request.room_id = field.text().await.map_err(malformed)?,
+                Some("file") => {
+                    has_file = true;
+                    request.file_name = field.file_name().unwrap_or_default().to_string();
+                    request.content_type = field.content_type().unwrap_or_default().to_string();
+
+                    let mut data = Vec::new();
+                    while let Some(chunk) = field.chunk().await.map_err(malformed)? {
+                        if data.len() + chunk.len() > max_size_bytes {
+                            return Err(vec![APIResponseErrorDetail::body(
+                                "file",
+                                format!("File must be at most {} bytes.", max_size_bytes),
+                            )]);
+                        }
+                        data.extend_from_slice(&chunk);
+                    }
+                    request.data = Bytes::from(data);
+                }
+                _ => {}
+            }
+        }
+
+        if !has_file {
+            return Err(vec![APIResponseErrorDetail::body(
+                "file",
+                "A file must be provided.".to_string(),
+            )]);
+        }
+
+        Ok(request)
+    }
+}
+
+impl Validation<uuid::Uuid> for UploadAttachmentRequest {
+    fn validate(&self) -> Result<uuid::Uuid, Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        if self.content_type.trim().is_empty() {
+            details.push(APIResponseErrorDetail::body(
+                "file",
+                "File must have a content type.".to_string(),
+            ));
+        }
+
+        if self.data.is_empty() {
+            details.push(APIResponseErrorDetail::body(
+                "file",
+                "File cannot be empty.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+        Ok(room_id.unwrap())
+    }
+}
+
+// --- Export Related Requests ---
+
+/// Represents the data required to export the conversation of a room.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateRoomExportRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// One of `json`, `markdown` or `html`.
+    #[schema(example = "markdown")]
+    pub format: String,
+}
+
+impl Validation<(uuid::Uuid, ExportFormat)> for CreateRoomExportRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, ExportFormat), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let format = match self.format.to_ascii_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "markdown" | "md" => Some(ExportFormat::Markdown),
+            "html" => Some(ExportFormat::Html),
+            _ => {
+                details.push(APIResponseErrorDetail::body(
+                    "format",
+                    "Must be one of json, markdown or html.".to_string(),
+                ));
+                None
+            }
+        };
+
+        match (room_id, format) {
+            (Ok(room_id), Some(format)) if details.is_empty() => Ok((room_id, format)),
+            _ => Err(details),
+        }
+    }
+}
+
+// --- Moderation Related Requests ---
+
+/// Represents the data required to report a message to the moderators of its room.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReportMessageRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub message_id: String,
+    /// One of `spam`, `harassment`, `hate_speech`, `inappropriate` or `other`.
+    #[schema(example = "spam")]
+    pub reason: String,
+    #[schema(example = "Same link posted in every room", nullable = true)]
+    pub details: Option<String>,
+}
+
+impl Validation<(uuid::Uuid, uuid::Uuid, ReportReason, Option<String>)> for ReportMessageRequest {
+    fn validate(
+        &self,
+    ) -> Result<(uuid::Uuid, uuid::Uuid, ReportReason, Option<String>), Vec<APIResponseErrorDetail>>
+    {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let message_id = string_to_uuid(&self.message_id);
+        if message_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "message_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let reason = match self.reason.to_ascii_lowercase().as_str() {
+            "spam" => Some(ReportReason::Spam),
+            "harassment" => Some(ReportReason::Harassment),
+            "hate_speech" => Some(ReportReason::HateSpeech),
+            "inappropriate" => Some(ReportReason::Inappropriate),
+            "other" => Some(ReportReason::Other),
+            _ => {
+                details.push(APIResponseErrorDetail::body(
+                    "reason",
+                    "Must be one of spam, harassment, hate_speech, inappropriate or other."
+                        .to_string(),
+                ));
+                None
+            }
+        };
+
+        let report_details = self
+            .details
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string);
+        if report_details
+            .as_ref()
+            .is_some_and(|d| d.chars().count() > MAX_REPORT_DETAILS_LENGTH)
+        {
+            details.push(APIResponseErrorDetail::body(
+                "details",
+                format!(
+                    "Must be at most {} characters long.",
+                    MAX_REPORT_DETAILS_LENGTH
+                ),
+            ));
+        }
+
+        match (room_id, message_id, reason) {
+            (Ok(room_id), Ok(message_id), Some(reason)) if details.is_empty() => {
+                Ok((room_id, message_id, reason, report_details))
+            }
+            _ => Err(details),
+        }
+    }
+}
+
+/// Represents the decision of a moderator on a reported message.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ModerateMessageRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub message_id: String,
+    /// One of `hide`, `unhide`, `delete` or `dismiss`.
+    #[schema(example = "hide")]
+    pub action: String,
+}
+
+impl Validation<(uuid::Uuid, uuid::Uuid, ModerationAction)> for ModerateMessageRequest {
+    fn validate(
+        &self,
+    ) -> Result<(uuid::Uuid, uuid::Uuid, ModerationAction), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let message_id = string_to_uuid(&self.message_id);
+        if message_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "message_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let action = match self.action.to_ascii_lowercase().as_str() {
+            "hide" => Some(ModerationAction::Hide),
+            "unhide" => Some(ModerationAction::Unhide),
+            "delete" => Some(ModerationAction::Delete),
+            "dismiss" => Some(ModerationAction::Dismiss),
+            _ => {
+                details.push(APIResponseErrorDetail::body(
+                    "action",
+                    "Must be one of hide, unhide, delete or dismiss.".to_string(),
+                ));
+                None
+            }
+        };
+
+        match (room_id, message_id, action) {
+            (Ok(room_id), Ok(message_id), Some(action)) if details.is_empty() => {
+                Ok((room_id, message_id, action))
+            }
+            _ => Err(details),
+        }
+    }
+}
+
+// --- Ticket Related Requests ---
+
+/// Parses a ticket status, as written by `TicketStatus::as_str`.
+pub(crate) fn parse_ticket_status(status: &str) -> Option<TicketStatus> {
+    match status.trim().to_ascii_lowercase().as_str() {
+        "open" => Some(TicketStatus::Open),
+        "pending" => Some(TicketStatus::Pending),
+        "resolved" => Some(TicketStatus::Resolved),
+        "closed" => Some(TicketStatus::Closed),
+        _ => None,
+    }
+}
+
+/// Parses a ticket priority, as written by `TicketPriority::as_str`.
+pub(crate) fn parse_ticket_priority(priority: &str) -> Option<TicketPriority> {
+    match priority.trim().to_ascii_lowercase().as_str() {
+        "low" => Some(TicketPriority::Low),
+        "normal" => Some(TicketPriority::Normal),
+        "high" => Some(TicketPriority::High),
+        "urgent" => Some(TicketPriority::Urgent),
+        _ => None,
+    }
+}
+
+/// Represents the new status of the ticket of a support room.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SetTicketStatusRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// One of `open`, `pending`, `resolved` or `closed`.
+    #[schema(example = "resolved")]
+    pub status: String,
+}
+
+impl Validation<(uuid::Uuid, TicketStatus)> for SetTicketStatusRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, TicketStatus), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let status = parse_ticket_status(&self.status);
+        if status.is_none() {
+            details.push(APIResponseErrorDetail::body(
+                "status",
+                "Must be one of open, pending, resolved or closed.".to_string(),
+            ));
+        }
+
+        match (room_id, status) {
+            (Ok(room_id), Some(status)) if details.is_empty() => Ok((room_id, status)),
+            _ => Err(details),
+        }
+    }
+}
+
+/// Represents the new priority of the ticket of a support room.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SetTicketPriorityRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    /// One of `low`, `normal`, `high` or `urgent`.
+    #[schema(example = "high")]
+    pub priority: String,
+}
+
+impl Validation<(uuid::Uuid, TicketPriority)> for SetTicketPriorityRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, TicketPriority), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let priority = parse_ticket_priority(&self.priority);
+        if priority.is_none() {
+            details.push(APIResponseErrorDetail::body(
+                "priority",
+                "Must be one of low, normal, high or urgent.".to_string(),
+            ));
+        }
+
+        match (room_id, priority) {
+            (Ok(room_id), Some(priority)) if details.is_empty() => Ok((room_id, priority)),
+            _ => Err(details),
+        }
+    }
+}
+
+/// Represents the staff account to assign the ticket of a support room to, `null` to
+/// unassign it.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AssignTicketRequest {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub room_id: String,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000", nullable = true)]
+    pub assignee_id: Option<String>,
+}
+
+impl Validation<(uuid::Uuid, Option<uuid::Uuid>)> for AssignTicketRequest {
+    fn validate(&self) -> Result<(uuid::Uuid, Option<uuid::Uuid>), Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let room_id = string_to_uuid(&self.room_id);
+        if room_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "room_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        let assignee_id = match self.assignee_id.as_deref() {
+            None => Ok(None),
+            Some(assignee_id) => string_to_uuid(assignee_id).map(Some),
+        };
+        if assignee_id.is_err() {
+            details.push(APIResponseErrorDetail::body(
+                "assignee_id",
+                "Must be a valid UUID.".to_string(),
+            ));
+        }
+
+        match (room_id, assignee_id) {
+            (Ok(room_id), Ok(assignee_id)) if details.is_empty() => Ok((room_id, assignee_id)),
+            _ => Err(details),
+        }
+    }
+}
+
+// --- Import Related Requests ---
+
+/// Represents the data required to create a room out of an archive.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ImportRoomRequest {
+    /// `cadence` or `open_ai`. Detected from the archive when omitted.
+    #[schema(example = "open_ai", nullable = true)]
+    pub format: Option<String>,
+    /// Name of the room. Defaults to the name found in the archive.
+    #[schema(example = "Imported chat", nullable = true)]
+    pub name: Option<String>,
+    /// Author of the archive whose messages are the caller's.
+    #[schema(example = "User", nullable = true)]
+    pub own_author: Option<String>,
+    /// Only report what would be created.
+    #[schema(example = true)]
+    pub dry_run: Option<bool>,
+    /// A Cadence JSON export or an OpenAI-style chat transcript.
+    #[schema(value_type = Object)]
+    pub archive: serde_json::Value,
+}
+
+impl Validation<Option<ImportFormat>> for ImportRoomRequest {
+    fn validate(&self) -> Result<Option<ImportFormat>, Vec<APIResponseErrorDetail>> {
+        let mut details = Vec::new();
+
+        let format = match self.format.as_deref() {
+            None => None,
+            Some("cadence") => Some(ImportFormat::Cadence),
+            Some("open_ai" | "openai") => Some(ImportFormat::OpenAi),
+            Some(_) => {
+                details.push(APIResponseErrorDetail::body(
+                    "format",
+                    "Must be one of cadence or open_ai.".to_string(),
+                ));
+                None
+            }
+        };
+
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            details.push(APIResponseErrorDetail::body(
+                "name",
+                "Name cannot be empty.".to_string(),
+            ));
+        }
+
+        if !self.archive.is_object() && !self.archive.is_array() {
+            details.push(APIResponseErrorDetail::body(
+                "archive",
+                "Must be a JSON object or array.".to_string(),
+            ));
+        }
+
+        if !details.is_empty() {
+            return Err(details);
+        }
+        Ok(format)
+    }
+}
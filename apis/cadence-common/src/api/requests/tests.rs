@@ -1,8 +1,18 @@
 // /home/jean/cadence/apis/cadence-common/src/api/requests/tests.rs
 #![cfg(test)] // Ensure this file is only compiled for tests
 
-use super::account::get::{GetAccountQuery, GetAccountsQuery};
+use super::account::get::{GetAccountQuery, GetAccountsQuery, GetUsageSummaryQuery};
 use super::account::post::{AccountUpdateRequest, AddEmailRequest};
+use super::room::get::{
+    GetAttachmentDownloadQuery, GetReviewQueueQuery, GetTicketQueueQuery, ListRoomsQuery,
+    SearchMessagesQuery,
+};
+use super::room::post::{
+    AcceptRoomInviteRequest, BulkMessagesRequest, CreateRoomExportRequest,
+    CreateRoomFromTemplateRequest, AssignTicketRequest, ImportRoomRequest, MarkRoomAsReadRequest,
+    ModerateMessageRequest, OpenDirectRoomRequest, ReportMessageRequest, ScheduleMessageRequest,
+    SetTicketPriorityRequest, SetTicketStatusRequest, UpdateRoomSettingsRequest,
+};
 use super::traits::Validation;
 // Note: We don't need APIResponseErrorDetail, CadenceError, or InputError for these superficial tests
 // use crate::api::error::APIResponseErrorDetail;
@@ -411,3 +421,504 @@ fn test_get_accounts_query_just_comma() {
     assert!(result.is_err());
     assert!(!result.err().unwrap().is_empty());
 }
+
+// --- GetUsageSummaryQuery Tests ---
+
+#[test]
+fn test_get_usage_summary_query_month() {
+    let query = GetUsageSummaryQuery {
+        year: Some(2024),
+        month: Some(12),
+    };
+    assert_eq!(query.validate().unwrap(), (2024, 12));
+
+    let invalid = GetUsageSummaryQuery {
+        year: Some(2024),
+        month: Some(13),
+    };
+    assert!(invalid.validate().is_err());
+
+    let partial = GetUsageSummaryQuery {
+        year: None,
+        month: Some(1),
+    };
+    assert!(partial.validate().is_err());
+}
+
+// --- MarkRoomAsReadRequest Tests ---
+
+#[test]
+fn test_mark_room_as_read_request_valid_without_message() {
+    let req = MarkRoomAsReadRequest {
+        room_id: Uuid::new_v4().to_string(),
+        message_id: None,
+    };
+    let result = req.validate();
+    assert!(result.is_ok());
+    assert!(result.unwrap().1.is_none());
+}
+
+#[test]
+fn test_mark_room_as_read_request_invalid_message_id() {
+    let req = MarkRoomAsReadRequest {
+        room_id: Uuid::new_v4().to_string(),
+        message_id: Some("not-a-uuid".to_string()),
+    };
+    let result = req.validate();
+    assert!(result.is_err());
+    assert_eq!(result.err().unwrap().len(), 1);
+}
+
+// --- AcceptRoomInviteRequest Tests ---
+
+#[test]
+fn test_accept_invite_request_with_token() {
+    let req = AcceptRoomInviteRequest {
+        invite_id: None,
+        token: Some("9f1c2d3e4b5a69788796a5b4c3d2e1f0".to_string()),
+        anonymize: None,
+    };
+    assert_eq!(req.validate().unwrap(), None);
+}
+
+#[test]
+fn test_accept_invite_request_requires_exactly_one_reference() {
+    let neither = AcceptRoomInviteRequest {
+        invite_id: None,
+        token: None,
+        anonymize: None,
+    };
+    assert!(neither.validate().is_err());
+
+    let both = AcceptRoomInviteRequest {
+        invite_id: Some(Uuid::new_v4().to_string()),
+        token: Some("9f1c2d3e4b5a69788796a5b4c3d2e1f0".to_string()),
+        anonymize: Some(true),
+    };
+    assert!(both.validate().is_err());
+}
+
+// --- CreateRoomFromTemplateRequest Tests ---
+
+#[test]
+fn test_create_room_from_template_request_pinned_version() {
+    let version_id = Uuid::new_v4();
+    let req = CreateRoomFromTemplateRequest {
+        template_id: Uuid::new_v4().to_string(),
+        template_version_id: Some(version_id.to_string()),
+        name: None,
+        anonymize: None,
+    };
+    assert_eq!(req.validate().unwrap().1, Some(version_id));
+}
+
+#[test]
+fn test_create_room_from_template_request_invalid_fields() {
+    let req = CreateRoomFromTemplateRequest {
+        template_id: "not-a-uuid".to_string(),
+        template_version_id: Some("not-a-uuid".to_string()),
+        name: Some("   ".to_string()),
+        anonymize: Some(false),
+    };
+    assert_eq!(req.validate().err().unwrap().len(), 3);
+}
+
+// --- GetAttachmentDownloadQuery Tests ---
+
+#[test]
+fn test_get_attachment_download_query() {
+    let account_id = Uuid::new_v4();
+    let valid = GetAttachmentDownloadQuery {
+        account: account_id.to_string(),
+        expires: 1_767_225_600_000,
+        signature: "ab".repeat(32),
+    };
+    assert_eq!(valid.validate().unwrap(), account_id);
+
+    let invalid = GetAttachmentDownloadQuery {
+        account: "not-a-uuid".to_string(),
+        expires: 1_767_225_600_000,
+        signature: "xyz".to_string(),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 2);
+}
+
+// --- SearchMessagesQuery Tests ---
+
+#[test]
+fn test_search_messages_query() {
+    let room_id = Uuid::new_v4();
+    let valid = SearchMessagesQuery {
+        room_id: Some(room_id.to_string()),
+        q: "  deploy fri ".to_string(),
+        limit: None,
+        offset: None,
+    };
+    assert_eq!(
+        valid.validate().unwrap(),
+        (Some(room_id), "deploy fri".to_string(), 20, 0)
+    );
+
+    let invalid = SearchMessagesQuery {
+        room_id: Some("not-a-uuid".to_string()),
+        q: "   ".to_string(),
+        limit: Some(51),
+        offset: Some(1001),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 4);
+}
+
+// --- CreateRoomExportRequest Tests ---
+
+#[test]
+fn test_create_room_export_request() {
+    let room_id = Uuid::new_v4();
+    let valid = CreateRoomExportRequest {
+        room_id: room_id.to_string(),
+        format: "Markdown".to_string(),
+    };
+    assert_eq!(
+        valid.validate().unwrap(),
+        (
+            room_id,
+            crate::entities::room::export::ExportFormat::Markdown
+        )
+    );
+
+    let invalid = CreateRoomExportRequest {
+        room_id: "nope".to_string(),
+        format: "pdf".to_string(),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 2);
+}
+
+// --- ImportRoomRequest Tests ---
+
+#[test]
+fn test_import_room_request() {
+    let valid = ImportRoomRequest {
+        format: Some("open_ai".to_string()),
+        name: None,
+        own_author: Some("User".to_string()),
+        dry_run: Some(true),
+        archive: serde_json::json!([{"role": "user", "content": "Hi"}]),
+    };
+    assert_eq!(
+        valid.validate().unwrap(),
+        Some(crate::export::import::ImportFormat::OpenAi)
+    );
+
+    let invalid = ImportRoomRequest {
+        format: Some("slack".to_string()),
+        name: Some(" ".to_string()),
+        own_author: None,
+        dry_run: None,
+        archive: serde_json::json!("not an archive"),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 3);
+}
+
+// --- ReportMessageRequest Tests ---
+
+#[test]
+fn test_report_message_request() {
+    let room_id = Uuid::new_v4();
+    let message_id = Uuid::new_v4();
+    let valid = ReportMessageRequest {
+        room_id: room_id.to_string(),
+        message_id: message_id.to_string(),
+        reason: "Spam".to_string(),
+        details: Some("  ".to_string()),
+    };
+    assert_eq!(
+        valid.validate().unwrap(),
+        (
+            room_id,
+            message_id,
+            crate::entities::room::report::ReportReason::Spam,
+            None
+        )
+    );
+
+    // content filter reports can't be raised by members
+    let invalid = ReportMessageRequest {
+        room_id: "nope".to_string(),
+        message_id: message_id.to_string(),
+        reason: "content_filter".to_string(),
+        details: Some("x".repeat(1001)),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 3);
+}
+
+// --- ModerateMessageRequest Tests ---
+
+#[test]
+fn test_moderate_message_request() {
+    let room_id = Uuid::new_v4();
+    let message_id = Uuid::new_v4();
+    let valid = ModerateMessageRequest {
+        room_id: room_id.to_string(),
+        message_id: message_id.to_string(),
+        action: "unhide".to_string(),
+    };
+    assert_eq!(
+        valid.validate().unwrap(),
+        (
+            room_id,
+            message_id,
+            crate::entities::room::report::ModerationAction::Unhide
+        )
+    );
+
+    let invalid = ModerateMessageRequest {
+        room_id: room_id.to_string(),
+        message_id: "nope".to_string(),
+        action: "ban".to_string(),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 2);
+}
+
+// --- GetReviewQueueQuery Tests ---
+
+#[test]
+fn test_get_review_queue_query() {
+    let valid = GetReviewQueueQuery {
+        room_id: None,
+        limit: None,
+        offset: Some(40),
+    };
+    assert_eq!(valid.validate().unwrap(), (None, 20, 40));
+
+    let invalid = GetReviewQueueQuery {
+        room_id: Some("nope".to_string()),
+        limit: Some(0),
+        offset: None,
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 2);
+}
+
+// --- UpdateRoomSettingsRequest Tests ---
+
+#[test]
+fn test_update_room_settings_request() {
+    let room_id = Uuid::new_v4();
+    let valid = UpdateRoomSettingsRequest {
+        room_id: room_id.to_string(),
+        slow_mode_seconds: Some(30),
+        max_message_length: Some(0),
+        allow_replies: None,
+        allow_attachments: Some(false),
+        announcement_mode: None,
+        pin_role: Some("Member".to_string()),
+        max_pinned_messages: Some(10),
+    };
+    let (validated_room_id, schema) = valid.validate().unwrap();
+    assert_eq!(validated_room_id, room_id);
+    assert_eq!(schema.slow_mode_interval_ms, Some(30_000));
+    assert_eq!(schema.max_message_length, Some(0));
+    assert_eq!(schema.allow_replies, None);
+    assert_eq!(schema.allow_attachments, Some(false));
+    assert_eq!(
+        schema.pin_role,
+        Some(crate::entities::room::member::MemberRole::Member)
+    );
+    assert_eq!(schema.max_pinned_messages, Some(10));
+
+    let invalid = UpdateRoomSettingsRequest {
+        room_id: "nope".to_string(),
+        slow_mode_seconds: Some(-1),
+        max_message_length: Some(20_001),
+        allow_replies: None,
+        allow_attachments: None,
+        announcement_mode: None,
+        pin_role: Some("read_only".to_string()),
+        max_pinned_messages: Some(0),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 5);
+}
+
+// --- Ticket Request Tests ---
+
+#[test]
+fn test_ticket_requests() {
+    use crate::entities::room::ticket::{TicketPriority, TicketStatus};
+
+    let room_id = Uuid::new_v4();
+    let status = SetTicketStatusRequest {
+        room_id: room_id.to_string(),
+        status: "Resolved".to_string(),
+    };
+    assert_eq!(
+        status.validate().unwrap(),
+        (room_id, TicketStatus::Resolved)
+    );
+
+    let priority = SetTicketPriorityRequest {
+        room_id: "nope".to_string(),
+        priority: "critical".to_string(),
+    };
+    assert_eq!(priority.validate().err().unwrap().len(), 2);
+    let priority = SetTicketPriorityRequest {
+        room_id: room_id.to_string(),
+        priority: "urgent".to_string(),
+    };
+    assert_eq!(
+        priority.validate().unwrap(),
+        (room_id, TicketPriority::Urgent)
+    );
+
+    let unassign = AssignTicketRequest {
+        room_id: room_id.to_string(),
+        assignee_id: None,
+    };
+    assert_eq!(unassign.validate().unwrap(), (room_id, None));
+    let invalid = AssignTicketRequest {
+        room_id: room_id.to_string(),
+        assignee_id: Some("nope".to_string()),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 1);
+}
+
+// --- GetTicketQueueQuery Tests ---
+
+#[test]
+fn test_get_ticket_queue_query() {
+    use crate::entities::room::ticket::{TicketPriority, TicketStatus};
+    use crate::entities::services::ticket::AssigneeFilter;
+
+    let valid = GetTicketQueueQuery {
+        status: Some("open, resolved".to_string()),
+        priority: Some("high".to_string()),
+        assignee: Some("Unassigned".to_string()),
+        breached: Some(true),
+        limit: None,
+        offset: None,
+    };
+    let (filter, limit, offset) = valid.validate().unwrap();
+    assert_eq!(
+        filter.statuses,
+        vec![TicketStatus::Open, TicketStatus::Resolved]
+    );
+    assert_eq!(filter.priority, Some(TicketPriority::High));
+    assert_eq!(filter.assignee, Some(AssigneeFilter::Unassigned));
+    assert!(filter.breached);
+    assert_eq!((limit, offset), (20, 0));
+
+    let invalid = GetTicketQueueQuery {
+        status: Some("open,waiting".to_string()),
+        priority: Some("meh".to_string()),
+        assignee: Some("someone".to_string()),
+        breached: None,
+        limit: Some(101),
+        offset: None,
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 4);
+}
+
+// --- ScheduleMessageRequest Tests ---
+
+#[test]
+fn test_schedule_message_request() {
+    let room_id = Uuid::new_v4();
+    let reply_to = Uuid::new_v4();
+    let valid = ScheduleMessageRequest {
+        room_id: room_id.to_string(),
+        content: Some("Happy new year!".to_string()),
+        attachment_id: None,
+        reply_to: Some(reply_to.to_string()),
+        scheduled_for: 1767225600000,
+        ttl_seconds: Some(3600),
+    };
+    assert_eq!(valid.validate().unwrap(), (room_id, None, Some(reply_to)));
+
+    let invalid = ScheduleMessageRequest {
+        room_id: room_id.to_string(),
+        content: Some("  ".to_string()),
+        attachment_id: None,
+        reply_to: Some("nope".to_string()),
+        scheduled_for: 0,
+        ttl_seconds: Some(0),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 4);
+}
+
+// --- OpenDirectRoomRequest Tests ---
+
+#[test]
+fn test_open_direct_room_request() {
+    let account_id = Uuid::new_v4();
+    let valid = OpenDirectRoomRequest {
+        account_id: account_id.to_string(),
+    };
+    assert_eq!(valid.validate().unwrap(), account_id);
+
+    let invalid = OpenDirectRoomRequest {
+        account_id: "nope".to_string(),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 1);
+}
+
+// --- BulkMessagesRequest Tests ---
+
+#[test]
+fn test_bulk_messages_request() {
+    use crate::entities::services::room::BulkMessageAction;
+
+    let room_id = Uuid::new_v4();
+    let member_id = Uuid::new_v4();
+    let valid = BulkMessagesRequest {
+        room_id: room_id.to_string(),
+        action: "Purge".to_string(),
+        message_ids: None,
+        member_id: Some(member_id.to_string()),
+        from: Some(1),
+        to: Some(2),
+        dry_run: Some(true),
+    };
+    let (parsed_room_id, action, filter) = valid.validate().unwrap();
+    assert_eq!(parsed_room_id, room_id);
+    assert_eq!(action, BulkMessageAction::Purge);
+    assert_eq!(filter.member_id, Some(member_id));
+    assert_eq!((filter.from, filter.to), (Some(1), Some(2)));
+
+    let no_filter = BulkMessagesRequest {
+        message_ids: None,
+        member_id: None,
+        from: None,
+        to: None,
+        ..valid.clone()
+    };
+    assert_eq!(no_filter.validate().err().unwrap().len(), 1);
+
+    let invalid = BulkMessagesRequest {
+        action: "archive".to_string(),
+        message_ids: Some(vec!["nope".to_string()]),
+        from: Some(2),
+        to: Some(1),
+        ..valid
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 3);
+}
+
+// --- ListRoomsQuery Tests ---
+
+#[test]
+fn test_list_rooms_query() {
+    use crate::entities::services::room::RoomActivityCursor;
+
+    let cursor = RoomActivityCursor {
+        last_activity_at: 1735689600000,
+        room_id: Uuid::new_v4(),
+    };
+    let valid = ListRoomsQuery {
+        limit: None,
+        cursor: Some(cursor.encode()),
+    };
+    assert_eq!(valid.validate().unwrap(), (20, Some(cursor)));
+
+    let invalid = ListRoomsQuery {
+        limit: Some(0),
+        cursor: Some("yesterday".to_string()),
+    };
+    assert_eq!(invalid.validate().err().unwrap().len(), 2);
+}
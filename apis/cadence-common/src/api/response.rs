@@ -79,6 +79,9 @@ pub enum APIResponseObjectType {
     Email,
     ExternalIdentity,
     Energy,
+    Room,
+    Member,
+    Message,
     Unknown,
     Auth,
     None,
@@ -0,0 +1,6 @@
+// Response DTOs shared by the services, built from the entities so they only expose what
+// the caller is allowed to see.
+
+pub mod room;
+#[cfg(test)]
+pub mod tests;
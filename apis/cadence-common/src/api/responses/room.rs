@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::entities::room::identity::MemberIdentity;
+use crate::entities::room::member::{MemberRole, Model as MemberModel};
+use crate::entities::room::message::MessageType;
+use crate::entities::services::room::{MessagePage, MessageThread, ReactionSummary, RoomMessage};
+use crate::types::{ID, Timestamp};
+
+/// A member of a room, as seen by the caller. `account_id` is only set when the member
+/// is not anonymized, or when the privacy policy lets the caller see it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MemberResponse {
+    #[schema(example = json!(uuid::Uuid::new_v4()))]
+    pub id: String,
+    #[schema(example = "Quiet Otter 42")]
+    pub display_name: String,
+    #[schema(example = "data:image/svg+xml,...", nullable = true)]
+    pub avatar_url: Option<String>,
+    pub anonymous: bool,
+    #[schema(example = json!(uuid::Uuid::new_v4()), nullable = true)]
+    pub account_id: Option<String>,
+    #[schema(value_type = String, example = "member")]
+    pub role: MemberRole,
+    pub is_owner: bool,
+    #[schema(value_type = i64, example = 1)]
+    pub created_at: Timestamp,
+}
+
+impl MemberResponse {
+    pub fn new(member: &MemberModel, identity: &MemberIdentity) -> Self {
+        MemberResponse {
+            id: member.id.to_string(),
+            display_name: identity.display_name.clone(),
+            avatar_url: identity.avatar_url.clone(),
+            anonymous: identity.anonymous,
+            account_id: identity.account_id.map(|id| id.to_string()),
+            role: member.role.clone(),
+            is_owner: member.is_owner,
+            created_at: member.created_at,
+        }
+    }
+}
+
+/// Author of a message. It never carries the account, see `MemberResponse`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuthorResponse {
+    #[schema(example = json!(uuid::Uuid::new_v4()))]
+    pub member_id: String,
+    #[schema(example = "Quiet Otter 42")]
+    pub display_name: String,
+    #[schema(example = "data:image/svg+xml,...", nullable = true)]
+    pub avatar_url: Option<String>,
+    pub anonymous: bool,
+}
+
+impl From<&MemberIdentity> for AuthorResponse {
+    fn from(identity: &MemberIdentity) -> Self {
+        AuthorResponse {
+            member_id: identity.member_id.to_string(),
+            display_name: identity.display_name.clone(),
+            avatar_url: identity.avatar_url.clone(),
+            anonymous: identity.anonymous,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReactionResponse {
+    #[schema(example = "👍")]
+    pub emoji: String,
+    pub count: i64,
+    pub reacted: bool,
+}
+
+impl From<&ReactionSummary> for ReactionResponse {
+    fn from(reaction: &ReactionSummary) -> Self {
+        ReactionResponse {
+            emoji: reaction.emoji.clone(),
+            count: reaction.count,
+            reacted: reaction.reacted,
+        }
+    }
+}
+
+/// A message of a room. `author` is `None` for messages of the model and for system
+/// messages that are not about a member. `pinned_by` is the member who pinned it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MessageResponse {
+    #[schema(example = json!(uuid::Uuid::new_v4()))]
+    pub id: String,
+    #[schema(example = json!(uuid::Uuid::new_v4()))]
+    pub room_id: String,
+    #[schema(nullable = true)]
+    pub author: Option<AuthorResponse>,
+    pub system: bool,
+    #[schema(example = "gpt-4o", nullable = true)]
+    pub model_tag: Option<String>,
+    #[schema(nullable = true)]
+    pub content: Option<String>,
+    #[schema(example = json!(uuid::Uuid::new_v4()), nullable = true)]
+    pub attachment_id: Option<String>,
+    #[schema(example = json!(uuid::Uuid::new_v4()), nullable = true)]
+    pub reply_to: Option<String>,
+    #[schema(value_type = String, example = "Default")]
+    pub message_type: MessageType,
+    #[schema(value_type = Option<i64>, nullable = true)]
+    pub pinned_at: Option<Timestamp>,
+    #[schema(nullable = true)]
+    pub pinned_by: Option<AuthorResponse>,
+    pub reactions: Vec<ReactionResponse>,
+    pub reply_count: i64,
+    pub tombstone: bool,
+    #[schema(value_type = i64, example = 1)]
+    pub created_at: Timestamp,
+    #[schema(value_type = i64, example = 1)]
+    pub updated_at: Timestamp,
+}
+
+impl MessageResponse {
+    /// Builds the response with the identities returned by
+    /// `RoomService::get_member_identities`.
+    pub fn new(message: &RoomMessage, identities: &HashMap<ID, MemberIdentity>) -> Self {
+        let model = &message.message;
+        MessageResponse {
+            id: model.id.to_string(),
+            room_id: model.room_id.to_string(),
+            author: model
+                .member_id
+                .and_then(|id| identities.get(&id))
+                .map(AuthorResponse::from),
+            system: model.system,
+            model_tag: model.model_tag.clone(),
+            content: model.content.clone(),
+            attachment_id: model.attachment_id.map(|id| id.to_string()),
+            reply_to: model.reply_to.map(|id| id.to_string()),
+            message_type: model.message_type.clone(),
+            pinned_at: model.pinned_at,
+            pinned_by: model
+                .pinned_by
+                .and_then(|id| identities.get(&id))
+                .map(AuthorResponse::from),
+            reactions: message
+                .reactions
+                .iter()
+                .map(ReactionResponse::from)
+                .collect(),
+            reply_count: message.reply_count,
+            tombstone: message.tombstone,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+
+    /// Builds the responses of a page returned by `RoomService::get_messages` or
+    /// `RoomService::get_pinned_messages`.
+    pub fn page(page: &MessagePage) -> Vec<Self> {
+        page.messages
+            .iter()
+            .map(|message| MessageResponse::new(message, &page.identities))
+            .collect()
+    }
+}
+
+/// A reply inside a thread, with its depth relative to the root message (direct replies
+/// have a depth of 1).
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ThreadMessageResponse {
+    #[serde(flatten)]
+    pub message: MessageResponse,
+    #[schema(example = 1)]
+    pub depth: i32,
+}
+
+/// A root message and a page of its (possibly nested) replies, oldest first.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ThreadResponse {
+    pub root: MessageResponse,
+    pub replies: Vec<ThreadMessageResponse>,
+}
+
+impl From<&MessageThread> for ThreadResponse {
+    fn from(thread: &MessageThread) -> Self {
+        ThreadResponse {
+            root: MessageResponse::new(&thread.root, &thread.identities),
+            replies: thread
+                .replies
+                .iter()
+                .map(|reply| ThreadMessageResponse {
+                    message: MessageResponse::new(&reply.message, &thread.identities),
+                    depth: reply.depth,
+                })
+                .collect(),
+        }
+    }
+}
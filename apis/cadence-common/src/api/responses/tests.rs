@@ -0,0 +1,99 @@
+#![cfg(test)] // Ensure this file is only compiled for tests
+
+use std::collections::HashMap;
+
+use super::room::{MemberResponse, MessageResponse};
+use crate::entities::room::identity::{IdentityGenerator, PrivacyPolicy};
+use crate::entities::room::member::{MemberRole, Model as MemberModel};
+use crate::entities::room::message::{MessageType, Model as MessageModel};
+use crate::entities::services::room::RoomMessage;
+
+fn member(room_id: uuid::Uuid, anonymize: bool) -> MemberModel {
+    MemberModel {
+        id: uuid::Uuid::new_v4(),
+        room_id,
+        account_id: Some(uuid::Uuid::new_v4()),
+        role: MemberRole::Member,
+        is_owner: false,
+        anonymize,
+        last_read_message_id: None,
+        last_read_at: None,
+        banned_at: None,
+        ban_reason: None,
+        ban_expires_at: None,
+        deleted_at: None,
+        created_at: 1,
+        updated_at: 1,
+    }
+}
+
+// --- Room Response Tests ---
+
+#[test]
+fn test_responses_never_leak_anonymized_accounts() {
+    let room_id = uuid::Uuid::new_v4();
+    let anonymous = member(room_id, true);
+    let viewer = member(room_id, false);
+    let generator = IdentityGenerator::default();
+
+    let identity = generator.member_identity(
+        &anonymous,
+        Some("Alice"),
+        Some(&viewer),
+        PrivacyPolicy::Strict,
+    );
+    let response = MemberResponse::new(&anonymous, &identity);
+    assert!(response.anonymous);
+    assert_eq!(response.account_id, None);
+    assert_ne!(response.display_name, "Alice");
+
+    let message = RoomMessage {
+        message: MessageModel {
+            id: uuid::Uuid::new_v4(),
+            room_id,
+            member_id: Some(anonymous.id),
+            system: false,
+            model_tag: None,
+            content: Some("Hello".to_string()),
+            attachment_id: None,
+            reply_to: None,
+            message_type: MessageType::Default,
+            is_hidden: false,
+            pinned_at: None,
+            pinned_by: None,
+            summarized_until: None,
+            expires_at: None,
+            deleted_at: None,
+            created_at: 1,
+            updated_at: 1,
+        },
+        reactions: Vec::new(),
+        reply_count: 0,
+        tombstone: false,
+    };
+    let identities = HashMap::from([(anonymous.id, identity.clone())]);
+    let json = serde_json::to_string(&MessageResponse::new(&message, &identities)).unwrap();
+    assert!(json.contains(&identity.display_name));
+    assert!(!json.contains(&anonymous.account_id.unwrap().to_string()));
+    assert!(!json.contains("Alice"));
+}
+
+#[test]
+fn test_member_response_shows_named_members() {
+    let room_id = uuid::Uuid::new_v4();
+    let named = member(room_id, false);
+    let identity = IdentityGenerator::default().member_identity(
+        &named,
+        Some("Bob"),
+        None,
+        PrivacyPolicy::Strict,
+    );
+
+    let response = MemberResponse::new(&named, &identity);
+    assert_eq!(response.display_name, "Bob");
+    assert_eq!(
+        response.account_id,
+        named.account_id.map(|id| id.to_string())
+    );
+    assert_eq!(response.avatar_url, None);
+}
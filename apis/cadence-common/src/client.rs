@@ -0,0 +1,84 @@
+//!
+//! Minimal HTTP/1.1 client shared by the model providers and the storage backends.
+//!
+//! Every request opens its own connection, over TLS when a client config is given.
+//!
+
+use std::sync::Arc;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, Response, Uri};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tracing::{debug, warn};
+
+use crate::api::service::certs::load_certs;
+
+/// Builds a TLS client config trusting the root certificates of a PEM bundle.
+pub(crate) fn load_tls_config(ca_bundle_path: &str) -> Result<Arc<rustls::ClientConfig>, String> {
+    let bundle = std::fs::read(ca_bundle_path)
+        .map_err(|e| format!("failed to read CA bundle {}: {}", ca_bundle_path, e))?;
+    let mut roots = rustls::RootCertStore::empty();
+    let certs = load_certs(&bundle).map_err(|e| format!("invalid CA bundle: {}", e))?;
+    let (added, ignored) = roots.add_parsable_certificates(certs);
+    debug!("Loaded {} root certificates ({} ignored)", added, ignored);
+
+    let tls_config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(tls_config))
+}
+
+/// Sends a request to the host of `uri`, which must be absolute. The request itself only
+/// carries the path, its `Host` header is set by the caller.
+pub(crate) async fn send_request(
+    tls_config: Option<&Arc<rustls::ClientConfig>>,
+    uri: &Uri,
+    request: Request<Full<Bytes>>,
+) -> Result<Response<Incoming>, String> {
+    let host = uri.host().ok_or("url has no host")?.to_string();
+    let port = uri
+        .port_u16()
+        .unwrap_or(if tls_config.is_some() { 443 } else { 80 });
+
+    let stream = TcpStream::connect((host.as_str(), port))
+        .await
+        .map_err(|e| format!("failed to connect: {}", e))?;
+
+    match tls_config {
+        Some(tls_config) => {
+            let connector = tokio_rustls::TlsConnector::from(tls_config.clone());
+            let server_name = rustls::pki_types::ServerName::try_from(host)
+                .map_err(|e| format!("invalid host: {}", e))?;
+            let stream = connector
+                .connect(server_name, stream)
+                .await
+                .map_err(|e| format!("TLS handshake failed: {}", e))?;
+            exchange(stream, request).await
+        }
+        None => exchange(stream, request).await,
+    }
+}
+
+/// Sends a single request over an established connection.
+async fn exchange<T>(io: T, request: Request<Full<Bytes>>) -> Result<Response<Incoming>, String>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io))
+        .await
+        .map_err(|e| format!("HTTP handshake failed: {}", e))?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            warn!("Connection closed with error: {}", e);
+        }
+    });
+
+    sender
+        .send_request(request)
+        .await
+        .map_err(|e| format!("request failed: {}", e))
+}
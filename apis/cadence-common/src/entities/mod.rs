@@ -3,6 +3,7 @@ pub mod services;
 pub mod country;
 pub mod room;
 pub mod tag;
+pub mod usage;
 pub mod util;
 //#[cfg(test)]
 //pub mod tests;
\ No newline at end of file
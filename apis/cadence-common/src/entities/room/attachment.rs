@@ -0,0 +1,103 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use crate::types::{ID, Timestamp};
+
+/// # Attachment
+///
+/// The `attachment` table stores the metadata of the files uploaded to a room. The files
+/// themselves are kept by the storage backend under `storage_key`.
+///
+/// An attachment is uploaded first, then bound to the message that references it through
+/// `message_id`. It can only be bound once.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "attachment")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", indexed)]
+    pub room_id: ID,
+    /// # Member ID
+    ///
+    /// The member who uploaded the attachment, `None` for attachments cloned from a
+    /// template.
+    #[sea_orm(column_type = "Uuid", column_name = "member_id", nullable)]
+    pub member_id: Option<ID>,
+    #[sea_orm(column_type = "Uuid", column_name = "message_id", indexed, nullable)]
+    pub message_id: Option<ID>,
+
+    #[sea_orm(column_type = "Text", column_name = "file_name")]
+    pub file_name: String,
+    #[sea_orm(column_type = "Text", column_name = "content_type")]
+    pub content_type: String,
+    #[sea_orm(column_type = "BigInteger", column_name = "size_bytes")]
+    pub size_bytes: i64,
+    /// # Checksum
+    ///
+    /// Hex encoded SHA-256 of the content.
+    #[sea_orm(column_type = "Text", column_name = "checksum")]
+    pub checksum: String,
+
+    #[serde(skip_serializing)]
+    #[sea_orm(column_type = "Text", column_name = "storage_key")]
+    pub storage_key: String,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
+    pub deleted_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+    Membership,
+    Message,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Membership => Entity::belongs_to(crate::entities::room::member::Entity)
+                .from(Column::MemberId)
+                .to(crate::entities::room::member::Column::Id)
+                .into(),
+            Self::Message => Entity::belongs_to(crate::entities::room::message::Entity)
+                .from(Column::MessageId)
+                .to(crate::entities::room::message::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl Related<crate::entities::room::member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Membership.def()
+    }
+}
+
+impl Related<crate::entities::room::message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
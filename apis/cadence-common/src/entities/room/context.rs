@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::room::message::{MessageType, Model as MessageModel};
+use crate::llm::{ChatMessage, ChatRole};
+use crate::types::ID;
+
+/// # Tokenizer
+///
+/// Counts the tokens of a text for the model of a room. Counts only need to be close
+/// enough to stay under the context window of the model.
+pub trait Tokenizer: Send + Sync + Debug {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// # Whitespace Tokenizer
+///
+/// One token per whitespace separated word. Deterministic, meant for tests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+}
+
+/// # Char Ratio Tokenizer
+///
+/// Estimates one token every `chars_per_token` characters, which is close to what BPE
+/// tokenizers produce for English text.
+#[derive(Debug, Clone, Copy)]
+pub struct CharRatioTokenizer {
+    pub chars_per_token: usize,
+}
+
+impl Default for CharRatioTokenizer {
+    fn default() -> Self {
+        CharRatioTokenizer { chars_per_token: 4 }
+    }
+}
+
+impl Tokenizer for CharRatioTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(self.chars_per_token.max(1))
+    }
+}
+
+/// # Truncation Strategy
+///
+/// - `RecentOnly`: only the pinned messages and the `keep_recent` most recent messages are
+///   sent.
+/// - `FillBudget`: same as `RecentOnly`, then older messages are added, newest first, while
+///   they fit in the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    RecentOnly,
+    FillBudget,
+}
+
+/// # Context Window Config
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ContextWindowConfig {
+    /// Maximum number of tokens of the context, without the reply.
+    pub max_tokens: usize,
+    /// Number of most recent messages always sent, budget permitting.
+    pub keep_recent: usize,
+    /// Whether pinned messages are always sent, budget permitting.
+    pub keep_pinned: bool,
+    pub strategy: TruncationStrategy,
+    /// Tokens added to every message for its role and separators.
+    pub message_overhead_tokens: usize,
+    /// Number of messages left out of the context that triggers a new rolling summary.
+    pub summarize_threshold: usize,
+}
+
+impl Default for ContextWindowConfig {
+    fn default() -> Self {
+        ContextWindowConfig {
+            max_tokens: 8192,
+            keep_recent: 20,
+            keep_pinned: true,
+            strategy: TruncationStrategy::FillBudget,
+            message_overhead_tokens: 4,
+            summarize_threshold: 20,
+        }
+    }
+}
+
+/// # Context Window
+///
+/// The messages sent to the model, and which messages of the history were left out.
+#[derive(Debug, Clone, Default)]
+pub struct ContextWindow {
+    pub messages: Vec<ChatMessage>,
+    pub prompt_tokens: usize,
+    /// Messages of the history that made it into the context.
+    pub included: Vec<ID>,
+    /// Messages of the history left out of the context, oldest first.
+    pub omitted: Vec<ID>,
+}
+
+/// # Context Builder
+///
+/// Builds the context sent to the model of a room: the system prompt, the rolling summary
+/// and as much of the history as the configuration and the token budget allow.
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    pub tokenizer: Arc<dyn Tokenizer>,
+    pub config: ContextWindowConfig,
+}
+
+impl Default for ContextBuilder {
+    fn default() -> Self {
+        ContextBuilder {
+            tokenizer: Arc::new(CharRatioTokenizer::default()),
+            config: ContextWindowConfig::default(),
+        }
+    }
+}
+
+/// Role of a message in the context of the model, `None` when the message is not part of
+/// the conversation (system notices, hidden, deleted or empty messages).
+///
+/// Messages without an author that carry a `model_tag` were generated by a model.
+pub fn chat_role(message: &MessageModel) -> Option<ChatRole> {
+    if message.message_type != MessageType::Default
+        || message.system
+        || message.is_hidden
+        || message.deleted_at.is_some()
+        || message.content.as_ref().is_none_or(|c| c.is_empty())
+    {
+        return None;
+    }
+
+    if message.member_id.is_none() && message.model_tag.is_some() {
+        Some(ChatRole::Assistant)
+    } else {
+        Some(ChatRole::User)
+    }
+}
+
+impl ContextBuilder {
+    pub fn new(tokenizer: Arc<dyn Tokenizer>, config: ContextWindowConfig) -> Self {
+        ContextBuilder { tokenizer, config }
+    }
+
+    /// Tokens taken by a message of the context, overhead included.
+    pub fn message_tokens(&self, content: &str) -> usize {
+        self.tokenizer.count_tokens(content) + self.config.message_overhead_tokens
+    }
+
+    /// Builds the context window.
+    ///
+    /// `history` is the conversation after the rolling summary, oldest first, and `pinned`
+    /// the pinned messages of the room, which may be older than the summary. The system
+    /// prompt and the summary are always sent. When the kept messages do not fit in the
+    /// budget, the oldest non pinned ones are dropped first, then the oldest pinned ones;
+    /// the most recent message is always sent.
+    pub fn build(
+        &self,
+        system_prompt: Option<&str>,
+        summary: Option<&str>,
+        pinned: &[MessageModel],
+        history: &[MessageModel],
+    ) -> ContextWindow {
+        let mut window = ContextWindow::default();
+        let mut used = 0;
+
+        if let Some(prompt) = system_prompt.filter(|p| !p.trim().is_empty()) {
+            used += self.message_tokens(prompt);
+            window
+                .messages
+                .push(ChatMessage::new(ChatRole::System, prompt));
+        }
+        if let Some(summary) = summary.filter(|s| !s.trim().is_empty()) {
+            let content = format!("Summary of the earlier conversation:\n{}", summary);
+            used += self.message_tokens(&content);
+            window
+                .messages
+                .push(ChatMessage::new(ChatRole::System, content));
+        }
+
+        let history: Vec<&MessageModel> =
+            history.iter().filter(|m| chat_role(m).is_some()).collect();
+        let mut seen = HashSet::new();
+        let pinned: Vec<&MessageModel> = if self.config.keep_pinned {
+            pinned
+                .iter()
+                .filter(|m| m.pinned_at.is_some() && chat_role(m).is_some() && seen.insert(m.id))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let pinned_ids: HashSet<ID> = pinned.iter().map(|m| m.id).collect();
+
+        // selected messages, as (message, tokens), oldest first
+        let recent_start = history.len().saturating_sub(self.config.keep_recent);
+        let mut selected: Vec<(&MessageModel, usize)> = pinned
+            .iter()
+            .copied()
+            .chain(
+                history[recent_start..]
+                    .iter()
+                    .copied()
+                    .filter(|m| !pinned_ids.contains(&m.id)),
+            )
+            .map(|m| {
+                (
+                    m,
+                    self.message_tokens(m.content.as_deref().unwrap_or_default()),
+                )
+            })
+            .collect();
+        sort_chronologically(&mut selected);
+
+        let last_id = history.last().map(|m| m.id);
+        let kept = selected.len();
+        let mut total = used + selected.iter().map(|(_, t)| t).sum::<usize>();
+        while total > self.config.max_tokens && selected.len() > 1 {
+            let position = selected
+                .iter()
+                .position(|(m, _)| !pinned_ids.contains(&m.id) && Some(m.id) != last_id)
+                .or_else(|| selected.iter().position(|(m, _)| Some(m.id) != last_id));
+            let Some(position) = position else {
+                break;
+            };
+            total -= selected.remove(position).1;
+        }
+
+        // older messages are only added while contiguous to the recent ones, so the model
+        // never sees a conversation with holes in it
+        if self.config.strategy == TruncationStrategy::FillBudget && selected.len() == kept {
+            for message in history[..recent_start].iter().rev() {
+                if pinned_ids.contains(&message.id) {
+                    continue;
+                }
+                let tokens = self.message_tokens(message.content.as_deref().unwrap_or_default());
+                if total + tokens > self.config.max_tokens {
+                    break;
+                }
+                total += tokens;
+                selected.push((message, tokens));
+            }
+            sort_chronologically(&mut selected);
+        }
+
+        let included: HashSet<ID> = selected.iter().map(|(m, _)| m.id).collect();
+        for (message, _) in &selected {
+            if let (Some(role), Some(content)) = (chat_role(message), &message.content) {
+                window
+                    .messages
+                    .push(ChatMessage::new(role, content.clone()));
+            }
+        }
+
+        window.prompt_tokens = total;
+        window.included = selected.iter().map(|(m, _)| m.id).collect();
+        window.omitted = history
+            .iter()
+            .filter(|m| !included.contains(&m.id))
+            .map(|m| m.id)
+            .collect();
+        window
+    }
+}
+
+fn sort_chronologically(messages: &mut [(&MessageModel, usize)]) {
+    messages.sort_by_key(|(m, _)| (m.created_at, m.id));
+}
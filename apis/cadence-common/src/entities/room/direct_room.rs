@@ -0,0 +1,87 @@
+use sea_orm::SqlErr;
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+/// # Direct Room
+///
+/// The `direct_room` table maps a pair of accounts to their `Direct` room. The pair is
+/// stored ordered, see [`pair`], so the composite primary key guarantees a single room per
+/// pair of accounts whatever the order they are given in.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(table_name = "direct_room")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "first_account_id"
+    )]
+    pub first_account_id: ID,
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "second_account_id",
+        indexed
+    )]
+    pub second_account_id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", unique)]
+    pub room_id: ID,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+}
+
+/// Orders two account IDs the way they are stored.
+pub fn pair(account_id: ID, other_account_id: ID) -> (ID, ID) {
+    if account_id <= other_account_id {
+        (account_id, other_account_id)
+    } else {
+        (other_account_id, account_id)
+    }
+}
+
+/// Whether a failed insert of a pair is the unique violation of the pair key, meaning
+/// another call created the room of the pair first.
+pub fn is_pair_conflict(err: &SqlErr) -> bool {
+    matches!(err, SqlErr::UniqueConstraintViolation(message) if message.contains("direct_room_pkey"))
+}
+
+impl Model {
+    /// The account of the pair that is not `account_id`.
+    pub fn other_account_id(&self, account_id: ID) -> ID {
+        if self.first_account_id == account_id {
+            self.second_account_id
+        } else {
+            self.first_account_id
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
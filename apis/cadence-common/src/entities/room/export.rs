@@ -0,0 +1,157 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ExportFormat {
+    #[default]
+    #[sea_orm(string_value = "json")]
+    Json,
+    #[sea_orm(string_value = "markdown")]
+    Markdown,
+    /// A single page with its styles inlined, readable offline.
+    #[sea_orm(string_value = "html")]
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// - `Pending`: waiting for a worker.
+/// - `Running`: being written by a worker.
+/// - `Completed`: the result can be downloaded until `expires_at`.
+/// - `Failed`: see `error`.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ExportStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "running")]
+    Running,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+/// # Room Export
+///
+/// The `room_export` table tracks the exports of the conversation of a room, requested by
+/// a member and written in the background. The result is kept by the storage backend under
+/// `storage_key` and only downloadable by the account that requested it.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "room_export")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", indexed)]
+    pub room_id: ID,
+    #[sea_orm(column_type = "Uuid", column_name = "account_id", indexed)]
+    pub account_id: ID,
+
+    #[sea_orm(column_type = "Text", column_name = "format")]
+    pub format: ExportFormat,
+    #[sea_orm(column_type = "Text", column_name = "status")]
+    pub status: ExportStatus,
+    #[sea_orm(column_type = "Text", column_name = "error", nullable)]
+    pub error: Option<String>,
+
+    #[sea_orm(column_type = "Text", column_name = "file_name", nullable)]
+    pub file_name: Option<String>,
+    #[sea_orm(column_type = "BigInteger", column_name = "size_bytes", nullable)]
+    pub size_bytes: Option<i64>,
+    #[sea_orm(column_type = "BigInteger", column_name = "message_count", nullable)]
+    pub message_count: Option<i64>,
+
+    #[serde(skip_serializing)]
+    #[sea_orm(column_type = "Text", column_name = "storage_key", nullable)]
+    pub storage_key: Option<String>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "completed_at", nullable)]
+    pub completed_at: Option<Timestamp>,
+    /// # Expires At
+    ///
+    /// When the result is removed from the storage, see `ExportService::purge_expired_exports`.
+    #[sea_orm(column_type = "BigInteger", column_name = "expires_at", nullable)]
+    pub expires_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
+    pub deleted_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+    Account,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Account => Entity::belongs_to(crate::entities::account::account::Entity)
+                .from(Column::AccountId)
+                .to(crate::entities::account::account::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl Related<crate::entities::account::account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Account.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
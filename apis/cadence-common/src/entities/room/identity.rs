@@ -0,0 +1,213 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::entities::room::member::{MemberRole, Model as MemberModel};
+use crate::types::ID;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const ADJECTIVES: [&str; 32] = [
+    "Amber", "Brave", "Bright", "Calm", "Clever", "Cosmic", "Curious", "Daring", "Gentle",
+    "Golden", "Happy", "Hidden", "Humble", "Jolly", "Kind", "Lively", "Lucky", "Mellow", "Misty",
+    "Nimble", "Noble", "Quiet", "Rapid", "Rustic", "Silent", "Silver", "Sunny", "Swift", "Tidy",
+    "Vivid", "Wise", "Witty",
+];
+
+const ANIMALS: [&str; 32] = [
+    "Badger", "Beaver", "Bison", "Crane", "Dolphin", "Falcon", "Ferret", "Finch", "Fox", "Gecko",
+    "Heron", "Ibis", "Jaguar", "Koala", "Lemur", "Lynx", "Marten", "Moose", "Newt", "Otter", "Owl",
+    "Panda", "Puffin", "Quokka", "Raven", "Robin", "Seal", "Sparrow", "Tapir", "Turtle", "Walrus",
+    "Wombat",
+];
+
+const AVATAR_COLORS: [&str; 16] = [
+    "#e57373", "#f06292", "#ba68c8", "#9575cd", "#7986cb", "#64b5f6", "#4fc3f7", "#4dd0e1",
+    "#4db6ac", "#81c784", "#aed581", "#dce775", "#ffd54f", "#ffb74d", "#ff8a65", "#a1887f",
+];
+
+/// Display name of members whose account has no name.
+pub const UNNAMED_MEMBER: &str = "Unknown member";
+
+/// # Anonymous Identity
+///
+/// Pseudonym of an anonymized member, e.g. "Quiet Otter 42", with an identicon avatar
+/// as an SVG data URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AnonymousIdentity {
+    pub handle: String,
+    pub avatar_color: String,
+    pub avatar_url: String,
+}
+
+/// # Identity Generator
+///
+/// Derives the anonymous identity of a member from an HMAC of its membership, so it is
+/// stable for the whole membership, differs from room to room, and cannot be traced back
+/// to the account without the key. The default key is fixed: deployments should set
+/// their own, and keep it, since changing it renames every anonymized member.
+#[derive(Clone)]
+pub struct IdentityGenerator {
+    key: Vec<u8>,
+}
+
+impl std::fmt::Debug for IdentityGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentityGenerator").finish_non_exhaustive()
+    }
+}
+
+impl Default for IdentityGenerator {
+    fn default() -> Self {
+        IdentityGenerator::new(b"cadence-anonymous-identity".to_vec())
+    }
+}
+
+impl IdentityGenerator {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        IdentityGenerator { key: key.into() }
+    }
+
+    fn digest(&self, room_id: ID, member_id: ID) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(room_id.as_bytes());
+        mac.update(member_id.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// The anonymous identity of a membership.
+    pub fn anonymous_identity(&self, room_id: ID, member_id: ID) -> AnonymousIdentity {
+        let digest = self.digest(room_id, member_id);
+
+        let handle = format!(
+            "{} {} {}",
+            ADJECTIVES[digest[0] as usize % ADJECTIVES.len()],
+            ANIMALS[digest[1] as usize % ANIMALS.len()],
+            u16::from_be_bytes([digest[2], digest[3]]) % 100
+        );
+        let avatar_color = AVATAR_COLORS[digest[4] as usize % AVATAR_COLORS.len()].to_string();
+        let avatar_url = identicon_data_url(&digest[5..], &avatar_color);
+
+        AnonymousIdentity {
+            handle,
+            avatar_color,
+            avatar_url,
+        }
+    }
+
+    /// How `member` appears to `viewer`, the membership of the account looking at the room
+    /// (`None` for outsiders, e.g. exports). `account_name` is the name of the account of
+    /// the member.
+    pub fn member_identity(
+        &self,
+        member: &MemberModel,
+        account_name: Option<&str>,
+        viewer: Option<&MemberModel>,
+        policy: PrivacyPolicy,
+    ) -> MemberIdentity {
+        let account_id = policy
+            .reveals_account(member, viewer)
+            .then_some(member.account_id)
+            .flatten();
+
+        if member.anonymize {
+            let identity = self.anonymous_identity(member.room_id, member.id);
+            return MemberIdentity {
+                member_id: member.id,
+                display_name: identity.handle,
+                avatar_url: Some(identity.avatar_url),
+                anonymous: true,
+                account_id,
+            };
+        }
+
+        MemberIdentity {
+            member_id: member.id,
+            display_name: account_name.unwrap_or(UNNAMED_MEMBER).to_string(),
+            avatar_url: None,
+            anonymous: false,
+            account_id,
+        }
+    }
+}
+
+/// 5x5 horizontally symmetric identicon.
+fn identicon_data_url(bits: &[u8], color: &str) -> String {
+    let mut cells = String::new();
+    for row in 0..5 {
+        for column in 0..3 {
+            let bit = row * 3 + column;
+            if bits[bit / 8] & (1 << (bit % 8)) == 0 {
+                continue;
+            }
+            // the middle column is its own mirror
+            let mirrored = 4 - column;
+            for x in [column, mirrored]
+                .iter()
+                .take(if column == mirrored { 1 } else { 2 })
+            {
+                cells.push_str(&format!(
+                    "<rect width='1' height='1' x='{}' y='{}'/>",
+                    x, row
+                ));
+            }
+        }
+    }
+
+    let svg = format!(
+        "<svg xmlns='http://www.w3.org/2000/svg' viewBox='-1 -1 7 7'>\
+         <rect x='-1' y='-1' width='7' height='7' fill='#f5f5f5'/><g fill='{}'>{}</g></svg>",
+        color, cells
+    );
+    format!(
+        "data:image/svg+xml,{}",
+        svg.replace('%', "%25")
+            .replace('#', "%23")
+            .replace('<', "%3C")
+            .replace('>', "%3E")
+    )
+}
+
+/// # Privacy Policy
+///
+/// Who can see the account behind an anonymized member. Members that are not anonymized
+/// are shown with their account to everyone.
+///
+/// - `Strict`: only the member itself.
+/// - `OwnersSeeAccounts`: the owners of the room too, e.g. to answer abuse reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyPolicy {
+    #[default]
+    Strict,
+    OwnersSeeAccounts,
+}
+
+impl PrivacyPolicy {
+    /// Whether `viewer` may see the account of `member`.
+    pub fn reveals_account(&self, member: &MemberModel, viewer: Option<&MemberModel>) -> bool {
+        if !member.anonymize {
+            return true;
+        }
+        match viewer {
+            Some(viewer) if viewer.id == member.id => true,
+            Some(viewer) => {
+                *self == PrivacyPolicy::OwnersSeeAccounts && viewer.role == MemberRole::Owner
+            }
+            None => false,
+        }
+    }
+}
+
+/// # Member Identity
+///
+/// How a member appears to a given viewer: its handle and avatar when anonymized, and its
+/// account only when the privacy policy lets the viewer see it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemberIdentity {
+    pub member_id: ID,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    pub anonymous: bool,
+    pub account_id: Option<ID>,
+}
@@ -0,0 +1,117 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use crate::time::now_millis;
+use crate::types::{ID, Timestamp};
+
+/// # Room Invite
+///
+/// The `room_invite` table stores invitations to join a room. An invite is either
+/// direct, addressed to a single account through `invitee_account_id`, or a shareable
+/// link identified by its `token` that any account can redeem until it runs out of uses.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "room_invite")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", indexed)]
+    pub room_id: ID,
+    #[sea_orm(column_type = "Uuid", column_name = "inviter_account_id")]
+    pub inviter_account_id: ID,
+
+    /// # Invitee
+    ///
+    /// Set for direct invites, `NULL` for invite links.
+    #[sea_orm(
+        column_type = "Uuid",
+        column_name = "invitee_account_id",
+        indexed,
+        nullable
+    )]
+    pub invitee_account_id: Option<ID>,
+
+    /// # Token
+    ///
+    /// Secret part of the shareable link, `NULL` for direct invites.
+    #[sea_orm(column_type = "Text", column_name = "token", unique, nullable)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    /// # Uses
+    ///
+    /// How many times the invite has been accepted. The invite can't be accepted anymore
+    /// once `uses` reaches `max_uses`. A `NULL` `max_uses` means unlimited.
+    #[sea_orm(column_type = "Integer", column_name = "uses")]
+    pub uses: i32,
+    #[sea_orm(column_type = "Integer", column_name = "max_uses", nullable)]
+    pub max_uses: Option<i32>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "expires_at", nullable)]
+    pub expires_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "revoked_at", nullable)]
+    pub revoked_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "declined_at", nullable)]
+    pub declined_at: Option<Timestamp>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
+    pub deleted_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+    Invitee,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Invitee => Entity::belongs_to(crate::entities::account::account::Entity)
+                .from(Column::InviteeAccountId)
+                .to(crate::entities::account::account::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl Related<crate::entities::account::account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Invitee.def()
+    }
+}
+
+impl Model {
+    /// Whether the invite can still be accepted: not revoked, declined, deleted, expired
+    /// nor exhausted.
+    pub fn is_redeemable(&self) -> bool {
+        self.revoked_at.is_none()
+            && self.declined_at.is_none()
+            && self.deleted_at.is_none()
+            && self
+                .expires_at
+                .is_none_or(|expires_at| expires_at > now_millis())
+            && self.max_uses.is_none_or(|max_uses| self.uses < max_uses)
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,11 +1,46 @@
+use sea_orm::ActiveValue::Set;
 use sea_orm::entity::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
+use crate::time::now_millis;
 use crate::types::{ID, Timestamp};
 
-/// # Account
+/// # Member Role
 ///
-/// The `account` table stores information about user accounts.
+/// Role of a member inside a room. What each role is allowed to do is defined by the
+/// permission matrix in [`crate::entities::room::permission`]. Roles are ordered by
+/// [`MemberRole::rank`], so `Owner` is the greatest.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum MemberRole {
+    #[sea_orm(string_value = "owner")]
+    Owner,
+    #[sea_orm(string_value = "admin")]
+    Admin,
+    #[sea_orm(string_value = "moderator")]
+    Moderator,
+    #[sea_orm(string_value = "member")]
+    Member,
+    #[sea_orm(string_value = "read_only")]
+    ReadOnly,
+}
+
+impl Ord for MemberRole {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for MemberRole {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// # Member
+///
+/// The `member` table stores the membership of accounts in rooms.
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "member")]
 pub struct Model {
@@ -25,19 +60,44 @@ pub struct Model {
         indexed
     )]
     pub room_id: ID,
+    /// The account holding the membership. Placeholders created by imports for the authors
+    /// of an archive have none, so they are never mistaken for a member.
     #[sea_orm(
         auto_increment = false,
         column_type = "Uuid",
         column_name = "account_id",
-        indexed
+        indexed,
+        nullable
     )]
-    pub account_id: ID,
+    pub account_id: Option<ID>,
 
+    /// # Role
+    ///
+    /// `is_owner` is kept in sync with `role` for readers that only care about ownership.
+    #[sea_orm(column_type = "Text", column_name = "role")]
+    pub role: MemberRole,
     pub is_owner: bool,
     pub anonymize: bool,
 
+    /// # Read Marker
+    ///
+    /// The last message the member has read in the room, and the creation time of that
+    /// message. Messages created after `last_read_at` are considered unread.
+    #[sea_orm(column_type = "Uuid", column_name = "last_read_message_id", nullable)]
+    pub last_read_message_id: Option<ID>,
+    #[sea_orm(column_type = "BigInteger", column_name = "last_read_at", nullable)]
+    pub last_read_at: Option<Timestamp>,
+
+    /// # Ban
+    ///
+    /// When `banned_at` is set the member can't interact with the room until the ban is lifted
+    /// or `ban_expires_at` is reached. A ban without expiry is permanent.
     #[sea_orm(column_type = "BigInteger", column_name = "banned_at", nullable)]
     pub banned_at: Option<Timestamp>,
+    #[sea_orm(column_type = "Text", column_name = "ban_reason", nullable)]
+    pub ban_reason: Option<String>,
+    #[sea_orm(column_type = "BigInteger", column_name = "ban_expires_at", nullable)]
+    pub ban_expires_at: Option<Timestamp>,
 
     #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
     pub deleted_at: Option<Timestamp>,
@@ -91,4 +151,65 @@ impl Related<crate::entities::room::message::Entity> for Entity {
     }
 }
 
+impl Model {
+    /// Whether the member is currently banned, taking the ban expiry into account.
+    pub fn is_banned(&self) -> bool {
+        match (self.banned_at, self.ban_expires_at) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(_), Some(expires_at)) => expires_at > now_millis(),
+        }
+    }
+}
+
+/// Placeholder member standing for an author of an imported archive in `room_id`. It has
+/// no account and is created already removed from the room, so it never grants any access.
+pub fn placeholder(room_id: ID, now: Timestamp) -> ActiveModel {
+    ActiveModel {
+        id: Set(uuid::Uuid::new_v4()),
+        room_id: Set(room_id),
+        account_id: Set(None),
+        role: Set(MemberRole::Member),
+        is_owner: Set(false),
+        anonymize: Set(true),
+        deleted_at: Set(Some(now)),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+}
+
+/// Checks whether `trigger` can ban `target`: owners can't be banned, a member can only
+/// ban members with a lower role, and a ban can't be stacked on an active one. Without
+/// `trigger` the ban is issued by the system, which outranks everyone.
+pub fn check_can_ban(trigger: Option<&Model>, target: &Model) -> Result<(), String> {
+    if target.role == MemberRole::Owner {
+        return Err("cannot ban owner".to_string());
+    }
+    if trigger.is_some_and(|trigger| !trigger.role.outranks(&target.role)) {
+        return Err("cannot ban a member with an equal or higher role".to_string());
+    }
+    if target.is_banned() {
+        return Err("member is already banned".to_string());
+    }
+    Ok(())
+}
+
+/// Latest of the memberships of an account in a room, left ones included.
+pub fn latest_membership(memberships: &[Model]) -> Option<&Model> {
+    memberships.iter().max_by_key(|m| m.created_at)
+}
+
+/// Checks whether an account can join a room, given all of its memberships in the room,
+/// left ones included, so leaving doesn't lift a ban.
+pub fn check_can_join(memberships: &[Model]) -> Result<(), String> {
+    if memberships.iter().any(|m| m.is_banned()) {
+        return Err("member is banned".to_string());
+    }
+    if memberships.iter().any(|m| m.deleted_at.is_none()) {
+        return Err("account is already a member".to_string());
+    }
+    Ok(())
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -14,6 +14,29 @@ pub enum MessageType {
     RecipientAdded,
     #[sea_orm(string_value = "recipient_removed")]
     RecipientRemoved,
+    #[sea_orm(string_value = "recipient_banned")]
+    RecipientBanned,
+    #[sea_orm(string_value = "recipient_unbanned")]
+    RecipientUnbanned,
+    #[sea_orm(string_value = "recipient_left")]
+    RecipientLeft,
+    /// Rolling summary of the older messages of a model room. Always hidden, it is only
+    /// sent to the model as context.
+    #[sea_orm(string_value = "summary")]
+    Summary,
+    /// Change of the ticket of a support room (status, priority or assignee), described by
+    /// the content.
+    #[sea_orm(string_value = "ticket_updated")]
+    TicketUpdated,
+    /// Summary of a bulk operation on the messages of the room, see
+    /// `RoomService::bulk_update_messages`.
+    #[sea_orm(string_value = "messages_moderated")]
+    MessagesModerated,
+    /// A message was pinned or unpinned, the content naming it.
+    #[sea_orm(string_value = "message_pinned")]
+    MessagePinned,
+    #[sea_orm(string_value = "message_unpinned")]
+    MessageUnpinned,
 }
 
 /// # Message
@@ -49,8 +72,11 @@ pub struct Model {
     #[sea_orm(column_type = "Text", column_name = "content", nullable)]
     pub content: Option<String>,
 
-    #[sea_orm(column_type = "Text", column_name = "attachment", nullable)]
-    pub attachment: Option<String>,
+    /// # Attachment ID
+    ///
+    /// The uploaded attachment of the message, see `attachment::Model`.
+    #[sea_orm(column_type = "Uuid", column_name = "attachment_id", nullable)]
+    pub attachment_id: Option<ID>,
 
     #[sea_orm(column_type = "Uuid", column_name = "reply_to_id", nullable)]
     pub reply_to: Option<ID>,
@@ -62,6 +88,28 @@ pub struct Model {
 
     #[sea_orm(column_type = "BigInteger", column_name = "pinned_at", nullable)]
     pub pinned_at: Option<Timestamp>,
+    /// The membership of the member who pinned the message.
+    #[sea_orm(column_type = "Uuid", column_name = "pinned_by", nullable)]
+    pub pinned_by: Option<ID>,
+
+    /// # Summarized Until
+    ///
+    /// For `Summary` messages, the creation time of the most recent message folded into
+    /// the summary. Later messages are still sent to the model as they are.
+    #[sea_orm(column_type = "BigInteger", column_name = "summarized_until", nullable)]
+    pub summarized_until: Option<Timestamp>,
+
+    /// # Expires At
+    ///
+    /// When the message self-destructs. Expired messages are soft-deleted by the message
+    /// worker of the room service, see `RoomService::expire_messages`.
+    #[sea_orm(
+        column_type = "BigInteger",
+        column_name = "expires_at",
+        nullable,
+        indexed
+    )]
+    pub expires_at: Option<Timestamp>,
 
     #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
     pub deleted_at: Option<Timestamp>,
@@ -0,0 +1,79 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+/// # Message Reaction
+///
+/// The `message_reaction` table stores the reactions (emoji or shortcode) that members
+/// leave on messages. The composite primary key guarantees one reaction per emoji per member.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(table_name = "message_reaction")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "message_id"
+    )]
+    pub message_id: ID,
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "member_id"
+    )]
+    pub member_id: ID,
+
+    /// # Emoji
+    ///
+    /// Either a unicode emoji (e.g. `👍`) or a shortcode (e.g. `:thumbs_up:`).
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Text",
+        column_name = "emoji"
+    )]
+    pub emoji: String,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Message,
+    Member,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Message => Entity::belongs_to(crate::entities::room::message::Entity)
+                .from(Column::MessageId)
+                .to(crate::entities::room::message::Column::Id)
+                .into(),
+            Self::Member => Entity::belongs_to(crate::entities::room::member::Entity)
+                .from(Column::MemberId)
+                .to(crate::entities::room::member::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
+impl Related<crate::entities::room::member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Member.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
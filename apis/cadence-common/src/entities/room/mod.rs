@@ -2,4 +2,21 @@ pub mod room;
 pub mod member;
 pub mod message;
 pub mod template;
-pub mod repositories;
\ No newline at end of file
+pub mod template_version;
+pub mod template_seed;
+pub mod message_reaction;
+pub mod attachment;
+pub mod export;
+pub mod invite;
+pub mod permission;
+pub mod context;
+pub mod identity;
+pub mod report;
+pub mod search;
+pub mod settings;
+pub mod ticket;
+pub mod scheduled_message;
+pub mod direct_room;
+pub mod repositories;
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::room::member::MemberRole;
+
+/// # Permission
+///
+/// Actions inside a room that are restricted by the role of the member performing them.
+/// See [`MemberRole::can`] for the permission matrix. Pinning messages is not part of it,
+/// the lowest role allowed to pin being set per room by the `pin_role` setting.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    PostMessage,
+    HideMessage,
+    /// Delete messages written by other members. Authors can always delete their own messages.
+    DeleteMessage,
+    AddMember,
+    RemoveMember,
+    BanMember,
+    ManageRoles,
+    EditRoom,
+    DeleteRoom,
+    ManageTemplates,
+    TransferOwnership,
+}
+
+impl MemberRole {
+    /// # Permission Matrix
+    ///
+    /// | Permission        | Owner | Admin | Moderator | Member | ReadOnly |
+    /// |-------------------|:-----:|:-----:|:---------:|:------:|:--------:|
+    /// | PostMessage       |   x   |   x   |     x     |   x    |          |
+    /// | HideMessage       |   x   |   x   |     x     |        |          |
+    /// | DeleteMessage     |   x   |   x   |     x     |        |          |
+    /// | AddMember         |   x   |   x   |           |        |          |
+    /// | RemoveMember      |   x   |   x   |     x     |        |          |
+    /// | BanMember         |   x   |   x   |     x     |        |          |
+    /// | ManageRoles       |   x   |   x   |           |        |          |
+    /// | EditRoom          |   x   |   x   |           |        |          |
+    /// | ManageTemplates   |   x   |   x   |           |        |          |
+    /// | DeleteRoom        |   x   |       |           |        |          |
+    /// | TransferOwnership |   x   |       |           |        |          |
+    pub fn can(&self, permission: Permission) -> bool {
+        match self {
+            MemberRole::Owner => true,
+            MemberRole::Admin => !matches!(
+                permission,
+                Permission::DeleteRoom | Permission::TransferOwnership
+            ),
+            MemberRole::Moderator => matches!(
+                permission,
+                Permission::PostMessage
+                    | Permission::HideMessage
+                    | Permission::DeleteMessage
+                    | Permission::RemoveMember
+                    | Permission::BanMember
+            ),
+            MemberRole::Member => matches!(permission, Permission::PostMessage),
+            MemberRole::ReadOnly => false,
+        }
+    }
+
+    /// Position of the role in the room hierarchy, higher is more privileged.
+    pub fn rank(&self) -> u8 {
+        match self {
+            MemberRole::Owner => 4,
+            MemberRole::Admin => 3,
+            MemberRole::Moderator => 2,
+            MemberRole::Member => 1,
+            MemberRole::ReadOnly => 0,
+        }
+    }
+
+    /// Whether a member with this role can act upon (remove, ban, change the role of)
+    /// a member with the `other` role.
+    pub fn outranks(&self, other: &MemberRole) -> bool {
+        self.rank() > other.rank()
+    }
+}
@@ -0,0 +1,177 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ReportReason {
+    #[sea_orm(string_value = "spam")]
+    Spam,
+    #[sea_orm(string_value = "harassment")]
+    Harassment,
+    #[sea_orm(string_value = "hate_speech")]
+    HateSpeech,
+    #[sea_orm(string_value = "inappropriate")]
+    Inappropriate,
+    #[default]
+    #[sea_orm(string_value = "other")]
+    Other,
+    /// Raised by the content filters of the room service when they hide a message, `details`
+    /// holding the verdict.
+    #[sea_orm(string_value = "content_filter")]
+    ContentFilter,
+}
+
+/// - `Open`: waiting in the review queue.
+/// - `Resolved`: a moderator acted upon the message, see `action`.
+/// - `Dismissed`: a moderator left the message as it is.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ReportStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "resolved")]
+    Resolved,
+    #[sea_orm(string_value = "dismissed")]
+    Dismissed,
+}
+
+/// Decision of a moderator on a reported message.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ModerationAction {
+    #[sea_orm(string_value = "hide")]
+    Hide,
+    #[sea_orm(string_value = "unhide")]
+    Unhide,
+    #[sea_orm(string_value = "delete")]
+    Delete,
+    #[default]
+    #[sea_orm(string_value = "dismiss")]
+    Dismiss,
+}
+
+/// # Message Report
+///
+/// The `message_report` table stores the reports of messages, raised by members or by the
+/// content filters, until a moderator reviews them.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "message_report")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", indexed)]
+    pub room_id: ID,
+    #[sea_orm(column_type = "Uuid", column_name = "message_id", indexed)]
+    pub message_id: ID,
+    /// # Reporter ID
+    ///
+    /// The membership of the member who reported the message, `None` for content filters.
+    #[sea_orm(column_type = "Uuid", column_name = "reporter_id", nullable)]
+    pub reporter_id: Option<ID>,
+
+    #[sea_orm(column_type = "Text", column_name = "reason")]
+    pub reason: ReportReason,
+    #[sea_orm(column_type = "Text", column_name = "details", nullable)]
+    pub details: Option<String>,
+
+    #[sea_orm(column_type = "Text", column_name = "status", indexed)]
+    pub status: ReportStatus,
+    #[sea_orm(column_type = "Text", column_name = "action", nullable)]
+    pub action: Option<ModerationAction>,
+    /// # Resolved By
+    ///
+    /// The account of the moderator who reviewed the report. Global admins may review
+    /// reports of rooms they are not a member of, hence an account rather than a membership.
+    #[sea_orm(column_type = "Uuid", column_name = "resolved_by", nullable)]
+    pub resolved_by: Option<ID>,
+    #[sea_orm(column_type = "BigInteger", column_name = "resolved_at", nullable)]
+    pub resolved_at: Option<Timestamp>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+    Message,
+    Reporter,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Message => Entity::belongs_to(crate::entities::room::message::Entity)
+                .from(Column::MessageId)
+                .to(crate::entities::room::message::Column::Id)
+                .into(),
+            Self::Reporter => Entity::belongs_to(crate::entities::room::member::Entity)
+                .from(Column::ReporterId)
+                .to(crate::entities::room::member::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl Related<crate::entities::room::message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
+impl Related<crate::entities::room::member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reporter.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,72 @@
+use crate::entities::room::invite::ActiveModel;
+use crate::entities::room::invite::Column;
+use crate::entities::room::invite::Entity;
+use crate::entities::room::invite::Model;
+use crate::entities::room::invite::PrimaryKey;
+use crate::repository_traits::CrudEntityRepository;
+use crate::time::now_millis;
+use crate::types::Timestamp;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// # Room Invite Repository
+///
+/// This struct provides a repository for managing room invites.
+#[derive(Clone, Debug)]
+pub struct RoomInviteRepository {
+    pub db: sea_orm::DatabaseConnection,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreationSchema {
+    pub room_id: uuid::Uuid,
+    pub inviter_account_id: uuid::Uuid,
+    pub invitee_account_id: Option<uuid::Uuid>,
+    pub token: Option<String>,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<Timestamp>,
+}
+
+#[async_trait::async_trait]
+impl CrudEntityRepository<Model, Entity, ActiveModel, Column, PrimaryKey> for RoomInviteRepository {
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+    type CreationSchema = CreationSchema;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        RoomInviteRepository { db }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+
+    fn deleted_at_column(&self) -> Column {
+        Column::DeletedAt
+    }
+
+    fn updated_at_column(&self) -> Column {
+        Column::UpdatedAt
+    }
+
+    fn primary_key_column(&self) -> Column {
+        Column::Id
+    }
+
+    fn schema_to_active_model(&self, schema: CreationSchema) -> ActiveModel {
+        ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            room_id: Set(schema.room_id),
+            inviter_account_id: Set(schema.inviter_account_id),
+            invitee_account_id: Set(schema.invitee_account_id),
+            token: Set(schema.token),
+            uses: Set(0),
+            max_uses: Set(schema.max_uses),
+            expires_at: Set(schema.expires_at),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+            ..Default::default()
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::entities::room::member::ActiveModel;
 use crate::entities::room::member::Column;
 use crate::entities::room::member::Entity;
+use crate::entities::room::member::MemberRole;
 use crate::entities::room::member::Model;
 use crate::entities::room::member::PrimaryKey;
 use crate::repository_traits::CrudEntityRepository;
@@ -22,7 +23,7 @@ pub struct MemberRepository {
 pub struct CreationSchema {
     pub room_id: uuid::Uuid,
     pub account_id: uuid::Uuid,
-    pub is_owner: bool,
+    pub role: MemberRole,
     pub anonymize: bool,
 }
 
@@ -55,8 +56,9 @@ impl CrudEntityRepository<Model, Entity, ActiveModel, Column, PrimaryKey> for Me
         ActiveModel {
             id: Set(uuid::Uuid::new_v4()),
             room_id: Set(schema.room_id),
-            account_id: Set(schema.account_id),
-            is_owner: Set(schema.is_owner),
+            account_id: Set(Some(schema.account_id)),
+            is_owner: Set(schema.role == MemberRole::Owner),
+            role: Set(schema.role),
             anonymize: Set(schema.anonymize),
             created_at: Set(now_millis()),
             updated_at: Set(now_millis()),
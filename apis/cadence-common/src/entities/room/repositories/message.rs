@@ -6,6 +6,7 @@ use crate::entities::room::message::Model;
 use crate::entities::room::message::PrimaryKey;
 use crate::repository_traits::CrudEntityRepository;
 use crate::time::now_millis;
+use crate::types::Timestamp;
 use sea_orm::ActiveValue::Set;
 use sea_orm::prelude::*;
 use serde::Deserialize;
@@ -26,10 +27,12 @@ pub struct CreationSchema {
     pub system: bool,
     pub model_tag: Option<String>,
     pub content: Option<String>,
-    pub attachment: Option<String>,
+    pub attachment_id: Option<uuid::Uuid>,
     pub reply_to: Option<uuid::Uuid>,
     pub message_type: MessageType,
     pub is_hidden: bool,
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
 }
 
 #[async_trait::async_trait]
@@ -65,10 +68,11 @@ impl CrudEntityRepository<Model, Entity, ActiveModel, Column, PrimaryKey> for Me
             system: Set(schema.system),
             model_tag: Set(schema.model_tag),
             content: Set(schema.content),
-            attachment: Set(schema.attachment),
+            attachment_id: Set(schema.attachment_id),
             reply_to: Set(schema.reply_to),
             message_type: Set(schema.message_type),
             is_hidden: Set(schema.is_hidden),
+            expires_at: Set(schema.expires_at),
             created_at: Set(now_millis()),
             updated_at: Set(now_millis()),
             ..Default::default()
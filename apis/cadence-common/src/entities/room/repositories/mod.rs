@@ -1,4 +1,6 @@
 pub mod room;
 pub mod member;
 pub mod template;
-pub mod message;
\ No newline at end of file
+pub mod template_version;
+pub mod message;
+pub mod invite;
\ No newline at end of file
@@ -28,7 +28,9 @@ pub struct CreationSchema {
     pub background_url: Option<String>,
     pub visibility: RoomVisibility,
     pub template_id: Option<uuid::Uuid>,
+    pub template_version_id: Option<uuid::Uuid>,
     pub model_tag: Option<String>,
+    pub system_prompt: Option<String>,
     pub room_type: RoomType,
 }
 
@@ -67,7 +69,9 @@ impl CrudEntityRepository<Model, Entity, ActiveModel, Column, PrimaryKey> for Ro
             room_type: Set(schema.room_type),
             visibility: Set(schema.visibility),
             template_id: Set(schema.template_id),
+            template_version_id: Set(schema.template_version_id),
             model_tag: Set(schema.model_tag),
+            system_prompt: Set(schema.system_prompt),
             created_at: Set(now_millis()),
             updated_at: Set(now_millis()),
             ..Default::default()
@@ -3,6 +3,7 @@ use crate::entities::room::template::Column;
 use crate::entities::room::template::Entity;
 use crate::entities::room::template::Model;
 use crate::entities::room::template::PrimaryKey;
+use crate::entities::room::template::TemplateVisibility;
 use crate::repository_traits::CrudEntityRepository;
 use crate::time::now_millis;
 use sea_orm::ActiveValue::Set;
@@ -26,6 +27,7 @@ pub struct CreationSchema {
     pub name: Option<String>,
     pub description: Option<String>,
     pub system_prompt: Option<String>,
+    pub visibility: TemplateVisibility,
 }
 
 #[async_trait::async_trait]
@@ -62,6 +64,10 @@ impl CrudEntityRepository<Model, Entity, ActiveModel, Column, PrimaryKey> for Ro
             author_id: Set(schema.author_id),
             model_tag: Set(schema.model_tag),
             system_prompt: Set(schema.system_prompt),
+            visibility: Set(schema.visibility),
+            latest_version: Set(0),
+            usage_count: Set(0),
+            fork_count: Set(0),
             created_at: Set(now_millis()),
             updated_at: Set(now_millis()),
             ..Default::default()
@@ -0,0 +1,75 @@
+use crate::entities::room::template_version::ActiveModel;
+use crate::entities::room::template_version::Column;
+use crate::entities::room::template_version::Entity;
+use crate::entities::room::template_version::Model;
+use crate::entities::room::template_version::PrimaryKey;
+use crate::repository_traits::CrudEntityRepository;
+use crate::time::now_millis;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// # Room Template Version Repository
+///
+/// This struct provides a repository for managing the published versions of room templates.
+#[derive(Clone, Debug)]
+pub struct RoomTemplateVersionRepository {
+    pub db: sea_orm::DatabaseConnection,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreationSchema {
+    pub template_id: uuid::Uuid,
+    pub version: i32,
+    pub published_by: Option<uuid::Uuid>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model_tag: String,
+    pub system_prompt: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl CrudEntityRepository<Model, Entity, ActiveModel, Column, PrimaryKey>
+    for RoomTemplateVersionRepository
+{
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+    type CreationSchema = CreationSchema;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        RoomTemplateVersionRepository { db }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+
+    fn deleted_at_column(&self) -> Column {
+        Column::DeletedAt
+    }
+
+    fn updated_at_column(&self) -> Column {
+        Column::UpdatedAt
+    }
+
+    fn primary_key_column(&self) -> Column {
+        Column::Id
+    }
+
+    fn schema_to_active_model(&self, schema: CreationSchema) -> ActiveModel {
+        ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            template_id: Set(schema.template_id),
+            version: Set(schema.version),
+            published_by: Set(schema.published_by),
+            name: Set(schema.name),
+            description: Set(schema.description),
+            model_tag: Set(schema.model_tag),
+            system_prompt: Set(schema.system_prompt),
+            usage_count: Set(0),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+            ..Default::default()
+        }
+    }
+}
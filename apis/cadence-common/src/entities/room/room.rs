@@ -14,6 +14,9 @@ pub enum RoomType {
     Group,
     #[sea_orm(string_value = "support")]
     Support,
+    /// One-to-one conversation between two accounts, see `RoomService::get_or_create_direct_room`.
+    #[sea_orm(string_value = "direct")]
+    Direct,
 }
 
 #[derive(
@@ -57,6 +60,19 @@ pub struct Model {
     #[sea_orm(column_type = "Uuid", column_name = "template_id", indexed, nullable)]
     pub template_id: Option<ID>,
 
+    /// # Template Version ID
+    ///
+    /// The published version of the template the room was created from. Rooms stay pinned
+    /// to it even when newer versions of the template are published.
+    #[sea_orm(column_type = "Uuid", column_name = "template_version_id", nullable)]
+    pub template_version_id: Option<ID>,
+
+    /// # System Prompt
+    ///
+    /// Instructions given to the model of the room, usually copied from the template.
+    #[sea_orm(column_type = "Text", column_name = "system_prompt", nullable)]
+    pub system_prompt: Option<String>,
+
     /// # Name
     ///
     /// The `name` field stores the name of the room.
@@ -76,6 +92,18 @@ pub struct Model {
     #[sea_orm(column_type = "Text", column_name = "visibility")]
     pub visibility: RoomVisibility,
 
+    /// # Last Message At
+    ///
+    /// Creation time of the latest visible message of the room, kept up to date by the
+    /// room service as messages are posted so rooms can be sorted by activity.
+    #[sea_orm(
+        column_type = "BigInteger",
+        column_name = "last_message_at",
+        nullable,
+        indexed
+    )]
+    pub last_message_at: Option<Timestamp>,
+
     #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
     pub deleted_at: Option<Timestamp>,
     #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
@@ -0,0 +1,128 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+/// Furthest a message can be scheduled, 1 year.
+pub const MAX_SCHEDULE_AHEAD_MS: i64 = 365 * 24 * 60 * 60 * 1000;
+
+/// Longest lifetime of a self-destructing message, 30 days.
+pub const MAX_MESSAGE_TTL_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// - `Pending`: waiting for `scheduled_for`.
+/// - `Delivered`: posted as `message_id`.
+/// - `Cancelled`: withdrawn by its author.
+/// - `Failed`: could not be posted, see `error`. The author may have left the room, or
+///   the message broke its settings or content filters by the time it was due.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ScheduledMessageStatus {
+    #[default]
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "delivered")]
+    Delivered,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+/// # Scheduled Message
+///
+/// The `scheduled_message` table queues the messages of members to be posted at
+/// `scheduled_for` by the message worker of the room service. The status only leaves
+/// `Pending` in the transaction that posts the message, so a message is never delivered
+/// twice, even across restarts.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "scheduled_message")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", indexed)]
+    pub room_id: ID,
+    /// The membership of the author at the time the message was scheduled.
+    #[sea_orm(column_type = "Uuid", column_name = "member_id", indexed)]
+    pub member_id: ID,
+
+    #[sea_orm(column_type = "Text", column_name = "content", nullable)]
+    pub content: Option<String>,
+    #[sea_orm(column_type = "Uuid", column_name = "attachment_id", nullable)]
+    pub attachment_id: Option<ID>,
+    #[sea_orm(column_type = "Uuid", column_name = "reply_to_id", nullable)]
+    pub reply_to: Option<ID>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "scheduled_for", indexed)]
+    pub scheduled_for: Timestamp,
+    /// # TTL
+    ///
+    /// Lifetime of the posted message, its `expires_at` being set from the delivery time.
+    #[sea_orm(column_type = "BigInteger", column_name = "ttl_ms", nullable)]
+    pub ttl_ms: Option<i64>,
+
+    #[sea_orm(column_type = "Text", column_name = "status", indexed)]
+    pub status: ScheduledMessageStatus,
+    #[sea_orm(column_type = "Uuid", column_name = "message_id", nullable)]
+    pub message_id: Option<ID>,
+    #[sea_orm(column_type = "Text", column_name = "error", nullable)]
+    pub error: Option<String>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+    Member,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Member => Entity::belongs_to(crate::entities::room::member::Entity)
+                .from(Column::MemberId)
+                .to(crate::entities::room::member::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl Related<crate::entities::room::member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Member.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
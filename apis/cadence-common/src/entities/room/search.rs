@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::room::message::Model as MessageModel;
+use crate::entities::room::template::Model as RoomTemplateModel;
+
+/// Maximum number of terms of a search query, the rest is ignored.
+pub const MAX_SEARCH_TERMS: usize = 8;
+
+/// Markers wrapped around the matches in search snippets. Snippets are plain text, the
+/// markers being Markdown emphasis like the rest of the message content.
+pub const SNIPPET_START: &str = "**";
+pub const SNIPPET_STOP: &str = "**";
+
+/// Maximum number of results of a search page, and of results skipped by `offset`.
+pub const MAX_SEARCH_LIMIT: u64 = 50;
+pub const MAX_SEARCH_OFFSET: u64 = 1000;
+
+/// Options of `ts_headline` producing the snippets.
+pub fn headline_options() -> String {
+    format!(
+        "StartSel={}, StopSel={}, MaxWords=24, MinWords=8, MaxFragments=2, FragmentDelimiter=\" … \"",
+        SNIPPET_START, SNIPPET_STOP
+    )
+}
+
+/// # Search Language
+///
+/// Postgres text search configuration used to stem and index the messages and templates.
+///
+/// It is baked into the generated `search_vector` columns when the tables are created, and
+/// queries must use the same one to match: changing it requires dropping these columns so
+/// they are generated again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchLanguage {
+    /// No stemming nor stop words, suitable for mixed languages.
+    Simple,
+    #[default]
+    English,
+    Spanish,
+    Portuguese,
+    French,
+    German,
+    Italian,
+    Dutch,
+    Russian,
+}
+
+impl SearchLanguage {
+    /// Name of the Postgres `regconfig`. Only built-in configurations are listed, so it is
+    /// safe to inline in SQL.
+    pub fn regconfig(&self) -> &'static str {
+        match self {
+            SearchLanguage::Simple => "simple",
+            SearchLanguage::English => "english",
+            SearchLanguage::Spanish => "spanish",
+            SearchLanguage::Portuguese => "portuguese",
+            SearchLanguage::French => "french",
+            SearchLanguage::German => "german",
+            SearchLanguage::Italian => "italian",
+            SearchLanguage::Dutch => "dutch",
+            SearchLanguage::Russian => "russian",
+        }
+    }
+}
+
+/// Builds a `to_tsquery` expression matching every term of a free text query, the last
+/// term as a prefix so results show up while the query is being typed. Punctuation is
+/// dropped, so the expression is always valid. `None` when the query has no terms.
+pub fn prefix_tsquery(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .take(MAX_SEARCH_TERMS)
+        .map(|term| term.to_lowercase())
+        .collect();
+
+    let (last, rest) = terms.split_last()?;
+    let mut parts: Vec<String> = rest.iter().map(|term| format!("'{}'", term)).collect();
+    parts.push(format!("'{}':*", last));
+    Some(parts.join(" & "))
+}
+
+/// # Message Search Hit
+///
+/// - `rank`: relevance of the message, higher is better.
+/// - `snippet`: the most relevant fragments of the content, with the matches highlighted
+///   between `SNIPPET_START` and `SNIPPET_STOP`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageSearchHit {
+    pub message: MessageModel,
+    pub rank: f32,
+    pub snippet: String,
+}
+
+/// # Template Search Hit
+///
+/// Like `MessageSearchHit`, the snippet being taken from the description of the template.
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateSearchHit {
+    pub template: RoomTemplateModel,
+    pub rank: f32,
+    pub snippet: Option<String>,
+}
@@ -0,0 +1,169 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Serialize};
+
+use crate::entities::room::member::{MemberRole, Model as MemberModel};
+use crate::entities::room::permission::Permission;
+use crate::types::{ID, Timestamp};
+
+/// Longest slow mode interval, 1 day.
+pub const MAX_SLOW_MODE_INTERVAL_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Highest maximum message length a room can set.
+pub const MAX_MESSAGE_LENGTH: i32 = 20_000;
+
+/// Number of messages a room can pin unless its settings say otherwise.
+pub const DEFAULT_MAX_PINNED_MESSAGES: i32 = 50;
+
+/// Highest number of pinned messages a room can allow.
+pub const MAX_PINNED_MESSAGES: i32 = 500;
+
+/// # Room Settings
+///
+/// The `room_settings` table stores the posting rules of a room, checked by
+/// `RoomService::add_message` for the messages written by members. Rooms without a record
+/// use [`Model::defaults`], which allows everything.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "room_settings")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "room_id"
+    )]
+    pub room_id: ID,
+
+    /// # Slow Mode Interval
+    ///
+    /// Minimum time between two messages of a member, `None` when slow mode is off.
+    /// Members who can hide messages, such as moderators, are not slowed down.
+    #[sea_orm(
+        column_type = "BigInteger",
+        column_name = "slow_mode_interval_ms",
+        nullable
+    )]
+    pub slow_mode_interval_ms: Option<i64>,
+    /// # Max Message Length
+    ///
+    /// Maximum number of characters of the content of a message, `None` for no limit.
+    #[sea_orm(column_type = "Integer", column_name = "max_message_length", nullable)]
+    pub max_message_length: Option<i32>,
+    #[sea_orm(column_type = "Boolean", column_name = "allow_replies")]
+    pub allow_replies: bool,
+    #[sea_orm(column_type = "Boolean", column_name = "allow_attachments")]
+    pub allow_attachments: bool,
+    /// # Announcement Mode
+    ///
+    /// When set, only the owners of the room can post.
+    #[sea_orm(column_type = "Boolean", column_name = "announcement_mode")]
+    pub announcement_mode: bool,
+
+    /// # Pin Role
+    ///
+    /// Lowest role allowed to pin and unpin messages, see [`Model::can_pin`]. Defaults to
+    /// `Moderator`, as in the permission matrix.
+    #[sea_orm(column_type = "Text", column_name = "pin_role")]
+    pub pin_role: MemberRole,
+    #[sea_orm(column_type = "Integer", column_name = "max_pinned_messages")]
+    pub max_pinned_messages: i32,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+impl Model {
+    /// Settings of a room without a record.
+    pub fn defaults(room_id: ID) -> Self {
+        Model {
+            room_id,
+            slow_mode_interval_ms: None,
+            max_message_length: None,
+            allow_replies: true,
+            allow_attachments: true,
+            announcement_mode: false,
+            pin_role: MemberRole::Moderator,
+            max_pinned_messages: DEFAULT_MAX_PINNED_MESSAGES,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    /// Whether the messages of `member` are subject to slow mode.
+    pub fn slows_down(&self, member: &MemberModel) -> bool {
+        self.slow_mode_interval_ms
+            .is_some_and(|interval| interval > 0)
+            && !member.role.can(Permission::HideMessage)
+    }
+
+    /// Whether `member` can pin and unpin messages. Read-only members never can.
+    pub fn can_pin(&self, member: &MemberModel) -> bool {
+        member.role != MemberRole::ReadOnly && member.role.rank() >= self.pin_role.rank()
+    }
+
+    /// Checks a message written by `member` against the settings, `last_posted_at` being
+    /// the creation time of the previous message of the member in the room. The error is
+    /// the reason the message can't be posted.
+    pub fn check_message(
+        &self,
+        member: &MemberModel,
+        content: Option<&str>,
+        is_reply: bool,
+        has_attachment: bool,
+        last_posted_at: Option<Timestamp>,
+        now: Timestamp,
+    ) -> Result<(), String> {
+        if self.announcement_mode && member.role != MemberRole::Owner {
+            return Err("only owners can post in announcement mode".to_string());
+        }
+        if is_reply && !self.allow_replies {
+            return Err("replies are disabled in this room".to_string());
+        }
+        if has_attachment && !self.allow_attachments {
+            return Err("attachments are disabled in this room".to_string());
+        }
+        if let (Some(max_length), Some(content)) = (self.max_message_length, content)
+            && content.chars().count() > max_length as usize
+        {
+            return Err(format!("message is longer than {} characters", max_length));
+        }
+        if self.slows_down(member)
+            && let (Some(interval), Some(last_posted_at)) =
+                (self.slow_mode_interval_ms, last_posted_at)
+        {
+            let wait = last_posted_at + interval - now;
+            if wait > 0 {
+                return Err(format!(
+                    "slow mode is on, wait {} seconds before posting again",
+                    (wait + 999) / 1000
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,8 +1,26 @@
 use sea_orm::entity::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::types::{ID, Timestamp};
 
+/// # Template Visibility
+///
+/// - `Private`: only the author can see and use the template.
+/// - `Unlisted`: anyone with the template id can use and fork it, but it is not searchable.
+/// - `Public`: searchable through `search_templates`, usable and forkable by everyone.
+#[derive(
+    Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, EnumIter, DeriveActiveEnum,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum TemplateVisibility {
+    #[sea_orm(string_value = "private")]
+    Private,
+    #[sea_orm(string_value = "unlisted")]
+    Unlisted,
+    #[sea_orm(string_value = "public")]
+    Public,
+}
+
 /// # Template
 ///
 /// The `template` table stores information about templates used in rooms.
@@ -51,6 +69,38 @@ pub struct Model {
     #[sea_orm(column_type = "Text", column_name = "description", nullable)]
     pub description: Option<String>,
 
+    #[sea_orm(column_type = "Text", column_name = "visibility")]
+    pub visibility: TemplateVisibility,
+
+    /// # Latest Version
+    ///
+    /// Number of the most recent published version, `0` while the template is a draft
+    /// that was never published. The fields above are the editable draft; published
+    /// versions are immutable snapshots stored in `room_template_version`.
+    #[sea_orm(column_type = "Integer", column_name = "latest_version")]
+    pub latest_version: i32,
+
+    /// # Forked From
+    ///
+    /// The template (and the published version of it) this template was forked from.
+    #[sea_orm(
+        column_type = "Uuid",
+        column_name = "forked_from_id",
+        indexed,
+        nullable
+    )]
+    pub forked_from_id: Option<ID>,
+    #[sea_orm(column_type = "Uuid", column_name = "forked_from_version_id", nullable)]
+    pub forked_from_version_id: Option<ID>,
+
+    /// # Counters
+    ///
+    /// Number of rooms created from any version of the template, and number of forks.
+    #[sea_orm(column_type = "BigInteger", column_name = "usage_count")]
+    pub usage_count: i64,
+    #[sea_orm(column_type = "BigInteger", column_name = "fork_count")]
+    pub fork_count: i64,
+
     #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
     pub deleted_at: Option<Timestamp>,
     #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
@@ -63,23 +113,24 @@ pub struct Model {
 pub enum Relation {
     Account,
     Room,
+    Version,
 }
 
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
         match self {
-            Self::Account => {
-                Entity::belongs_to(crate::entities::account::account::Entity)
-                    .from(Column::AuthorId)
-                    .to(crate::entities::account::account::Column::Id)
-                    .into()
-            }
-            Self::Room => {
-                Entity::belongs_to(crate::entities::room::room::Entity)
-                    .from(Column::SourceRoomId)
-                    .to(crate::entities::room::room::Column::Id)
-                    .into()
-            }
+            Self::Account => Entity::belongs_to(crate::entities::account::account::Entity)
+                .from(Column::AuthorId)
+                .to(crate::entities::account::account::Column::Id)
+                .into(),
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::SourceRoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Version => Entity::has_many(crate::entities::room::template_version::Entity)
+                .from(Column::Id)
+                .to(crate::entities::room::template_version::Column::TemplateId)
+                .into(),
         }
     }
 }
@@ -96,4 +147,10 @@ impl Related<crate::entities::room::room::Entity> for Entity {
     }
 }
 
+impl Related<crate::entities::room::template_version::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Version.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
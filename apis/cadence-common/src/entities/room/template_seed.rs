@@ -0,0 +1,75 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use crate::types::{ID, Timestamp};
+
+/// # Template Seed
+///
+/// The `room_template_seed` table stores the seed messages of a published template version,
+/// copied from the source room when the version was published. Replies are kept by
+/// position instead of by message id, since the seeds are cloned into every new room.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "room_template_seed")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "version_id", indexed)]
+    pub version_id: ID,
+
+    /// # Position
+    ///
+    /// Order of the seed inside its version, starting at `0`.
+    #[sea_orm(column_type = "Integer", column_name = "position")]
+    pub position: i32,
+
+    #[sea_orm(column_type = "Text", column_name = "model_tag", nullable)]
+    pub model_tag: Option<String>,
+    #[sea_orm(column_type = "Text", column_name = "content", nullable)]
+    pub content: Option<String>,
+    /// # Attachment ID
+    ///
+    /// Attachment of the source message, copied into every room created from the version.
+    #[sea_orm(column_type = "Uuid", column_name = "attachment_id", nullable)]
+    pub attachment_id: Option<ID>,
+
+    /// # Reply To Position
+    ///
+    /// Position of the seed this one replies to, if any.
+    #[sea_orm(column_type = "Integer", column_name = "reply_to_position", nullable)]
+    pub reply_to_position: Option<i32>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Version,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Version => Entity::belongs_to(crate::entities::room::template_version::Entity)
+                .from(Column::VersionId)
+                .to(crate::entities::room::template_version::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::template_version::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Version.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,91 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use crate::types::{ID, Timestamp};
+
+/// # Template Version
+///
+/// The `room_template_version` table stores the published versions of a template. A version
+/// is an immutable snapshot of the template draft taken at publication time, together with
+/// its seed messages (`room_template_seed`), so rooms created from it keep pointing at
+/// exactly what they were built from.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "room_template_version")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "template_id", indexed)]
+    pub template_id: ID,
+
+    /// # Version
+    ///
+    /// Sequential number of the version inside its template, starting at `1`.
+    #[sea_orm(column_type = "Integer", column_name = "version")]
+    pub version: i32,
+
+    #[sea_orm(column_type = "Uuid", column_name = "published_by", nullable)]
+    pub published_by: Option<ID>,
+
+    #[sea_orm(column_type = "Text", column_name = "name", nullable)]
+    pub name: Option<String>,
+    #[sea_orm(column_type = "Text", column_name = "description", nullable)]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text", column_name = "model_tag")]
+    pub model_tag: String,
+    #[sea_orm(column_type = "Text", column_name = "system_prompt", nullable)]
+    pub system_prompt: Option<String>,
+
+    /// # Usage Count
+    ///
+    /// Number of rooms created from this version.
+    #[sea_orm(column_type = "BigInteger", column_name = "usage_count")]
+    pub usage_count: i64,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
+    pub deleted_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Template,
+    Seed,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Template => Entity::belongs_to(crate::entities::room::template::Entity)
+                .from(Column::TemplateId)
+                .to(crate::entities::room::template::Column::Id)
+                .into(),
+            Self::Seed => Entity::has_many(crate::entities::room::template_seed::Entity)
+                .from(Column::Id)
+                .to(crate::entities::room::template_seed::Column::VersionId)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::template::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Template.def()
+    }
+}
+
+impl Related<crate::entities::room::template_seed::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Seed.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,952 @@
+#![cfg(test)] // Ensure this file is only compiled for tests
+
+use std::sync::Arc;
+
+use super::direct_room;
+use super::message_reaction;
+use super::context::{ContextBuilder, ContextWindowConfig, TruncationStrategy, WhitespaceTokenizer};
+use super::identity::{IdentityGenerator, PrivacyPolicy};
+use super::member::{
+    MemberRole, Model as MemberModel, check_can_ban, check_can_join, latest_membership, placeholder,
+};
+use super::settings::Model as RoomSettingsModel;
+use super::ticket::{Model as TicketModel, SlaPolicy, TicketPriority, TicketStatus};
+use super::message::{MessageType, Model as MessageModel};
+use super::permission::Permission;
+use super::search::{SearchLanguage, prefix_tsquery};
+use crate::llm::{ChatMessage, ChatRole};
+use crate::input_validation::is_valid_reaction;
+use crate::time::now_millis;
+use super::room::{RoomType, RoomVisibility};
+use super::template::{Model as RoomTemplateModel, TemplateVisibility};
+use super::template_version::Model as TemplateVersionModel;
+use crate::entities::services::room::{
+    ReactionSummary, RoomFromTemplateSchema, RoomMessage, SeedMessage, bulk_author_condition,
+    message_member_ids, pinned_messages_condition, refresh_last_message_at,
+    visible_messages_condition, seed_message_copies, template_room_schema, thread_statement,
+    tombstone_if_removed, validate_thread_page,
+};
+use std::collections::HashSet;
+use crate::error::DatabaseError;
+use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait, Schema, SqlErr, Value};
+
+// --- Permission Matrix Tests ---
+
+#[test]
+fn test_owner_has_every_permission() {
+    assert!(MemberRole::Owner.can(Permission::DeleteRoom));
+    assert!(MemberRole::Owner.can(Permission::TransferOwnership));
+    assert!(MemberRole::Owner.can(Permission::PostMessage));
+}
+
+#[test]
+fn test_admin_cannot_delete_room_or_transfer_ownership() {
+    assert!(MemberRole::Admin.can(Permission::EditRoom));
+    assert!(MemberRole::Admin.can(Permission::ManageTemplates));
+    assert!(!MemberRole::Admin.can(Permission::DeleteRoom));
+    assert!(!MemberRole::Admin.can(Permission::TransferOwnership));
+}
+
+#[test]
+fn test_moderator_can_moderate_but_not_edit_room() {
+    assert!(MemberRole::Moderator.can(Permission::HideMessage));
+    assert!(MemberRole::Moderator.can(Permission::BanMember));
+    assert!(!MemberRole::Moderator.can(Permission::EditRoom));
+    assert!(!MemberRole::Moderator.can(Permission::AddMember));
+}
+
+#[test]
+fn test_read_only_cannot_post() {
+    assert!(MemberRole::Member.can(Permission::PostMessage));
+    assert!(!MemberRole::ReadOnly.can(Permission::PostMessage));
+}
+
+#[test]
+fn test_role_hierarchy() {
+    assert!(MemberRole::Owner.outranks(&MemberRole::Admin));
+    assert!(MemberRole::Moderator.outranks(&MemberRole::Member));
+    assert!(!MemberRole::Admin.outranks(&MemberRole::Admin));
+    assert!(!MemberRole::ReadOnly.outranks(&MemberRole::Member));
+    // ordering follows the hierarchy, not the declaration order
+    assert!(MemberRole::Owner > MemberRole::Admin);
+    assert!(MemberRole::ReadOnly < MemberRole::Member);
+    assert_eq!(
+        [MemberRole::Member, MemberRole::Owner, MemberRole::ReadOnly]
+            .iter()
+            .max(),
+        Some(&MemberRole::Owner)
+    );
+}
+
+// --- Read Marker Tests ---
+
+#[test]
+fn test_read_markers_skip_removed_messages_and_summaries() {
+    let room_id = uuid::Uuid::new_v4();
+    let sql = super::message::Entity::find()
+        .filter(visible_messages_condition(room_id))
+        .build(DbBackend::Postgres)
+        .to_string();
+    assert!(sql.contains(&format!(
+        r#""message"."room_id" = '{}' AND "message"."type" <> 'summary' AND "message"."is_hidden" = FALSE AND "message"."deleted_at" IS NULL"#,
+        room_id
+    )), "{}", sql);
+}
+
+// --- Reaction Tests ---
+
+#[test]
+fn test_reaction_validation() {
+    assert!(is_valid_reaction("👍"));
+    assert!(is_valid_reaction("👩‍💻"));
+    assert!(is_valid_reaction(":thumbs_up:"));
+    assert!(is_valid_reaction(":+1:"));
+
+    assert!(!is_valid_reaction(""));
+    assert!(!is_valid_reaction("ok"));
+    assert!(!is_valid_reaction("👍 👍"));
+    assert!(!is_valid_reaction(":Thumbs Up:"));
+    assert!(!is_valid_reaction("::"));
+    assert!(!is_valid_reaction("!!"));
+    assert!(!is_valid_reaction(&"👍".repeat(17)));
+}
+
+#[test]
+fn test_reaction_is_unique_per_member_and_emoji() {
+    let stmt = Schema::new(DbBackend::Postgres).create_table_from_entity(message_reaction::Entity);
+    let sql = DbBackend::Postgres.build(&stmt).to_string();
+    assert!(
+        sql.contains(r#"PRIMARY KEY ("message_id", "member_id", "emoji")"#),
+        "{}",
+        sql
+    );
+}
+
+// --- Thread Tests ---
+
+fn room_message(message: MessageModel) -> RoomMessage {
+    RoomMessage {
+        message,
+        reactions: vec![ReactionSummary {
+            emoji: "👍".to_string(),
+            count: 2,
+            reacted: false,
+        }],
+        reply_count: 1,
+        tombstone: false,
+    }
+}
+
+#[test]
+fn test_thread_page_bounds() {
+    assert!(validate_thread_page(1, 1, 0).is_ok());
+    assert!(validate_thread_page(10, 100, 1000).is_ok());
+    for (max_depth, limit, offset) in [
+        (0, 10, 0),
+        (11, 10, 0),
+        (3, 0, 0),
+        (3, 101, 0),
+        (3, 10, 1001),
+    ] {
+        assert!(matches!(
+            validate_thread_page(max_depth, limit, offset),
+            Err(DatabaseError::ConstraintViolation(_))
+        ));
+    }
+}
+
+#[test]
+fn test_thread_statement_binds_depth_and_page() {
+    let (room_id, message_id) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    let statement = thread_statement(DbBackend::Postgres, room_id, message_id, 3, 20, 40);
+
+    assert!(statement.sql.contains("WITH RECURSIVE thread"));
+    assert!(statement.sql.contains("WHERE t.depth < $3"));
+    assert!(statement.sql.contains("LIMIT $4 OFFSET $5"));
+    // removed replies are only kept to hold a visible reply, direct or nested
+    assert!(statement.sql.contains("t.path || m.id AS path"));
+    assert!(statement.sql.contains("AND t.id = ANY(d.path)"));
+    assert!(statement.sql.contains("AND d.deleted_at IS NULL"));
+    assert!(statement.sql.contains("AND d.is_hidden = FALSE"));
+    assert_eq!(
+        statement.values.unwrap().0,
+        vec![
+            Value::from(message_id),
+            Value::from(room_id),
+            Value::from(3),
+            Value::from(20i64),
+            Value::from(40i64),
+        ]
+    );
+}
+
+#[test]
+fn test_tombstone_if_removed() {
+    let visible = tombstone_if_removed(room_message(message(1, true, "visible")));
+    assert!(!visible.tombstone);
+    assert_eq!(visible.message.content, Some("visible".to_string()));
+    assert_eq!(visible.reactions.len(), 1);
+
+    let mut hidden = message(1, false, "hidden");
+    hidden.is_hidden = true;
+    hidden.attachment_id = Some(uuid::Uuid::new_v4());
+    let hidden = tombstone_if_removed(room_message(hidden));
+    assert!(hidden.tombstone);
+    assert_eq!(hidden.message.content, None);
+    assert_eq!(hidden.message.attachment_id, None);
+    assert_eq!(hidden.message.model_tag, None);
+    assert!(hidden.reactions.is_empty());
+    // the reply count keeps the thread navigable
+    assert_eq!(hidden.reply_count, 1);
+
+    let mut deleted = message(1, true, "deleted");
+    deleted.deleted_at = Some(2);
+    let deleted = tombstone_if_removed(room_message(deleted));
+    assert!(deleted.tombstone);
+    assert_eq!(deleted.message.content, None);
+}
+
+#[test]
+fn test_message_member_ids_only_cover_the_page() {
+    let authored = message(1, true, "authored");
+    let mut pinned = message(2, false, "from the model");
+    pinned.pinned_by = Some(uuid::Uuid::new_v4());
+
+    let ids = message_member_ids(&[room_message(authored.clone()), room_message(pinned.clone())]);
+    assert_eq!(
+        ids,
+        HashSet::from([authored.member_id.unwrap(), pinned.pinned_by.unwrap()])
+    );
+    assert!(message_member_ids(&[room_message(message(3, false, "model"))]).is_empty());
+}
+
+// --- Template Room Tests ---
+
+fn template(author_id: uuid::Uuid) -> RoomTemplateModel {
+    RoomTemplateModel {
+        id: uuid::Uuid::new_v4(),
+        author_id: Some(author_id),
+        system_prompt: Some("Draft prompt".to_string()),
+        model_tag: "gpt-4o".to_string(),
+        source_room_id: None,
+        name: Some("Draft".to_string()),
+        description: Some("Draft description".to_string()),
+        visibility: TemplateVisibility::Public,
+        latest_version: 1,
+        forked_from_id: None,
+        forked_from_version_id: None,
+        usage_count: 0,
+        fork_count: 0,
+        deleted_at: None,
+        created_at: 1,
+        updated_at: 1,
+    }
+}
+
+fn seed(content: &str, reply_to_position: Option<i32>) -> SeedMessage {
+    SeedMessage {
+        model_tag: None,
+        content: Some(content.to_string()),
+        attachment_id: None,
+        reply_to_position,
+    }
+}
+
+#[test]
+fn test_template_room_schema_records_template_and_version() {
+    let account_id = uuid::Uuid::new_v4();
+    let template = template(account_id);
+    let version = TemplateVersionModel {
+        id: uuid::Uuid::new_v4(),
+        template_id: template.id,
+        version: 1,
+        published_by: Some(account_id),
+        name: Some("Published".to_string()),
+        description: None,
+        model_tag: "claude".to_string(),
+        system_prompt: Some("Published prompt".to_string()),
+        usage_count: 0,
+        deleted_at: None,
+        created_at: 1,
+        updated_at: 1,
+    };
+    let mut schema = RoomFromTemplateSchema {
+        template_id: template.id,
+        template_version_id: None,
+        account_id,
+        anonymize: false,
+        name: None,
+        room_type: RoomType::Group,
+        visibility: RoomVisibility::Private,
+    };
+
+    let room = template_room_schema(&template, Some(&version), &schema);
+    assert_eq!(room.template_id, Some(template.id));
+    assert_eq!(room.template_version_id, Some(version.id));
+    assert_eq!(room.name, Some("Published".to_string()));
+    assert_eq!(room.description, None);
+    assert_eq!(room.model_tag, Some("claude".to_string()));
+    assert_eq!(room.system_prompt, Some("Published prompt".to_string()));
+
+    // drafts are used as they are, without a version
+    schema.name = Some("Mine".to_string());
+    let room = template_room_schema(&template, None, &schema);
+    assert_eq!(room.template_id, Some(template.id));
+    assert_eq!(room.template_version_id, None);
+    assert_eq!(room.name, Some("Mine".to_string()));
+    assert_eq!(room.model_tag, Some("gpt-4o".to_string()));
+    assert_eq!(room.system_prompt, Some("Draft prompt".to_string()));
+}
+
+#[test]
+fn test_seed_message_copies_remap_replies() {
+    let room_id = uuid::Uuid::new_v4();
+    let attachment_id = uuid::Uuid::new_v4();
+    let mut with_attachment = seed("second", Some(0));
+    with_attachment.attachment_id = Some(attachment_id);
+    let seeds = vec![
+        seed("first", None),
+        with_attachment,
+        seed("third", Some(1)),
+        // replies to a later or missing seed are dropped
+        seed("fourth", Some(5)),
+        seed("fifth", Some(4)),
+    ];
+
+    let copies = seed_message_copies(room_id, seeds, 1_000);
+    let ids: Vec<uuid::Uuid> = copies.iter().map(|(c, _)| c.id.clone().unwrap()).collect();
+    let reply_tos: Vec<Option<uuid::Uuid>> = copies
+        .iter()
+        .map(|(c, _)| c.reply_to.clone().unwrap())
+        .collect();
+
+    assert_eq!(copies.len(), 5);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 5);
+    assert_eq!(
+        reply_tos,
+        vec![None, Some(ids[0]), Some(ids[1]), None, None]
+    );
+    for (index, (copy, _)) in copies.iter().enumerate() {
+        assert_eq!(copy.room_id.clone().unwrap(), room_id);
+        assert_eq!(copy.member_id.clone().unwrap(), None);
+        assert_eq!(copy.created_at.clone().unwrap(), 1_000 + index as i64);
+        // attachments are cloned once the copy exists
+        assert_eq!(copy.attachment_id.clone().unwrap(), None);
+    }
+    assert_eq!(
+        copies[0].0.content.clone().unwrap(),
+        Some("first".to_string())
+    );
+    assert_eq!(copies[1].1, Some(attachment_id));
+    assert_eq!(copies[0].1, None);
+}
+
+// --- Membership Tests ---
+
+#[test]
+fn test_member_is_banned_with_expiry() {
+    let mut member = anonymized_member(uuid::Uuid::new_v4());
+    assert!(!member.is_banned());
+
+    member.banned_at = Some(1);
+    assert!(member.is_banned());
+    member.ban_expires_at = Some(now_millis() + 60_000);
+    assert!(member.is_banned());
+    member.ban_expires_at = Some(now_millis() - 1);
+    assert!(!member.is_banned());
+}
+
+#[test]
+fn test_placeholder_member_has_no_account() {
+    let room_id = uuid::Uuid::new_v4();
+    let model = placeholder(room_id, 1_000);
+    assert_eq!(model.room_id.unwrap(), room_id);
+    assert_eq!(model.account_id.unwrap(), None);
+    assert_eq!(model.deleted_at.unwrap(), Some(1_000));
+    assert!(model.anonymize.unwrap());
+
+    let mut placeholder = anonymized_member(room_id);
+    placeholder.account_id = None;
+
+    // even an owner allowed to see accounts gets nothing to join back
+    let mut owner = anonymized_member(room_id);
+    owner.role = MemberRole::Owner;
+    let identity = IdentityGenerator::default().member_identity(
+        &placeholder,
+        None,
+        Some(&owner),
+        PrivacyPolicy::OwnersSeeAccounts,
+    );
+    assert!(identity.anonymous);
+    assert_eq!(identity.account_id, None);
+}
+
+#[test]
+fn test_check_can_ban() {
+    let room_id = uuid::Uuid::new_v4();
+    let mut moderator = anonymized_member(room_id);
+    moderator.role = MemberRole::Moderator;
+    let mut target = anonymized_member(room_id);
+
+    assert!(check_can_ban(Some(&moderator), &target).is_ok());
+    // the system outranks everyone
+    assert!(check_can_ban(None, &target).is_ok());
+
+    target.role = MemberRole::Moderator;
+    assert_eq!(
+        check_can_ban(Some(&moderator), &target),
+        Err("cannot ban a member with an equal or higher role".to_string())
+    );
+    target.role = MemberRole::Admin;
+    assert!(check_can_ban(Some(&moderator), &target).is_err());
+
+    target.role = MemberRole::Owner;
+    assert_eq!(
+        check_can_ban(None, &target),
+        Err("cannot ban owner".to_string())
+    );
+
+    target.role = MemberRole::Member;
+    target.banned_at = Some(1);
+    assert_eq!(
+        check_can_ban(Some(&moderator), &target),
+        Err("member is already banned".to_string())
+    );
+    // an expired ban can be replaced
+    target.ban_expires_at = Some(now_millis() - 1);
+    assert!(check_can_ban(Some(&moderator), &target).is_ok());
+}
+
+#[test]
+fn test_check_can_join_after_leaving() {
+    let room_id = uuid::Uuid::new_v4();
+    assert!(check_can_join(&[]).is_ok());
+
+    let mut membership = anonymized_member(room_id);
+    assert_eq!(
+        check_can_join(std::slice::from_ref(&membership)),
+        Err("account is already a member".to_string())
+    );
+
+    // a banned member leaving the room before redeeming an invite stays banned
+    membership.banned_at = Some(1);
+    membership.deleted_at = Some(2);
+    assert_eq!(
+        check_can_join(std::slice::from_ref(&membership)),
+        Err("member is banned".to_string())
+    );
+
+    membership.banned_at = None;
+    assert!(check_can_join(&[membership]).is_ok());
+}
+
+#[test]
+fn test_unban_after_leaving_allows_rejoining() {
+    let room_id = uuid::Uuid::new_v4();
+    let mut earlier = anonymized_member(room_id);
+    earlier.deleted_at = Some(2);
+    let mut membership = anonymized_member(room_id);
+    membership.account_id = earlier.account_id;
+    membership.created_at = 3;
+
+    // banned, then leaves the room
+    assert!(check_can_ban(None, &membership).is_ok());
+    membership.banned_at = Some(4);
+    membership.deleted_at = Some(5);
+    let mut memberships = vec![earlier, membership];
+    assert!(check_can_join(&memberships).is_err());
+
+    // the ban is found on the left membership and lifted there
+    let target = latest_membership(&memberships).unwrap();
+    assert!(target.is_banned());
+    let target_id = target.id;
+    for membership in memberships.iter_mut().filter(|m| m.id == target_id) {
+        membership.banned_at = None;
+    }
+    assert!(check_can_join(&memberships).is_ok());
+    assert_eq!(latest_membership(&[]), None);
+}
+
+// --- Bulk Moderation Tests ---
+
+fn bulk_sql(trigger: &MemberModel) -> String {
+    super::message::Entity::find()
+        .filter(bulk_author_condition(trigger))
+        .build(DbBackend::Postgres)
+        .to_string()
+}
+
+#[test]
+fn test_bulk_moderation_spares_higher_roles() {
+    let mut moderator = anonymized_member(uuid::Uuid::new_v4());
+    moderator.role = MemberRole::Moderator;
+    let sql = bulk_sql(&moderator);
+    // the messages of the model and of the moderator stay in reach
+    assert!(sql.contains(r#""message"."member_id" IS NULL"#));
+    assert!(sql.contains(&format!(r#""message"."member_id" = '{}'"#, moderator.id)));
+    assert!(sql.contains(&format!(
+        r#""member_id" NOT IN (SELECT "id" FROM "member" WHERE "member"."room_id" = '{}' AND "member"."role" IN ('owner', 'admin', 'moderator'))"#,
+        moderator.room_id
+    )));
+
+    let mut owner = moderator.clone();
+    owner.role = MemberRole::Owner;
+    assert!(bulk_sql(&owner).contains(r#""member"."role" IN ('owner'))"#));
+}
+
+// --- Context Window Tests ---
+
+fn message(created_at: i64, member: bool, content: &str) -> MessageModel {
+    MessageModel {
+        id: uuid::Uuid::new_v4(),
+        room_id: uuid::Uuid::nil(),
+        member_id: member.then(uuid::Uuid::new_v4),
+        system: false,
+        model_tag: (!member).then(|| "gpt-4o".to_string()),
+        content: Some(content.to_string()),
+        attachment_id: None,
+        reply_to: None,
+        message_type: MessageType::Default,
+        is_hidden: false,
+        pinned_at: None,
+        pinned_by: None,
+        summarized_until: None,
+        expires_at: None,
+        deleted_at: None,
+        created_at,
+        updated_at: created_at,
+    }
+}
+
+fn builder(max_tokens: usize, keep_recent: usize, strategy: TruncationStrategy) -> ContextBuilder {
+    ContextBuilder::new(
+        Arc::new(WhitespaceTokenizer),
+        ContextWindowConfig {
+            max_tokens,
+            keep_recent,
+            keep_pinned: true,
+            strategy,
+            message_overhead_tokens: 0,
+            summarize_threshold: 10,
+        },
+    )
+}
+
+#[test]
+fn test_context_roles_and_system_prompt() {
+    let mut hidden = message(3, true, "hidden");
+    hidden.is_hidden = true;
+    let history = vec![
+        message(1, true, "hello"),
+        message(2, false, "hi there"),
+        hidden,
+    ];
+
+    let window = builder(100, 10, TruncationStrategy::RecentOnly).build(
+        Some("be nice"),
+        Some("they met before"),
+        &[],
+        &history,
+    );
+
+    assert_eq!(
+        window.messages,
+        vec![
+            ChatMessage::new(ChatRole::System, "be nice"),
+            ChatMessage::new(
+                ChatRole::System,
+                "Summary of the earlier conversation:\nthey met before"
+            ),
+            ChatMessage::new(ChatRole::User, "hello"),
+            ChatMessage::new(ChatRole::Assistant, "hi there"),
+        ]
+    );
+    assert!(window.omitted.is_empty());
+}
+
+#[test]
+fn test_context_keeps_pinned_and_recent() {
+    let mut pinned = message(1, true, "remember this");
+    pinned.pinned_at = Some(10);
+    let history = vec![
+        pinned.clone(),
+        message(2, true, "old"),
+        message(3, true, "older reply"),
+        message(4, true, "recent"),
+        message(5, false, "latest"),
+    ];
+
+    let window = builder(100, 2, TruncationStrategy::RecentOnly).build(
+        None,
+        None,
+        &[pinned.clone()],
+        &history,
+    );
+
+    assert_eq!(
+        window.included,
+        vec![pinned.id, history[3].id, history[4].id]
+    );
+    assert_eq!(window.omitted, vec![history[1].id, history[2].id]);
+}
+
+#[test]
+fn test_context_fill_budget_adds_contiguous_older_messages() {
+    let history = vec![
+        message(1, true, "one two three four five"),
+        message(2, true, "six"),
+        message(3, true, "seven"),
+        message(4, true, "eight"),
+    ];
+
+    let window = builder(4, 1, TruncationStrategy::FillBudget).build(None, None, &[], &history);
+
+    // "one two three four five" does not fit, so nothing older than it is added either
+    assert_eq!(
+        window.included,
+        vec![history[1].id, history[2].id, history[3].id]
+    );
+    assert_eq!(window.omitted, vec![history[0].id]);
+    assert_eq!(window.prompt_tokens, 3);
+}
+
+#[test]
+fn test_context_over_budget_drops_oldest_but_keeps_latest() {
+    let history = vec![
+        message(1, true, "a b c"),
+        message(2, true, "d e f"),
+        message(3, true, "g h i j k"),
+    ];
+
+    let window = builder(4, 3, TruncationStrategy::FillBudget).build(None, None, &[], &history);
+
+    assert_eq!(window.included, vec![history[2].id]);
+    assert_eq!(window.omitted.len(), 2);
+}
+
+// --- Search Tests ---
+
+#[test]
+fn test_prefix_tsquery() {
+    assert_eq!(prefix_tsquery("Deploy"), Some("'deploy':*".to_string()));
+    assert_eq!(
+        prefix_tsquery("deploy, on FRI"),
+        Some("'deploy' & 'on' & 'fri':*".to_string())
+    );
+    // operators and quotes never reach the query
+    assert_eq!(prefix_tsquery("a' | !b:*"), Some("'a' & 'b':*".to_string()));
+    assert_eq!(prefix_tsquery(" ?! "), None);
+    assert_eq!(prefix_tsquery(""), None);
+}
+
+#[test]
+fn test_prefix_tsquery_caps_terms() {
+    let query = prefix_tsquery("a b c d e f g h i j").unwrap();
+    assert_eq!(query.matches(" & ").count(), 7);
+    assert!(query.ends_with("'h':*"));
+}
+
+#[test]
+fn test_search_language_regconfig() {
+    assert_eq!(SearchLanguage::default().regconfig(), "english");
+    assert_eq!(SearchLanguage::Simple.regconfig(), "simple");
+}
+
+// --- Anonymous Identity Tests ---
+
+fn anonymized_member(room_id: uuid::Uuid) -> MemberModel {
+    MemberModel {
+        id: uuid::Uuid::new_v4(),
+        room_id,
+        account_id: Some(uuid::Uuid::new_v4()),
+        role: MemberRole::Member,
+        is_owner: false,
+        anonymize: true,
+        last_read_message_id: None,
+        last_read_at: None,
+        banned_at: None,
+        ban_reason: None,
+        ban_expires_at: None,
+        deleted_at: None,
+        created_at: 1,
+        updated_at: 1,
+    }
+}
+
+#[test]
+fn test_anonymous_identity_is_deterministic() {
+    let generator = IdentityGenerator::new(b"key".to_vec());
+    let room_id = uuid::Uuid::new_v4();
+    let member_id = uuid::Uuid::new_v4();
+
+    let identity = generator.anonymous_identity(room_id, member_id);
+    assert_eq!(identity, generator.anonymous_identity(room_id, member_id));
+    assert_eq!(identity.handle.split(' ').count(), 3);
+    assert!(identity.avatar_url.starts_with("data:image/svg+xml,"));
+    assert!(!identity.avatar_url.contains('#'));
+
+    // another key or room gives another identity
+    assert_ne!(
+        IdentityGenerator::new(b"other".to_vec()).anonymous_identity(room_id, member_id),
+        identity
+    );
+    assert_ne!(
+        generator.anonymous_identity(uuid::Uuid::new_v4(), member_id),
+        identity
+    );
+}
+
+#[test]
+fn test_privacy_policy_reveals_accounts() {
+    let room_id = uuid::Uuid::new_v4();
+    let member = anonymized_member(room_id);
+    let mut owner = anonymized_member(room_id);
+    owner.anonymize = false;
+    owner.role = MemberRole::Owner;
+    let mut other = anonymized_member(room_id);
+    // the legacy flag alone doesn't make an owner
+    other.is_owner = true;
+
+    assert!(PrivacyPolicy::Strict.reveals_account(&member, Some(&member)));
+    assert!(!PrivacyPolicy::Strict.reveals_account(&member, Some(&owner)));
+    assert!(!PrivacyPolicy::Strict.reveals_account(&member, None));
+    assert!(PrivacyPolicy::OwnersSeeAccounts.reveals_account(&member, Some(&owner)));
+    assert!(!PrivacyPolicy::OwnersSeeAccounts.reveals_account(&member, Some(&other)));
+    // members that are not anonymized are public
+    assert!(PrivacyPolicy::Strict.reveals_account(&owner, None));
+
+    let identity = IdentityGenerator::default().member_identity(
+        &member,
+        Some("Alice"),
+        Some(&other),
+        PrivacyPolicy::Strict,
+    );
+    assert!(identity.anonymous);
+    assert_eq!(identity.account_id, None);
+    assert_ne!(identity.display_name, "Alice");
+}
+
+// --- Room Settings Tests ---
+
+#[test]
+fn test_room_settings_check_message() {
+    let room_id = uuid::Uuid::new_v4();
+    let member = anonymized_member(room_id);
+    let mut moderator = anonymized_member(room_id);
+    moderator.role = MemberRole::Moderator;
+
+    let mut settings = RoomSettingsModel::defaults(room_id);
+    assert!(
+        settings
+            .check_message(&member, Some("hello"), true, true, Some(1_000), 1_000)
+            .is_ok()
+    );
+
+    settings.allow_replies = false;
+    settings.allow_attachments = false;
+    settings.max_message_length = Some(5);
+    assert_eq!(
+        settings.check_message(&member, None, true, false, None, 0),
+        Err("replies are disabled in this room".to_string())
+    );
+    assert_eq!(
+        settings.check_message(&member, None, false, true, None, 0),
+        Err("attachments are disabled in this room".to_string())
+    );
+    assert!(
+        settings
+            .check_message(&member, Some("héllo"), false, false, None, 0)
+            .is_ok()
+    );
+    assert_eq!(
+        settings.check_message(&member, Some("hello!"), false, false, None, 0),
+        Err("message is longer than 5 characters".to_string())
+    );
+
+    settings.slow_mode_interval_ms = Some(10_000);
+    assert_eq!(
+        settings.check_message(&member, None, false, false, Some(1_000), 2_500),
+        Err("slow mode is on, wait 9 seconds before posting again".to_string())
+    );
+    assert!(
+        settings
+            .check_message(&member, None, false, false, Some(1_000), 11_000)
+            .is_ok()
+    );
+    assert!(
+        settings
+            .check_message(&member, None, false, false, None, 2_500)
+            .is_ok()
+    );
+    // moderators are not slowed down
+    assert!(!settings.slows_down(&moderator));
+    assert!(
+        settings
+            .check_message(&moderator, None, false, false, Some(1_000), 2_500)
+            .is_ok()
+    );
+
+    settings.announcement_mode = true;
+    assert_eq!(
+        settings.check_message(&moderator, None, false, false, None, 0),
+        Err("only owners can post in announcement mode".to_string())
+    );
+    moderator.is_owner = true;
+    assert!(
+        settings
+            .check_message(&moderator, None, false, false, None, 0)
+            .is_err()
+    );
+    moderator.role = MemberRole::Owner;
+    assert!(
+        settings
+            .check_message(&moderator, None, false, false, None, 0)
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_pin_limit_counts_listed_pins_only() {
+    let room_id = uuid::Uuid::new_v4();
+    let sql = super::message::Entity::find()
+        .filter(pinned_messages_condition(room_id))
+        .build(DbBackend::Postgres)
+        .to_string();
+    assert!(sql.contains(&format!(
+        r#""message"."room_id" = '{}' AND "message"."pinned_at" IS NOT NULL AND "message"."deleted_at" IS NULL AND "message"."is_hidden" = FALSE"#,
+        room_id
+    )), "{}", sql);
+}
+
+#[test]
+fn test_room_settings_can_pin() {
+    let room_id = uuid::Uuid::new_v4();
+    let mut member = anonymized_member(room_id);
+    let mut moderator = anonymized_member(room_id);
+    moderator.role = MemberRole::Moderator;
+
+    let mut settings = RoomSettingsModel::defaults(room_id);
+    assert!(settings.can_pin(&moderator));
+    assert!(!settings.can_pin(&member));
+
+    settings.pin_role = MemberRole::Member;
+    assert!(settings.can_pin(&member));
+    member.role = MemberRole::ReadOnly;
+    assert!(!settings.can_pin(&member));
+
+    settings.pin_role = MemberRole::Admin;
+    assert!(!settings.can_pin(&moderator));
+}
+
+// --- Support Ticket Tests ---
+
+#[test]
+fn test_ticket_status_transitions() {
+    assert!(TicketStatus::Open.can_become(TicketStatus::Pending));
+    assert!(TicketStatus::Pending.can_become(TicketStatus::Resolved));
+    assert!(TicketStatus::Resolved.can_become(TicketStatus::Open));
+    assert!(TicketStatus::Resolved.can_become(TicketStatus::Closed));
+    assert!(!TicketStatus::Resolved.can_become(TicketStatus::Pending));
+    assert!(!TicketStatus::Open.can_become(TicketStatus::Open));
+    assert!(!TicketStatus::Closed.can_become(TicketStatus::Open));
+}
+
+#[test]
+fn test_ticket_sla() {
+    const HOUR: i64 = 60 * 60 * 1000;
+    let policy = SlaPolicy::default();
+    assert_eq!(
+        policy.due_dates(TicketPriority::Urgent, 0),
+        (HOUR, 8 * HOUR)
+    );
+    assert!(TicketPriority::Urgent > TicketPriority::Low);
+
+    let (first_response_due_at, resolution_due_at) = policy.due_dates(TicketPriority::Normal, 0);
+    let mut ticket = TicketModel {
+        room_id: uuid::Uuid::new_v4(),
+        requester_id: uuid::Uuid::new_v4(),
+        assignee_id: None,
+        status: TicketStatus::Open,
+        priority: TicketPriority::Normal,
+        first_response_due_at,
+        resolution_due_at,
+        first_response_at: None,
+        resolved_at: None,
+        closed_at: None,
+        created_at: 0,
+        updated_at: 0,
+    };
+    assert!(!ticket.first_response_breached(HOUR));
+    assert!(ticket.first_response_breached(25 * HOUR));
+
+    // a late answer stays breached
+    ticket.first_response_at = Some(30 * HOUR);
+    assert!(ticket.first_response_breached(31 * HOUR));
+
+    ticket.resolved_at = Some(2 * 24 * HOUR);
+    assert!(!ticket.resolution_breached(30 * 24 * HOUR));
+}
+
+// --- Room Activity Tests ---
+
+#[test]
+fn test_refresh_last_message_at_uses_visible_messages() {
+    let room_id = uuid::Uuid::new_v4();
+    let sql = refresh_last_message_at([room_id])
+        .build(DbBackend::Postgres)
+        .to_string();
+    assert_eq!(
+        sql,
+        format!(
+            r#"UPDATE "room" SET "last_message_at" = (SELECT MAX("message"."created_at") FROM "message" WHERE "message"."room_id" = "room"."id" AND "message"."deleted_at" IS NULL AND "message"."is_hidden" = FALSE) WHERE "room"."id" IN ('{}')"#,
+            room_id
+        )
+    );
+}
+
+// --- Direct Room Tests ---
+
+#[test]
+fn test_direct_room_pair() {
+    let a = uuid::Uuid::new_v4();
+    let b = uuid::Uuid::new_v4();
+    let (first, second) = direct_room::pair(a, b);
+    assert_eq!((first, second), direct_room::pair(b, a));
+    assert!(first < second);
+
+    let pair = direct_room::Model {
+        first_account_id: first,
+        second_account_id: second,
+        room_id: uuid::Uuid::new_v4(),
+        created_at: 0,
+    };
+    assert_eq!(pair.other_account_id(a), b);
+    assert_eq!(pair.other_account_id(b), a);
+}
+
+#[test]
+fn test_direct_room_pair_conflict() {
+    // the unnamed pair key is named `direct_room_pkey` by Postgres
+    let stmt = Schema::new(DbBackend::Postgres).create_table_from_entity(direct_room::Entity);
+    let sql = DbBackend::Postgres.build(&stmt).to_string();
+    assert!(
+        sql.contains(r#"PRIMARY KEY ("first_account_id", "second_account_id")"#),
+        "{}",
+        sql
+    );
+
+    assert!(direct_room::is_pair_conflict(
+        &SqlErr::UniqueConstraintViolation(
+            r#"duplicate key value violates unique constraint "direct_room_pkey""#.to_string()
+        )
+    ));
+    assert!(!direct_room::is_pair_conflict(
+        &SqlErr::UniqueConstraintViolation(
+            r#"duplicate key value violates unique constraint "direct_room_room_id_key""#
+                .to_string()
+        )
+    ));
+    assert!(!direct_room::is_pair_conflict(
+        &SqlErr::ForeignKeyConstraintViolation("direct_room_pkey".to_string())
+    ));
+}
@@ -0,0 +1,229 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+/// - `Open`: waiting for the staff.
+/// - `Pending`: waiting for the requester. A message of the requester opens it again.
+/// - `Resolved`: answered. The requester can still open it again.
+/// - `Closed`: final.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum TicketStatus {
+    #[default]
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "resolved")]
+    Resolved,
+    #[sea_orm(string_value = "closed")]
+    Closed,
+}
+
+impl TicketStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketStatus::Open => "open",
+            TicketStatus::Pending => "pending",
+            TicketStatus::Resolved => "resolved",
+            TicketStatus::Closed => "closed",
+        }
+    }
+
+    /// Whether a ticket can go from this status to `next`.
+    pub fn can_become(&self, next: TicketStatus) -> bool {
+        match self {
+            TicketStatus::Open => next != TicketStatus::Open,
+            TicketStatus::Pending => next != TicketStatus::Pending,
+            TicketStatus::Resolved => matches!(next, TicketStatus::Open | TicketStatus::Closed),
+            TicketStatus::Closed => false,
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum TicketPriority {
+    #[sea_orm(string_value = "low")]
+    Low,
+    #[default]
+    #[sea_orm(string_value = "normal")]
+    Normal,
+    #[sea_orm(string_value = "high")]
+    High,
+    #[sea_orm(string_value = "urgent")]
+    Urgent,
+}
+
+impl TicketPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketPriority::Low => "low",
+            TicketPriority::Normal => "normal",
+            TicketPriority::High => "high",
+            TicketPriority::Urgent => "urgent",
+        }
+    }
+}
+
+/// # SLA Policy
+///
+/// Time allowed, by priority, for the first response of the staff and for the resolution
+/// of a ticket, counted from its creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SlaPolicy {
+    pub first_response_ms: [i64; 4],
+    pub resolution_ms: [i64; 4],
+}
+
+impl Default for SlaPolicy {
+    fn default() -> Self {
+        const HOUR: i64 = 60 * 60 * 1000;
+        SlaPolicy {
+            first_response_ms: [48 * HOUR, 24 * HOUR, 4 * HOUR, HOUR],
+            resolution_ms: [14 * 24 * HOUR, 5 * 24 * HOUR, 2 * 24 * HOUR, 8 * HOUR],
+        }
+    }
+}
+
+impl SlaPolicy {
+    fn index(priority: TicketPriority) -> usize {
+        match priority {
+            TicketPriority::Low => 0,
+            TicketPriority::Normal => 1,
+            TicketPriority::High => 2,
+            TicketPriority::Urgent => 3,
+        }
+    }
+
+    /// Deadlines of the first response and of the resolution of a ticket created at
+    /// `created_at`.
+    pub fn due_dates(
+        &self,
+        priority: TicketPriority,
+        created_at: Timestamp,
+    ) -> (Timestamp, Timestamp) {
+        let index = SlaPolicy::index(priority);
+        (
+            created_at + self.first_response_ms[index],
+            created_at + self.resolution_ms[index],
+        )
+    }
+}
+
+/// # Support Ticket
+///
+/// The `support_ticket` table stores the ticket of every `Support` room, created along with
+/// the room. `requester_id` is the account that opened it and `assignee_id` the staff
+/// account handling it.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "support_ticket")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "room_id"
+    )]
+    pub room_id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "requester_id", indexed)]
+    pub requester_id: ID,
+    #[sea_orm(column_type = "Uuid", column_name = "assignee_id", indexed, nullable)]
+    pub assignee_id: Option<ID>,
+
+    #[sea_orm(column_type = "Text", column_name = "status", indexed)]
+    pub status: TicketStatus,
+    #[sea_orm(column_type = "Text", column_name = "priority")]
+    pub priority: TicketPriority,
+
+    /// # SLA
+    ///
+    /// Deadlines given by the `SlaPolicy` for the priority of the ticket, and when they
+    /// were met. `first_response_at` is the first message of someone else than the
+    /// requester, `resolved_at` when the ticket was resolved, cleared when it is opened again.
+    #[sea_orm(column_type = "BigInteger", column_name = "first_response_due_at")]
+    pub first_response_due_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "resolution_due_at", indexed)]
+    pub resolution_due_at: Timestamp,
+    #[sea_orm(
+        column_type = "BigInteger",
+        column_name = "first_response_at",
+        nullable
+    )]
+    pub first_response_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "resolved_at", nullable)]
+    pub resolved_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "closed_at", nullable)]
+    pub closed_at: Option<Timestamp>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+impl Model {
+    /// Whether the first response is late, or was.
+    pub fn first_response_breached(&self, now: Timestamp) -> bool {
+        self.first_response_at.unwrap_or(now) > self.first_response_due_at
+    }
+
+    /// Whether the resolution is late, or was.
+    pub fn resolution_breached(&self, now: Timestamp) -> bool {
+        self.resolved_at.or(self.closed_at).unwrap_or(now) > self.resolution_due_at
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Room,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -329,6 +329,26 @@ impl AccountService {
         Ok((account, flags))
     }
 
+    /// ## Has Flag
+    ///
+    /// Whether the account holds the flag named `flag_name`, e.g. the flag of global admins
+    /// or of support staff. Deleted flags are ignored.
+    pub async fn has_flag(&self, account_id: ID, flag_name: &str) -> Result<bool, DatabaseError> {
+        let count = account_flag::Entity::find()
+            .inner_join(flag::Entity)
+            .filter(account_flag::Column::AccountId.eq(account_id))
+            .filter(flag::Column::Name.eq(flag_name))
+            .filter(flag::Column::DeletedAt.is_null())
+            .count(self.db())
+            .await
+            .map_err(|e| {
+                trace!("Error getting account flags: {:?}", e);
+                DatabaseError::QueryFailed("Failed to get account flags".to_string())
+            })?;
+
+        Ok(count > 0)
+    }
+
     pub async fn get_from_email_address(
         &self,
         email_address: &str,
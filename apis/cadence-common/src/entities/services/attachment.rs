@@ -0,0 +1,302 @@
+use crate::entities::room::attachment::{self, Model as AttachmentModel};
+use crate::entities::room::member::{self, Model as MemberModel};
+use crate::entities::room::message;
+use crate::error::{AuthError, CadenceError, DatabaseError, EntityError};
+use crate::repository_traits::BasicApplicationService;
+use crate::storage::filesystem::FilesystemStorage;
+use crate::storage::signed_url::{SignedUrl, UrlSigner};
+use crate::storage::sigv4::sha256_hex;
+use crate::storage::{StorageBackend, UploadPolicy, sanitize_file_name};
+use crate::time::now_millis;
+use crate::types::{ID, Timestamp};
+use hyper::body::Bytes;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Default lifetime of a download URL, 15 minutes.
+pub const DEFAULT_DOWNLOAD_URL_TTL_MS: i64 = 15 * 60 * 1000;
+
+/// # Attachment Service
+///
+/// This struct provides a service for uploading the attachments of room messages and
+/// serving them back to the members of the room.
+///
+/// - `storage`: where the files are kept. Defaults to a directory under the temporary
+///   directory of the system.
+/// - `signer`: signs the download URLs. Defaults to a random key, so every instance
+///   serving downloads must be given the same signer.
+/// - `download_base_url`: prefix of the download URLs, the attachment id and the
+///   signature are appended to it.
+#[derive(Clone, Debug)]
+pub struct AttachmentService {
+    pub db: sea_orm::DatabaseConnection,
+    pub storage: Arc<dyn StorageBackend>,
+    pub policy: UploadPolicy,
+    pub signer: UrlSigner,
+    pub download_base_url: String,
+    pub download_url_ttl_ms: i64,
+}
+
+impl AttachmentService {
+    /// ## Upload Attachment
+    ///
+    /// Stores a file uploaded by a member of the room. The attachment is not visible to
+    /// other members until it is bound to a message by `RoomService::add_message`.
+    pub async fn upload_attachment(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        file_name: &str,
+        content_type: &str,
+        data: Bytes,
+    ) -> Result<AttachmentModel, CadenceError> {
+        let content_type = self.policy.validate(content_type, data.len())?;
+        let membership = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let id = uuid::Uuid::new_v4();
+        let storage_key = format!("rooms/{}/{}", room_id, id);
+        let checksum = sha256_hex(&data);
+        let size_bytes = data.len() as i64;
+
+        self.storage.put(&storage_key, data, &content_type).await?;
+
+        let inserted = attachment::ActiveModel {
+            id: Set(id),
+            room_id: Set(room_id),
+            member_id: Set(Some(membership.id)),
+            message_id: Set(None),
+            file_name: Set(sanitize_file_name(file_name)),
+            content_type: Set(content_type),
+            size_bytes: Set(size_bytes),
+            checksum: Set(checksum),
+            storage_key: Set(storage_key.clone()),
+            deleted_at: Set(None),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+        }
+        .insert(self.db())
+        .await;
+
+        match inserted {
+            Ok(attachment) => Ok(attachment),
+            Err(_) => {
+                // the object would be unreachable without its metadata
+                if let Err(e) = self.storage.delete(&storage_key).await {
+                    warn!(
+                        "Failed to delete orphaned attachment {}: {}",
+                        storage_key, e
+                    );
+                }
+                Err(CadenceError::Database(DatabaseError::InsertionError(
+                    "attachment".to_string(),
+                )))
+            }
+        }
+    }
+
+    /// ## Get Attachment
+    ///
+    /// Returns the metadata of an attachment the account can access, see
+    /// `get_accessible_attachment`.
+    pub async fn get_attachment(
+        &self,
+        attachment_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<AttachmentModel, CadenceError> {
+        self.get_accessible_attachment(attachment_id, trigger_account_id)
+            .await
+            .map(|(attachment, _)| attachment)
+    }
+
+    /// ## Create Download URL
+    ///
+    /// Issues a download URL of the attachment for the account, valid for
+    /// `download_url_ttl_ms`.
+    pub async fn create_download_url(
+        &self,
+        attachment_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<SignedUrl, CadenceError> {
+        self.get_accessible_attachment(attachment_id, trigger_account_id)
+            .await?;
+
+        let expires_at = now_millis() + self.download_url_ttl_ms;
+        let signature = self
+            .signer
+            .sign(attachment_id, trigger_account_id, expires_at);
+
+        Ok(SignedUrl {
+            url: format!(
+                "{}/{}?account={}&expires={}&signature={}",
+                self.download_base_url.trim_end_matches('/'),
+                attachment_id,
+                trigger_account_id,
+                expires_at,
+                signature
+            ),
+            expires_at,
+        })
+    }
+
+    /// ## Download Attachment
+    ///
+    /// Serves a download URL issued by `create_download_url`. Besides the signature and
+    /// the expiry, the membership of the account is checked again, so a URL stops working
+    /// as soon as the account leaves or is banned from the room.
+    pub async fn download_attachment(
+        &self,
+        attachment_id: ID,
+        account_id: ID,
+        expires_at: Timestamp,
+        signature: &str,
+    ) -> Result<(AttachmentModel, Bytes), CadenceError> {
+        self.signer.verify(
+            attachment_id,
+            account_id,
+            expires_at,
+            signature,
+            now_millis(),
+        )?;
+
+        let (attachment, _) = self
+            .get_accessible_attachment(attachment_id, account_id)
+            .await?;
+        let data = self.storage.get(&attachment.storage_key).await?;
+
+        Ok((attachment, data))
+    }
+
+    /// ## Delete Attachment
+    ///
+    /// Deletes an attachment that was never bound to a message. Only its uploader can
+    /// delete it; attachments of messages become inaccessible with their message.
+    pub async fn delete_attachment(
+        &self,
+        attachment_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<(), CadenceError> {
+        let (attachment, membership) = self
+            .get_accessible_attachment(attachment_id, trigger_account_id)
+            .await?;
+
+        if attachment.member_id != Some(membership.id) {
+            return Err(CadenceError::Auth(AuthError::Unauthorized(
+                "only the uploader can delete an attachment".to_string(),
+            )));
+        }
+        if attachment.message_id.is_some() {
+            return Err(CadenceError::Entity(EntityError::InvalidState(
+                "attachment is bound to a message".to_string(),
+            )));
+        }
+
+        attachment::ActiveModel {
+            id: Set(attachment.id),
+            deleted_at: Set(Some(now_millis())),
+            updated_at: Set(now_millis()),
+            ..Default::default()
+        }
+        .update(self.db())
+        .await
+        .map_err(|_| {
+            CadenceError::Database(DatabaseError::DeletionError("attachment".to_string()))
+        })?;
+
+        self.storage.delete(&attachment.storage_key).await?;
+        Ok(())
+    }
+
+    /// The attachment and the membership of the account in its room. Attachments not bound
+    /// to a message yet are only accessible to their uploader, and those of deleted or
+    /// hidden messages to nobody.
+    async fn get_accessible_attachment(
+        &self,
+        attachment_id: ID,
+        account_id: ID,
+    ) -> Result<(AttachmentModel, MemberModel), CadenceError> {
+        let not_found = || CadenceError::Entity(EntityError::NotFound("attachment".to_string()));
+
+        let attachment = attachment::Entity::find_by_id(attachment_id)
+            .filter(attachment::Column::DeletedAt.is_null())
+            .one(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::QueryFailed("attachment".to_string()))
+            })?
+            .ok_or_else(not_found)?;
+
+        let membership = self
+            .get_active_membership(attachment.room_id, account_id)
+            .await?;
+
+        match attachment.message_id {
+            None if attachment.member_id != Some(membership.id) => return Err(not_found()),
+            None => {}
+            Some(message_id) => {
+                let message = message::Entity::find_by_id(message_id)
+                    .one(self.db())
+                    .await
+                    .map_err(|_| {
+                        CadenceError::Database(DatabaseError::QueryFailed("message".to_string()))
+                    })?;
+                if message.is_none_or(|m| m.deleted_at.is_some() || m.is_hidden) {
+                    return Err(not_found());
+                }
+            }
+        }
+
+        Ok((attachment, membership))
+    }
+
+    async fn get_active_membership(
+        &self,
+        room_id: ID,
+        account_id: ID,
+    ) -> Result<MemberModel, CadenceError> {
+        let membership = member::Entity::find()
+            .filter(member::Column::RoomId.eq(room_id))
+            .filter(member::Column::AccountId.eq(account_id))
+            .filter(member::Column::DeletedAt.is_null())
+            .one(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::QueryFailed("membership".to_string()))
+            })?
+            .ok_or_else(|| {
+                CadenceError::Database(DatabaseError::RecordNotFound("membership".to_string()))
+            })?;
+
+        if membership.is_banned() {
+            return Err(CadenceError::Database(DatabaseError::ConstraintViolation(
+                "member is banned".to_string(),
+            )));
+        }
+
+        Ok(membership)
+    }
+}
+
+impl BasicApplicationService for AttachmentService {
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        AttachmentService {
+            db,
+            storage: Arc::new(FilesystemStorage::new(
+                std::env::temp_dir().join("cadence-attachments"),
+            )),
+            policy: UploadPolicy::default(),
+            signer: UrlSigner::random(),
+            download_base_url: "/attachments".to_string(),
+            download_url_ttl_ms: DEFAULT_DOWNLOAD_URL_TTL_MS,
+        }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+}
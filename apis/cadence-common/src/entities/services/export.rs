@@ -0,0 +1,405 @@
+use crate::entities::room::export::{self, ExportFormat, ExportStatus, Model as ExportModel};
+use crate::entities::services::room::RoomService;
+use crate::error::{AuthError, CadenceError, DatabaseError, EntityError};
+use crate::export::export_writer;
+use crate::repository_traits::BasicApplicationService;
+use crate::storage::StorageBackend;
+use crate::storage::filesystem::FilesystemStorage;
+use crate::storage::signed_url::{SignedUrl, UrlSigner};
+use crate::time::now_millis;
+use crate::types::{ID, Timestamp};
+use hyper::body::Bytes;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Default lifetime of a download URL, 15 minutes.
+pub const DEFAULT_EXPORT_URL_TTL_MS: i64 = 15 * 60 * 1000;
+
+/// Default time a completed export stays downloadable, 7 days.
+pub const DEFAULT_EXPORT_RETENTION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// # Export Service
+///
+/// This struct provides a service for exporting the conversation of a room in the
+/// background and serving the result to the account that requested it.
+///
+/// - `room_service`: writes the exports, see `RoomService::export_room`.
+/// - `storage`: where the results are kept. Defaults to a directory under the temporary
+///   directory of the system.
+/// - `signer`: signs the download URLs. Defaults to a random key, so every instance
+///   serving downloads must be given the same signer.
+/// - `download_base_url`: prefix of the download URLs, the export id and the signature
+///   are appended to it.
+/// - `attachment_base_url`: prefix of the attachment links written in the exports,
+///   usually the absolute `download_base_url` of the `AttachmentService`.
+#[derive(Clone, Debug)]
+pub struct ExportService {
+    pub db: sea_orm::DatabaseConnection,
+    pub room_service: RoomService,
+    pub storage: Arc<dyn StorageBackend>,
+    pub signer: UrlSigner,
+    pub download_base_url: String,
+    pub attachment_base_url: String,
+    pub download_url_ttl_ms: i64,
+    pub retention_ms: i64,
+}
+
+impl ExportService {
+    /// ## Request Export
+    ///
+    /// Queues an export of the room for the trigger account, who must be an active member
+    /// of it, and starts writing it in the background. Poll `get_export` until it is
+    /// `Completed` or `Failed`.
+    pub async fn request_export(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        format: ExportFormat,
+    ) -> Result<ExportModel, CadenceError> {
+        self.room_service
+            .get_active_membership(room_id, trigger_account_id)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        let export = export::ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            room_id: Set(room_id),
+            account_id: Set(trigger_account_id),
+            format: Set(format),
+            status: Set(ExportStatus::Pending),
+            error: Set(None),
+            file_name: Set(None),
+            size_bytes: Set(None),
+            message_count: Set(None),
+            storage_key: Set(None),
+            completed_at: Set(None),
+            expires_at: Set(None),
+            deleted_at: Set(None),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+        }
+        .insert(self.db())
+        .await
+        .map_err(|_| CadenceError::Database(DatabaseError::InsertionError("export".to_string())))?;
+
+        self.spawn_export(export.id);
+        Ok(export)
+    }
+
+    /// ## Resume Exports
+    ///
+    /// Queues again the exports interrupted by a restart and starts every pending export.
+    /// Meant to be called once at startup by the instance running the exports: exports
+    /// still `Running` are assumed to have been abandoned.
+    pub async fn resume_exports(&self) -> Result<u64, CadenceError> {
+        export::Entity::update_many()
+            .col_expr(export::Column::Status, Expr::value(ExportStatus::Pending))
+            .col_expr(export::Column::UpdatedAt, Expr::value(now_millis()))
+            .filter(export::Column::Status.eq(ExportStatus::Running))
+            .filter(export::Column::DeletedAt.is_null())
+            .exec(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::UpdateError("exports".to_string()))
+            })?;
+
+        let pending = export::Entity::find()
+            .filter(export::Column::Status.eq(ExportStatus::Pending))
+            .filter(export::Column::DeletedAt.is_null())
+            .all(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::QueryFailed("exports".to_string()))
+            })?;
+
+        info!("Resuming {} pending exports", pending.len());
+        for export in &pending {
+            self.spawn_export(export.id);
+        }
+        Ok(pending.len() as u64)
+    }
+
+    fn spawn_export(&self, export_id: ID) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.run_export(export_id).await {
+                warn!("Export {} failed: {:?}", export_id, e);
+            }
+        });
+    }
+
+    /// ## Run Export
+    ///
+    /// Writes a pending export and stores the result. The export is claimed first, so it
+    /// is only written once even when several workers pick it up; an export that is not
+    /// pending anymore is returned as it is.
+    pub async fn run_export(&self, export_id: ID) -> Result<ExportModel, CadenceError> {
+        let claimed = export::Entity::update_many()
+            .col_expr(export::Column::Status, Expr::value(ExportStatus::Running))
+            .col_expr(export::Column::UpdatedAt, Expr::value(now_millis()))
+            .filter(export::Column::Id.eq(export_id))
+            .filter(export::Column::Status.eq(ExportStatus::Pending))
+            .exec(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::UpdateError("export".to_string()))
+            })?;
+
+        let export = self.get_export_by_id(export_id).await?;
+        if claimed.rows_affected == 0 {
+            return Ok(export);
+        }
+
+        match self.write_export(&export).await {
+            Ok((storage_key, file_name, size_bytes, message_count)) => {
+                let now = now_millis();
+                export::ActiveModel {
+                    id: Set(export.id),
+                    status: Set(ExportStatus::Completed),
+                    storage_key: Set(Some(storage_key)),
+                    file_name: Set(Some(file_name)),
+                    size_bytes: Set(Some(size_bytes)),
+                    message_count: Set(Some(message_count)),
+                    completed_at: Set(Some(now)),
+                    expires_at: Set(Some(now + self.retention_ms)),
+                    updated_at: Set(now),
+                    ..Default::default()
+                }
+                .update(self.db())
+                .await
+                .map_err(|_| {
+                    CadenceError::Database(DatabaseError::UpdateError("export".to_string()))
+                })
+            }
+            Err(e) => {
+                export::ActiveModel {
+                    id: Set(export.id),
+                    status: Set(ExportStatus::Failed),
+                    error: Set(Some(format!("{:?}", e))),
+                    updated_at: Set(now_millis()),
+                    ..Default::default()
+                }
+                .update(self.db())
+                .await
+                .map_err(|_| {
+                    CadenceError::Database(DatabaseError::UpdateError("export".to_string()))
+                })?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Writes the export and stores it, returning its storage key, file name, size and
+    /// number of messages.
+    async fn write_export(
+        &self,
+        export: &ExportModel,
+    ) -> Result<(String, String, i64, i64), CadenceError> {
+        let mut data = Vec::new();
+        let message_count = {
+            let mut writer = export_writer(export.format, &mut data);
+            self.room_service
+                .export_room(
+                    export.room_id,
+                    export.account_id,
+                    &self.attachment_base_url,
+                    writer.as_mut(),
+                )
+                .await?
+        };
+
+        let storage_key = format!(
+            "exports/{}/{}.{}",
+            export.room_id,
+            export.id,
+            export.format.extension()
+        );
+        let file_name = format!(
+            "room-{}-{}.{}",
+            export.room_id,
+            chrono::DateTime::from_timestamp_millis(export.created_at)
+                .map(|t| t.format("%Y%m%d-%H%M%S").to_string())
+                .unwrap_or_default(),
+            export.format.extension()
+        );
+        let size_bytes = data.len() as i64;
+
+        self.storage
+            .put(
+                &storage_key,
+                Bytes::from(data),
+                export.format.content_type(),
+            )
+            .await?;
+
+        Ok((storage_key, file_name, size_bytes, message_count as i64))
+    }
+
+    /// ## Get Export
+    ///
+    /// Returns an export requested by the trigger account.
+    pub async fn get_export(
+        &self,
+        export_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<ExportModel, CadenceError> {
+        let export = self.get_export_by_id(export_id).await?;
+        if export.account_id != trigger_account_id {
+            return Err(CadenceError::Entity(EntityError::NotFound(
+                "export".to_string(),
+            )));
+        }
+        Ok(export)
+    }
+
+    /// ## Create Download URL
+    ///
+    /// Issues a download URL of a completed export for the account that requested it,
+    /// valid for `download_url_ttl_ms`.
+    pub async fn create_download_url(
+        &self,
+        export_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<SignedUrl, CadenceError> {
+        self.get_downloadable_export(export_id, trigger_account_id)
+            .await?;
+
+        let expires_at = now_millis() + self.download_url_ttl_ms;
+        let signature = self.signer.sign(export_id, trigger_account_id, expires_at);
+
+        Ok(SignedUrl {
+            url: format!(
+                "{}/{}?account={}&expires={}&signature={}",
+                self.download_base_url.trim_end_matches('/'),
+                export_id,
+                trigger_account_id,
+                expires_at,
+                signature
+            ),
+            expires_at,
+        })
+    }
+
+    /// ## Download Export
+    ///
+    /// Serves a download URL issued by `create_download_url`. As for attachments, the
+    /// membership of the account is checked again.
+    pub async fn download_export(
+        &self,
+        export_id: ID,
+        account_id: ID,
+        expires_at: Timestamp,
+        signature: &str,
+    ) -> Result<(ExportModel, Bytes), CadenceError> {
+        self.signer
+            .verify(export_id, account_id, expires_at, signature, now_millis())?;
+
+        let (export, storage_key) = self.get_downloadable_export(export_id, account_id).await?;
+        let data = self.storage.get(&storage_key).await?;
+
+        Ok((export, data))
+    }
+
+    /// ## Purge Expired Exports
+    ///
+    /// Removes the results of the exports past their `expires_at` from the storage.
+    /// Meant to be called periodically.
+    pub async fn purge_expired_exports(&self) -> Result<u64, CadenceError> {
+        let expired = export::Entity::find()
+            .filter(export::Column::DeletedAt.is_null())
+            .filter(export::Column::ExpiresAt.lte(now_millis()))
+            .all(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::QueryFailed("exports".to_string()))
+            })?;
+
+        for export in &expired {
+            if let Some(storage_key) = &export.storage_key {
+                self.storage.delete(storage_key).await?;
+            }
+            export::ActiveModel {
+                id: Set(export.id),
+                deleted_at: Set(Some(now_millis())),
+                updated_at: Set(now_millis()),
+                ..Default::default()
+            }
+            .update(self.db())
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::DeletionError("export".to_string()))
+            })?;
+        }
+
+        Ok(expired.len() as u64)
+    }
+
+    async fn get_export_by_id(&self, export_id: ID) -> Result<ExportModel, CadenceError> {
+        export::Entity::find_by_id(export_id)
+            .filter(export::Column::DeletedAt.is_null())
+            .one(self.db())
+            .await
+            .map_err(|_| CadenceError::Database(DatabaseError::QueryFailed("export".to_string())))?
+            .ok_or_else(|| CadenceError::Entity(EntityError::NotFound("export".to_string())))
+    }
+
+    /// A completed, unexpired export of the account with its storage key. The account must
+    /// still be an active member of the room.
+    async fn get_downloadable_export(
+        &self,
+        export_id: ID,
+        account_id: ID,
+    ) -> Result<(ExportModel, String), CadenceError> {
+        let export = self.get_export(export_id, account_id).await?;
+
+        self.room_service
+            .get_active_membership(export.room_id, account_id)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        let storage_key = match (&export.status, &export.storage_key) {
+            (ExportStatus::Completed, Some(storage_key))
+                if export.expires_at.is_none_or(|t| t > now_millis()) =>
+            {
+                storage_key.clone()
+            }
+            (ExportStatus::Completed, _) => {
+                return Err(CadenceError::Auth(AuthError::ExpiredToken(
+                    "export expired".to_string(),
+                )));
+            }
+            _ => {
+                return Err(CadenceError::Entity(EntityError::InvalidState(
+                    "export is not completed".to_string(),
+                )));
+            }
+        };
+
+        Ok((export, storage_key))
+    }
+}
+
+impl BasicApplicationService for ExportService {
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        ExportService {
+            db: db.clone(),
+            room_service: RoomService::new(db),
+            storage: Arc::new(FilesystemStorage::new(
+                std::env::temp_dir().join("cadence-exports"),
+            )),
+            signer: UrlSigner::random(),
+            download_base_url: "/exports".to_string(),
+            attachment_base_url: "/attachments".to_string(),
+            download_url_ttl_ms: DEFAULT_EXPORT_URL_TTL_MS,
+            retention_ms: DEFAULT_EXPORT_RETENTION_MS,
+        }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+}
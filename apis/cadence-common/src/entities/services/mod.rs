@@ -3,4 +3,9 @@
 // This is a higher level repository that can control multiple entities to make a cohesive and workable business logic
 
 pub mod account;
-pub mod room;
\ No newline at end of file
+pub mod room;
+pub mod usage;
+pub mod attachment;
+pub mod export;
+pub mod moderation;
+pub mod ticket;
\ No newline at end of file
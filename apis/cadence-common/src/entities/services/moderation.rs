@@ -0,0 +1,391 @@
+use crate::entities::room::member::{self, Model as MemberModel};
+use crate::entities::room::message::{self, MessageType, Model as MessageModel};
+use crate::entities::room::permission::Permission;
+use crate::entities::room::report::{
+    self, Model as ReportModel, ModerationAction, ReportReason, ReportStatus,
+};
+use crate::entities::services::account::AccountService;
+use crate::entities::services::room::RoomService;
+use crate::error::DatabaseError;
+use crate::repository_traits::{BasicApplicationService, CrudEntityRepository};
+use crate::time::now_millis;
+use crate::types::{ID, Timestamp};
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use sea_orm::{FromQueryResult, Order, QueryOrder, QuerySelect, TransactionTrait};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Name of the account flag of global admins.
+pub const DEFAULT_ADMIN_FLAG: &str = "admin";
+
+/// Maximum number of messages of a review queue page.
+pub const MAX_REVIEW_QUEUE_LIMIT: u64 = 100;
+
+/// Maximum length of the details of a report.
+pub const MAX_REPORT_DETAILS_LENGTH: usize = 1000;
+
+/// # Moderation Service
+///
+/// This struct provides a service for reporting messages and reviewing the reports.
+///
+/// Reports are reviewed by the members of the room whose role grants `HideMessage`, and by
+/// global admins, the accounts with the `admin_flag` flag, in every room.
+#[derive(Clone, Debug)]
+pub struct ModerationService {
+    pub db: sea_orm::DatabaseConnection,
+    pub room_service: RoomService,
+    pub account_service: AccountService,
+    pub admin_flag: String,
+}
+
+/// # Review Item
+///
+/// A message of the review queue with its open reports, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewItem {
+    pub message: MessageModel,
+    pub reports: Vec<ReportModel>,
+    pub first_reported_at: Timestamp,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct QueuedMessage {
+    message_id: ID,
+    first_reported_at: Timestamp,
+}
+
+impl ModerationService {
+    /// Whether the account holds the `admin_flag` flag.
+    pub async fn is_global_admin(&self, account_id: ID) -> Result<bool, DatabaseError> {
+        self.account_service
+            .has_flag(account_id, &self.admin_flag)
+            .await
+    }
+
+    /// Fails unless the account is a global admin or a member of the room whose role
+    /// grants `permission`.
+    async fn authorize_moderator(
+        &self,
+        room_id: ID,
+        account_id: ID,
+        permission: Permission,
+    ) -> Result<(), DatabaseError> {
+        if self.is_global_admin(account_id).await? {
+            return Ok(());
+        }
+        self.room_service
+            .authorize(room_id, account_id, permission)
+            .await
+            .map(|_| ())
+    }
+
+    /// ## Report Message
+    ///
+    /// Reports a message of another member to the moderators of the room. The trigger
+    /// account must be an active member of the room, and can only have one open report per
+    /// message.
+    pub async fn report_message(
+        &self,
+        room_id: ID,
+        message_id: ID,
+        trigger_account_id: ID,
+        reason: ReportReason,
+        details: Option<String>,
+    ) -> Result<ReportModel, DatabaseError> {
+        if reason == ReportReason::ContentFilter {
+            return Err(DatabaseError::ConstraintViolation(
+                "content filter reports are raised by the content filters".to_string(),
+            ));
+        }
+        if details
+            .as_ref()
+            .is_some_and(|details| details.chars().count() > MAX_REPORT_DETAILS_LENGTH)
+        {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "details must be at most {} characters",
+                MAX_REPORT_DETAILS_LENGTH
+            )));
+        }
+
+        let membership = self
+            .room_service
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let message = self.get_room_message(room_id, message_id).await?;
+        if message.deleted_at.is_some() {
+            return Err(DatabaseError::ConstraintViolation(
+                "message is deleted".to_string(),
+            ));
+        }
+        let Some(author_id) = message.member_id else {
+            return Err(DatabaseError::ConstraintViolation(
+                "only messages of members can be reported".to_string(),
+            ));
+        };
+
+        let author = member::Entity::find_by_id(author_id)
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+        if author.is_some_and(|author: MemberModel| author.account_id == Some(trigger_account_id)) {
+            return Err(DatabaseError::ConstraintViolation(
+                "members can't report their own messages".to_string(),
+            ));
+        }
+
+        let already_reported = report::Entity::find()
+            .filter(report::Column::MessageId.eq(message_id))
+            .filter(report::Column::ReporterId.eq(membership.id))
+            .filter(report::Column::Status.eq(ReportStatus::Open))
+            .count(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("message reports".to_string()))?;
+        if already_reported > 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "message already reported".to_string(),
+            ));
+        }
+
+        report::ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            room_id: Set(room_id),
+            message_id: Set(message_id),
+            reporter_id: Set(Some(membership.id)),
+            reason: Set(reason),
+            details: Set(details),
+            status: Set(ReportStatus::Open),
+            action: Set(None),
+            resolved_by: Set(None),
+            resolved_at: Set(None),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+        }
+        .insert(self.db())
+        .await
+        .map_err(|_| DatabaseError::InsertionError("message report".to_string()))
+    }
+
+    /// ## Get Review Queue
+    ///
+    /// The reported messages awaiting a decision, the longest waiting first. Global admins
+    /// see every room, other accounts the rooms where they can hide messages. `room_id`
+    /// narrows the queue to a single room.
+    pub async fn get_review_queue(
+        &self,
+        trigger_account_id: ID,
+        room_id: Option<ID>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<ReviewItem>, DatabaseError> {
+        if limit == 0 || limit > MAX_REVIEW_QUEUE_LIMIT {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "limit must be between 1 and {}",
+                MAX_REVIEW_QUEUE_LIMIT
+            )));
+        }
+
+        let room_ids: Option<Vec<ID>> = match room_id {
+            Some(room_id) => {
+                self.authorize_moderator(room_id, trigger_account_id, Permission::HideMessage)
+                    .await?;
+                Some(vec![room_id])
+            }
+            None if self.is_global_admin(trigger_account_id).await? => None,
+            None => Some(
+                member::Entity::find()
+                    .filter(member::Column::AccountId.eq(trigger_account_id))
+                    .filter(member::Column::DeletedAt.is_null())
+                    .all(self.db())
+                    .await
+                    .map_err(|_| DatabaseError::QueryFailed("memberships".to_string()))?
+                    .into_iter()
+                    .filter(|m| !m.is_banned() && m.role.can(Permission::HideMessage))
+                    .map(|m| m.room_id)
+                    .collect(),
+            ),
+        };
+
+        let mut query = report::Entity::find()
+            .select_only()
+            .column(report::Column::MessageId)
+            .column_as(report::Column::CreatedAt.min(), "first_reported_at")
+            .filter(report::Column::Status.eq(ReportStatus::Open))
+            .group_by(report::Column::MessageId)
+            .order_by(report::Column::CreatedAt.min(), Order::Asc)
+            .order_by(report::Column::MessageId, Order::Asc)
+            .limit(limit)
+            .offset(offset);
+        if let Some(room_ids) = room_ids {
+            if room_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+            query = query.filter(report::Column::RoomId.is_in(room_ids));
+        }
+
+        let queued = query
+            .into_model::<QueuedMessage>()
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("review queue".to_string()))?;
+        let message_ids: Vec<ID> = queued.iter().map(|q| q.message_id).collect();
+
+        let mut messages: HashMap<ID, MessageModel> = message::Entity::find()
+            .filter(message::Column::Id.is_in(message_ids.clone()))
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect();
+
+        let mut reports: HashMap<ID, Vec<ReportModel>> = HashMap::new();
+        for report in report::Entity::find()
+            .filter(report::Column::MessageId.is_in(message_ids))
+            .filter(report::Column::Status.eq(ReportStatus::Open))
+            .order_by(report::Column::CreatedAt, Order::Asc)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("message reports".to_string()))?
+        {
+            reports.entry(report.message_id).or_default().push(report);
+        }
+
+        Ok(queued
+            .into_iter()
+            .filter_map(|q| {
+                Some(ReviewItem {
+                    message: messages.remove(&q.message_id)?,
+                    reports: reports.remove(&q.message_id).unwrap_or_default(),
+                    first_reported_at: q.first_reported_at,
+                })
+            })
+            .collect())
+    }
+
+    /// ## Moderate Message
+    ///
+    /// Hides, unhides or deletes a message, or dismisses its reports leaving it as it is,
+    /// and closes its open reports with that decision. Deleting requires the
+    /// `DeleteMessage` permission, the other actions `HideMessage`.
+    pub async fn moderate_message(
+        &self,
+        room_id: ID,
+        message_id: ID,
+        trigger_account_id: ID,
+        action: ModerationAction,
+    ) -> Result<MessageModel, DatabaseError> {
+        let permission = match action {
+            ModerationAction::Delete => Permission::DeleteMessage,
+            _ => Permission::HideMessage,
+        };
+        self.authorize_moderator(room_id, trigger_account_id, permission)
+            .await?;
+
+        let message = self.get_room_message(room_id, message_id).await?;
+        if message.deleted_at.is_some() && action != ModerationAction::Dismiss {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} is already deleted",
+                message_id
+            )));
+        }
+        if message.message_type == MessageType::Summary {
+            return Err(DatabaseError::ConstraintViolation(
+                "summary messages are always hidden".to_string(),
+            ));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let message = match action {
+            ModerationAction::Hide | ModerationAction::Unhide => message::ActiveModel {
+                id: Set(message_id),
+                is_hidden: Set(action == ModerationAction::Hide),
+                updated_at: Set(now_millis()),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("message".to_string()))?,
+            ModerationAction::Delete => self
+                .room_service
+                .message_repository
+                .delete_tx(message_id, &txn)
+                .await
+                .map_err(|_| DatabaseError::DeletionError("message".to_string()))?,
+            ModerationAction::Dismiss => message,
+        };
+        if action != ModerationAction::Dismiss {
+            self.room_service
+                .refresh_last_message_at_tx([room_id], &txn)
+                .await?;
+        }
+
+        let status = match action {
+            ModerationAction::Dismiss => ReportStatus::Dismissed,
+            _ => ReportStatus::Resolved,
+        };
+        report::Entity::update_many()
+            .col_expr(report::Column::Status, Expr::value(status))
+            .col_expr(report::Column::Action, Expr::value(action))
+            .col_expr(report::Column::ResolvedBy, Expr::value(trigger_account_id))
+            .col_expr(report::Column::ResolvedAt, Expr::value(now_millis()))
+            .col_expr(report::Column::UpdatedAt, Expr::value(now_millis()))
+            .filter(report::Column::MessageId.eq(message_id))
+            .filter(report::Column::Status.eq(ReportStatus::Open))
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("message reports".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(message)
+    }
+
+    async fn get_room_message(
+        &self,
+        room_id: ID,
+        message_id: ID,
+    ) -> Result<MessageModel, DatabaseError> {
+        let message = self
+            .room_service
+            .message_repository
+            .get_by_id(message_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+            .ok_or_else(|| {
+                DatabaseError::RecordNotFound(format!("Message {} not found", message_id))
+            })?;
+
+        if message.room_id != room_id {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} does not belong to room {}",
+                message_id, room_id
+            )));
+        }
+
+        Ok(message)
+    }
+}
+
+impl BasicApplicationService for ModerationService {
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        ModerationService {
+            db: db.clone(),
+            room_service: RoomService::new(db.clone()),
+            account_service: AccountService::new(db),
+            admin_flag: DEFAULT_ADMIN_FLAG.to_string(),
+        }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+}
@@ -1,6 +1,18 @@
+use crate::entities::account::account;
 use crate::entities::account::repositories::account::AccountRepository;
-use crate::entities::room::member::{self, Entity as MemberEntity, Model as MemberModel};
+use crate::entities::room::attachment;
+use crate::entities::room::context::{ContextBuilder, ContextWindow, chat_role};
+use crate::entities::room::direct_room;
+use crate::entities::room::identity::{IdentityGenerator, MemberIdentity, PrivacyPolicy};
+use crate::entities::room::invite::{self, Model as InviteModel};
+use crate::entities::room::member::{self, Entity as MemberEntity, MemberRole, Model as MemberModel};
 use crate::entities::room::message::{self, MessageType, Model as MessageModel};
+use crate::entities::room::message_reaction::{self, Model as ReactionModel};
+use crate::entities::room::permission::Permission;
+use crate::entities::room::report::{self, ReportReason, ReportStatus};
+use crate::entities::room::repositories::invite::{
+    CreationSchema as InviteCreationSchema, RoomInviteRepository,
+};
 use crate::entities::room::repositories::member::{
     CreationSchema as MemberCreationSchema, MemberRepository,
 };
@@ -13,18 +25,74 @@ use crate::entities::room::repositories::room::{
 use crate::entities::room::repositories::template::{
     CreationSchema as RoomTemplateCreationSchema, RoomTemplateRepository,
 };
-use crate::entities::room::room::Model as RoomModel;
-use crate::entities::room::template::{self, Model as RoomTemplateModel};
-use crate::error::DatabaseError;
+use crate::entities::room::room::{self, Model as RoomModel, RoomType, RoomVisibility};
+use crate::entities::room::scheduled_message::{
+    self, MAX_MESSAGE_TTL_MS, MAX_SCHEDULE_AHEAD_MS, Model as ScheduledMessageModel,
+    ScheduledMessageStatus,
+};
+use crate::entities::room::settings::{
+    self, MAX_MESSAGE_LENGTH, MAX_PINNED_MESSAGES, MAX_SLOW_MODE_INTERVAL_MS,
+    Model as RoomSettingsModel,
+};
+use crate::entities::room::ticket::{self, SlaPolicy, TicketStatus};
+use crate::entities::room::search::{
+    MAX_SEARCH_LIMIT, MAX_SEARCH_OFFSET, MessageSearchHit, SearchLanguage, TemplateSearchHit,
+    headline_options, prefix_tsquery,
+};
+use crate::entities::services::usage::{UsageRecordSchema, UsageService};
+use crate::entities::usage::usage_record::{self, UsageKind};
+use crate::entities::room::repositories::template_version::{
+    CreationSchema as TemplateVersionCreationSchema, RoomTemplateVersionRepository,
+};
+use crate::entities::room::template::{self, Model as RoomTemplateModel, TemplateVisibility};
+use crate::entities::room::template_seed::{self, Model as TemplateSeedModel};
+use crate::entities::room::template_version::{self, Model as TemplateVersionModel};
+use crate::error::{CadenceError, DatabaseError, ServerError};
+use crate::export::import::{ImportPlan, ImportReport, ImportedConversation, PlannedMember};
+use crate::export::{
+    AuthorKind, ExportWriter, ExportedAttachment, ExportedAuthor, ExportedMessage, ExportedRoom,
+};
+use crate::input_validation::is_valid_reaction;
+use crate::llm::{ChatMessage, ChatRole, Completion, CompletionRequest, ModelProvider};
+use crate::moderation::{ContentFilterPipeline, FilterAction, FilterVerdict};
 use crate::repository_traits::BasicApplicationService;
 use crate::repository_traits::CrudEntityRepository;
 use crate::time::now_millis;
-use crate::types::ID;
+use crate::types::{ID, Timestamp};
+use sea_orm::ActiveValue::Set;
 use sea_orm::QueryOrder;
 use sea_orm::QuerySelect;
 use sea_orm::prelude::*;
-use sea_orm::{Order, TransactionTrait};
+use sea_orm::sea_query::{Func, Query, SimpleExpr};
+use sea_orm::{
+    Condition, DbBackend, FromQueryResult, Iterable, Order, Statement, TransactionTrait, UpdateMany,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Maximum number of messages after the rolling summary loaded to build the context of
+/// a model room. Anything older is expected to be folded into the summary.
+pub const CONTEXT_HISTORY_LIMIT: u64 = 500;
+
+/// Number of messages read at once by `export_room`.
+pub const EXPORT_BATCH_SIZE: u64 = 500;
+
+/// Number of messages inserted at once by `import_room`.
+pub const IMPORT_BATCH_SIZE: usize = 500;
+
+/// Number of scheduled messages posted at once by `deliver_due_messages`.
+pub const MESSAGE_WORKER_BATCH_SIZE: u64 = 100;
+
+/// Maximum number of messages changed by a single `bulk_update_messages` call.
+pub const MAX_BULK_MESSAGES: u64 = 1000;
+
+const SUMMARY_INSTRUCTIONS: &str = "You maintain a running summary of a conversation. \
+Merge the previous summary and the new messages into a single concise summary that keeps \
+names, facts, decisions and open questions. Reply with the summary only.";
 
 /// # Room Service
 ///
@@ -36,7 +104,19 @@ pub struct RoomService {
     pub room_repository: RoomRepository,
     pub member_repository: MemberRepository,
     pub room_template_repository: RoomTemplateRepository,
+    pub room_template_version_repository: RoomTemplateVersionRepository,
     pub message_repository: MessageRepository,
+    pub room_invite_repository: RoomInviteRepository,
+    pub context_builder: ContextBuilder,
+    pub usage_service: UsageService,
+    /// Must match the language the search columns were created with.
+    pub search_language: SearchLanguage,
+    pub identity_generator: IdentityGenerator,
+    pub privacy_policy: PrivacyPolicy,
+    /// Checks the messages posted by members, see `add_message`.
+    pub content_filters: ContentFilterPipeline,
+    /// Deadlines of the tickets of support rooms.
+    pub sla_policy: SlaPolicy,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -45,6 +125,333 @@ pub struct RoomServiceCreationSchema {
     pub author: MemberCreationSchema,
 }
 
+/// # Room From Template Schema
+///
+/// Data needed to instantiate a room out of a template. The room name defaults to the
+/// template name, and the latest published version is used when `template_version_id`
+/// is omitted.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoomFromTemplateSchema {
+    pub template_id: ID,
+    pub template_version_id: Option<ID>,
+    pub account_id: ID,
+    pub anonymize: bool,
+    pub name: Option<String>,
+    pub room_type: RoomType,
+    pub visibility: RoomVisibility,
+}
+
+/// # Room Import Schema
+///
+/// Data needed to create a room out of an archive, see `import_room`. `name` and
+/// `model_tag` override those of the archive.
+///
+/// - `own_author`: display name of the archive author who is the importing account. Their
+///   messages are attributed to its membership rather than to a placeholder.
+/// - `dry_run`: only report what would be created.
+#[derive(Debug, Clone)]
+pub struct RoomImportSchema {
+    pub account_id: ID,
+    pub conversation: ImportedConversation,
+    pub name: Option<String>,
+    pub model_tag: Option<String>,
+    pub own_author: Option<String>,
+    pub room_type: RoomType,
+    pub visibility: RoomVisibility,
+    pub dry_run: bool,
+}
+
+/// # Scheduled Message Schema
+///
+/// A message of a member to be posted at `scheduled_for`, see `schedule_message`. The
+/// posted message self-destructs `ttl_ms` after it is posted.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScheduledMessageSchema {
+    pub room_id: ID,
+    pub account_id: ID,
+    pub content: Option<String>,
+    pub attachment_id: Option<ID>,
+    pub reply_to: Option<ID>,
+    pub scheduled_for: Timestamp,
+    pub ttl_ms: Option<i64>,
+}
+
+/// # Bulk Message Action
+///
+/// - `Hide` / `Unhide`: toggles `is_hidden`, requires `HideMessage`.
+/// - `Delete`: soft-deletes, requires `DeleteMessage`.
+/// - `Purge`: removes the messages for good along with their reactions and reports,
+///   deleted ones included. Only owners can purge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMessageAction {
+    #[default]
+    Hide,
+    Unhide,
+    Delete,
+    Purge,
+}
+
+impl BulkMessageAction {
+    fn past_tense(&self) -> &'static str {
+        match self {
+            BulkMessageAction::Hide => "Hid",
+            BulkMessageAction::Unhide => "Unhid",
+            BulkMessageAction::Delete => "Deleted",
+            BulkMessageAction::Purge => "Purged",
+        }
+    }
+}
+
+/// # Bulk Message Filter
+///
+/// Selects the messages of a bulk operation. The criteria are combined, and at least one
+/// is needed. `member_id` is a membership of the room, and the time range is inclusive.
+/// Only regular messages are selected, never system messages or summaries.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BulkMessageFilter {
+    pub message_ids: Option<Vec<ID>>,
+    pub member_id: Option<ID>,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+}
+
+impl BulkMessageFilter {
+    pub fn is_empty(&self) -> bool {
+        self.message_ids.is_none()
+            && self.member_id.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+    }
+}
+
+/// # Bulk Message Report
+///
+/// Outcome of `bulk_update_messages`. `count` messages were changed, or would be on a dry
+/// run, and `remaining` more match the filter beyond `MAX_BULK_MESSAGES`. `summary` is the
+/// system message posted about it.
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkMessageReport {
+    pub action: BulkMessageAction,
+    pub dry_run: bool,
+    pub count: u64,
+    pub remaining: u64,
+    pub summary: Option<MessageModel>,
+}
+
+/// # Room Service Update Schema
+///
+/// Fields of a room that can be edited. `None` keeps the current value.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoomServiceUpdateSchema {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon_url: Option<String>,
+    pub background_url: Option<String>,
+    pub visibility: Option<RoomVisibility>,
+    pub model_tag: Option<String>,
+    pub system_prompt: Option<String>,
+}
+
+/// # Room Template Update Schema
+///
+/// Fields of a template draft that can be edited. `None` keeps the current value.
+/// Published versions are never modified.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoomTemplateUpdateSchema {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model_tag: Option<String>,
+    pub system_prompt: Option<String>,
+    pub visibility: Option<TemplateVisibility>,
+}
+
+/// # Room Settings Update Schema
+///
+/// Settings of a room that can be edited. `None` keeps the current value, and `0` turns
+/// the slow mode or the message length limit off. Read-only members can't be given the
+/// right to pin messages.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoomSettingsUpdateSchema {
+    pub slow_mode_interval_ms: Option<i64>,
+    pub max_message_length: Option<i32>,
+    pub allow_replies: Option<bool>,
+    pub allow_attachments: Option<bool>,
+    pub announcement_mode: Option<bool>,
+    pub pin_role: Option<MemberRole>,
+    pub max_pinned_messages: Option<i32>,
+}
+
+/// # Room Message
+///
+/// A message as returned by `get_messages`, enriched with its aggregated reactions
+/// and the number of visible direct replies.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomMessage {
+    #[serde(flatten)]
+    pub message: MessageModel,
+    pub reactions: Vec<ReactionSummary>,
+    pub reply_count: i64,
+    /// Set when the message is hidden or deleted and only kept to hold its thread together.
+    /// Its content, attachment and reactions are stripped.
+    pub tombstone: bool,
+}
+
+/// # Message Page
+///
+/// A page of messages and, by member id, the identities of their authors and pinners as
+/// the viewer may see them. See `MessageResponse` for the privacy-aware DTO.
+#[derive(Debug, Clone)]
+pub struct MessagePage {
+    pub messages: Vec<RoomMessage>,
+    pub identities: HashMap<ID, MemberIdentity>,
+}
+
+/// # Thread Message
+///
+/// A reply inside a thread, with its depth relative to the root message (direct replies
+/// have a depth of 1).
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadMessage {
+    #[serde(flatten)]
+    pub message: RoomMessage,
+    pub depth: i32,
+}
+
+/// # Message Thread
+///
+/// A root message and a page of its (possibly nested) replies, oldest first, with the
+/// identities of the members they mention as in `MessagePage`.
+#[derive(Debug, Clone)]
+pub struct MessageThread {
+    pub root: RoomMessage,
+    pub replies: Vec<ThreadMessage>,
+    pub identities: HashMap<ID, MemberIdentity>,
+}
+
+/// # Reaction Summary
+///
+/// Number of members that reacted to a message with a given emoji, and whether the
+/// caller is one of them.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactionSummary {
+    pub emoji: String,
+    pub count: i64,
+    pub reacted: bool,
+}
+
+/// # Room Unread Count
+///
+/// Number of unread messages of a member in a room, and the creation time of the
+/// most recent of them.
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct RoomUnreadCount {
+    pub room_id: ID,
+    pub unread_count: i64,
+    pub last_message_at: Timestamp,
+}
+
+/// # Room Activity
+///
+/// A room as listed by `list_rooms_for_account`: its latest visible message, the number
+/// of messages the account hasn't read and its number of members who aren't banned.
+/// `last_activity_at` is the `last_message_at` of the room, which follows its latest
+/// visible message, or its creation time when it has no message.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomActivity {
+    #[serde(flatten)]
+    pub room: RoomModel,
+    pub last_activity_at: Timestamp,
+    pub last_message: Option<MessageModel>,
+    pub unread_count: i64,
+    pub member_count: i64,
+}
+
+/// # Room Activity Page
+///
+/// A page of `list_rooms_for_account`. `next_cursor` fetches the following page, `None`
+/// on the last one.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomActivityPage {
+    pub rooms: Vec<RoomActivity>,
+    pub next_cursor: Option<String>,
+}
+
+/// # Room Activity Cursor
+///
+/// Position in the list of `list_rooms_for_account`: the activity time and ID of the last
+/// room of the previous page, written as `<last_activity_at>_<room_id>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomActivityCursor {
+    pub last_activity_at: Timestamp,
+    pub room_id: ID,
+}
+
+impl RoomActivityCursor {
+    pub fn encode(&self) -> String {
+        format!("{}_{}", self.last_activity_at, self.room_id)
+    }
+
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let (last_activity_at, room_id) = cursor.split_once('_')?;
+        Some(RoomActivityCursor {
+            last_activity_at: last_activity_at.parse().ok()?,
+            room_id: uuid::Uuid::parse_str(room_id).ok()?,
+        })
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RoomActivityRow {
+    room_id: ID,
+    last_activity_at: Timestamp,
+    last_message_id: Option<ID>,
+    unread_count: i64,
+    member_count: i64,
+}
+
+/// # Direct Room
+///
+/// A direct room as listed by `get_direct_rooms`, with the other account of the pair and
+/// the time of its latest activity.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectRoom {
+    #[serde(flatten)]
+    pub room: RoomModel,
+    pub other_account_id: ID,
+    pub last_activity_at: Timestamp,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct DirectRoomActivity {
+    room_id: ID,
+    other_account_id: ID,
+    last_activity_at: Timestamp,
+}
+
+/// A seed message, either read from the source room of a template draft or from the
+/// seeds of a published version.
+#[derive(Debug, Clone)]
+pub(crate) struct SeedMessage {
+    pub(crate) model_tag: Option<String>,
+    pub(crate) content: Option<String>,
+    pub(crate) attachment_id: Option<ID>,
+    pub(crate) reply_to_position: Option<i32>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ReplyCount {
+    reply_to: ID,
+    count: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ReactionCount {
+    message_id: ID,
+    emoji: String,
+    count: i64,
+}
+
 /// # Account Service
 ///
 /// This service is responsible for managing accounts and their associations.
@@ -53,6 +460,12 @@ impl RoomService {
         &self,
         mut schema: RoomServiceCreationSchema,
     ) -> Result<(RoomModel, Vec<MemberModel>), DatabaseError> {
+        if schema.room.room_type == RoomType::Direct {
+            return Err(DatabaseError::ConstraintViolation(
+                "direct rooms are opened with get_or_create_direct_room".to_string(),
+            ));
+        }
+
         let txn = self.db().begin().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to start transaction".to_string())
         })?;
@@ -71,6 +484,8 @@ impl RoomService {
             .create_tx(&schema.room, &txn)
             .await
             .map_err(|_| DatabaseError::InsertionError("room".to_string()))?;
+        self.open_ticket_tx(&room, schema.author.account_id, &txn)
+            .await?;
 
         schema.author.room_id = room.id;
 
@@ -87,28 +502,62 @@ impl RoomService {
         Ok((room, vec![member]))
     }
 
-    pub async fn delete_room(
+    /// ## Get Or Create Direct Room
+    ///
+    /// Returns the `Direct` room of the two accounts, creating it the first time. Asking
+    /// again, in either order, returns the same room, and accounts who left it are brought
+    /// back. Concurrent calls can't create two rooms: the `direct_room` pair key lets a
+    /// single one through and the others return it.
+    pub async fn get_or_create_direct_room(
         &self,
-        room_id: ID,
-        trigger_account_id: Option<ID>,
+        account_id: ID,
+        other_account_id: ID,
     ) -> Result<RoomModel, DatabaseError> {
+        if account_id == other_account_id {
+            return Err(DatabaseError::ConstraintViolation(
+                "a direct room needs two different accounts".to_string(),
+            ));
+        }
+        let (first_account_id, second_account_id) = direct_room::pair(account_id, other_account_id);
+
+        if let Some(room) = self
+            .find_or_restore_direct_room(first_account_id, second_account_id)
+            .await?
+        {
+            return Ok(room);
+        }
+
+        match self
+            .create_direct_room(first_account_id, second_account_id)
+            .await?
+        {
+            Some(room) => Ok(room),
+            // someone else created it in between
+            None => self
+                .find_or_restore_direct_room(first_account_id, second_account_id)
+                .await?
+                .ok_or_else(|| DatabaseError::InsertionError("direct room".to_string())),
+        }
+    }
+
+    /// The room of an ordered pair of accounts, with the accounts who left it brought back,
+    /// in a single transaction.
+    async fn find_or_restore_direct_room(
+        &self,
+        first_account_id: ID,
+        second_account_id: ID,
+    ) -> Result<Option<RoomModel>, DatabaseError> {
         let txn = self.db().begin().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to start transaction".to_string())
         })?;
 
-        if let Some(account_id) = trigger_account_id {
-            if !self.has_room_ownership(room_id, account_id).await? {
-                return Err(DatabaseError::ConstraintViolation(
-                    "trigger account_id is not owner".to_string(),
-                ));
-            }
-        }
-
         let room = self
-            .room_repository
-            .delete_tx(room_id, &txn)
-            .await
-            .map_err(|_| DatabaseError::DeletionError("room".to_string()))?;
+            .find_direct_room(first_account_id, second_account_id, &txn)
+            .await?;
+        if let Some(room) = &room {
+            self.restore_direct_memberships(room, [first_account_id, second_account_id], &txn)
+                .await?;
+        }
 
         txn.commit().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
@@ -117,139 +566,575 @@ impl RoomService {
         Ok(room)
     }
 
-    pub async fn get_member_by_account_id(
+    /// The room of an ordered pair of accounts. The pair of a deleted room is released so a
+    /// new room can be created.
+    async fn find_direct_room(
         &self,
-        room_id: ID,
-        account_id: ID,
-    ) -> Result<Option<MemberModel>, DatabaseError> {
-        let member = MemberEntity::find()
-            .filter(member::Column::RoomId.eq(room_id))
-            .filter(member::Column::AccountId.eq(account_id))
-            .one(self.db())
-            .await
-            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+        first_account_id: ID,
+        second_account_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<Option<RoomModel>, DatabaseError> {
+        let Some((pair, room)) =
+            direct_room::Entity::find_by_id((first_account_id, second_account_id))
+                .find_also_related(room::Entity)
+                .one(txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("direct room".to_string()))?
+        else {
+            return Ok(None);
+        };
 
-        if let Some(ref member) = member {
-            if member.deleted_at.is_some() {
-                return Ok(None);
+        match room {
+            Some(room) if room.deleted_at.is_none() => Ok(Some(room)),
+            _ => {
+                direct_room::Entity::delete_many()
+                    .filter(direct_room::Column::FirstAccountId.eq(first_account_id))
+                    .filter(direct_room::Column::SecondAccountId.eq(second_account_id))
+                    .filter(direct_room::Column::RoomId.eq(pair.room_id))
+                    .exec(txn)
+                    .await
+                    .map_err(|_| DatabaseError::DeletionError("direct room".to_string()))?;
+                Ok(None)
             }
         }
-
-        Ok(member)
     }
 
-    pub async fn add_member(
+    /// Creates the room of an ordered pair of accounts, or returns `None` when the pair was
+    /// taken by a concurrent call.
+    async fn create_direct_room(
         &self,
-        room_id: ID,
-        trigger_account_id: Option<ID>,
-        account_id: ID,
-        anonymize: bool,
-    ) -> Result<MemberModel, DatabaseError> {
+        first_account_id: ID,
+        second_account_id: ID,
+    ) -> Result<Option<RoomModel>, DatabaseError> {
         let txn = self.db().begin().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to start transaction".to_string())
         })?;
 
-        if !self
-            .room_repository
-            .exists_tx(room_id, &txn)
-            .await
-            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?.0
-        {
-            return Err(DatabaseError::RecordNotFound("room".to_string()));
-        }
-
-        if let Some(account_id) = trigger_account_id {
-            if !self.has_room_ownership(room_id, account_id).await? {
-                return Err(DatabaseError::ConstraintViolation(
-                    "trigger account_id is not owner".to_string(),
-                ));
+        for account_id in [first_account_id, second_account_id] {
+            if !self
+                .account_repository
+                .exists_tx(account_id, &txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?
+                .0
+            {
+                return Err(DatabaseError::RecordNotFound("account".to_string()));
             }
         }
 
-        if !self
-            .account_repository
-            .exists_tx(account_id, &txn)
-            .await
-            .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?.0
-        {
-            return Err(DatabaseError::RecordNotFound("account".to_string()));
-        }
-
-        let member = self
-            .member_repository
+        let room = self
+            .room_repository
             .create_tx(
-                &MemberCreationSchema {
-                    room_id,
-                    account_id,
-                    is_owner: false,
-                    anonymize,
+                &RoomCreationSchema {
+                    name: None,
+                    description: None,
+                    icon_url: None,
+                    background_url: None,
+                    visibility: RoomVisibility::Private,
+                    template_id: None,
+                    template_version_id: None,
+                    model_tag: None,
+                    system_prompt: None,
+                    room_type: RoomType::Direct,
                 },
                 &txn,
             )
             .await
-            .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
+            .map_err(|_| DatabaseError::InsertionError("room".to_string()))?;
+
+        let inserted = direct_room::ActiveModel {
+            first_account_id: Set(first_account_id),
+            second_account_id: Set(second_account_id),
+            room_id: Set(room.id),
+            created_at: Set(now_millis()),
+        }
+        .insert(&txn)
+        .await;
+        match inserted {
+            Ok(_) => {}
+            // the transaction is dropped with the room it created
+            Err(e)
+                if e.sql_err()
+                    .is_some_and(|e| direct_room::is_pair_conflict(&e)) =>
+            {
+                return Ok(None);
+            }
+            Err(_) => return Err(DatabaseError::InsertionError("direct room".to_string())),
+        }
+
+        // neither account owns the room, they can only post in it
+        for account_id in [first_account_id, second_account_id] {
+            self.member_repository
+                .create_tx(
+                    &MemberCreationSchema {
+                        room_id: room.id,
+                        account_id,
+                        role: MemberRole::Member,
+                        anonymize: false,
+                    },
+                    &txn,
+                )
+                .await
+                .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
+        }
 
         txn.commit().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
         })?;
 
-        Ok(member)
+        Ok(Some(room))
     }
 
-    // soft delete (repository already does this)
-    pub async fn remove_member(
+    /// Adds back the accounts of a direct room who left it.
+    async fn restore_direct_memberships(
         &self,
-        room_id: ID,
-        trigger_account_id: Option<ID>,
-        account_id: ID,
-    ) -> Result<MemberModel, DatabaseError> {
-        let txn = self.db().begin().await.map_err(|_| {
-            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
-        })?;
-
-        if let Some(account_id) = trigger_account_id {
-            if !self.has_room_ownership(room_id, account_id).await? {
-                return Err(DatabaseError::ConstraintViolation(
-                    "trigger account_id is not owner".to_string(),
-                ));
+        room: &RoomModel,
+        account_ids: [ID; 2],
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<(), DatabaseError> {
+        for account_id in account_ids {
+            if MemberEntity::find()
+                .filter(member::Column::RoomId.eq(room.id))
+                .filter(member::Column::AccountId.eq(account_id))
+                .filter(member::Column::DeletedAt.is_null())
+                .one(txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?
+                .is_none()
+            {
+                self.member_repository
+                    .create_tx(
+                        &MemberCreationSchema {
+                            room_id: room.id,
+                            account_id,
+                            role: MemberRole::Member,
+                            anonymize: false,
+                        },
+                        txn,
+                    )
+                    .await
+                    .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
             }
         }
+        Ok(())
+    }
 
-        let target_membership = self
-            .get_member_by_account_id(room_id, account_id)
-            .await
-            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?
-            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
-
-        if target_membership.is_owner {
+    /// ## Get Direct Rooms
+    ///
+    /// The direct rooms of the account it is still a member of, with the other account of
+    /// each, most recently active first. The activity of a room is its `last_message_at`,
+    /// or its creation when it has none.
+    pub async fn get_direct_rooms(
+        &self,
+        account_id: ID,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<DirectRoom>, DatabaseError> {
+        if limit > 100 {
             return Err(DatabaseError::ConstraintViolation(
-                "cannot remove owner".to_string(),
+                "limit must be less than 100".to_string(),
+            ));
+        }
+        if offset > 1000 {
+            return Err(DatabaseError::ConstraintViolation(
+                "offset must be less than 1000".to_string(),
+            ));
+        }
+        if limit == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be greater than 0".to_string(),
             ));
         }
 
-        let member = self
-            .member_repository
-            .delete_tx(target_membership.id, &txn)
+        let statement = Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            r#"
+            SELECT dr.room_id AS room_id,
+                   CASE WHEN dr.first_account_id = $1
+                        THEN dr.second_account_id
+                        ELSE dr.first_account_id END AS other_account_id,
+                   COALESCE(r.last_message_at, r.created_at) AS last_activity_at
+            FROM direct_room dr
+            INNER JOIN room r ON r.id = dr.room_id AND r.deleted_at IS NULL
+            INNER JOIN member mb ON mb.room_id = dr.room_id
+                AND mb.account_id = $1
+                AND mb.deleted_at IS NULL
+            WHERE dr.first_account_id = $1 OR dr.second_account_id = $1
+            ORDER BY last_activity_at DESC, dr.room_id
+            LIMIT $2 OFFSET $3
+            "#,
+            [
+                account_id.into(),
+                (limit as i64).into(),
+                (offset as i64).into(),
+            ],
+        );
+
+        let activities = DirectRoomActivity::find_by_statement(statement)
+            .all(self.db())
             .await
-            .map_err(|_| DatabaseError::DeletionError("member".to_string()))?;
+            .map_err(|_| DatabaseError::QueryFailed("direct rooms".to_string()))?;
 
-        txn.commit().await.map_err(|_| {
-            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
-        })?;
+        let mut rooms: HashMap<ID, RoomModel> = room::Entity::find()
+            .filter(room::Column::Id.is_in(activities.iter().map(|a| a.room_id)))
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("rooms".to_string()))?
+            .into_iter()
+            .map(|room| (room.id, room))
+            .collect();
 
-        Ok(member)
+        Ok(activities
+            .into_iter()
+            .filter_map(|activity| {
+                rooms.remove(&activity.room_id).map(|room| DirectRoom {
+                    room,
+                    other_account_id: activity.other_account_id,
+                    last_activity_at: activity.last_activity_at,
+                })
+            })
+            .collect())
     }
 
-    pub async fn save_template(
+    /// ## Create Room From Template
+    ///
+    /// Creates a room out of a published version of the template, with the account as owner.
+    /// The room copies the `model_tag` and `system_prompt` of the version, records the
+    /// `template_id` and `template_version_id` it was built from, and receives a copy of the
+    /// version seed messages keeping their order and reply references.
+    ///
+    /// The author of a template that was never published can still try it out: the room is
+    /// then built from the draft and the current messages of its `source_room_id`.
+    pub async fn create_room_from_template(
         &self,
-        schema: RoomTemplateCreationSchema,
-    ) -> Result<RoomTemplateModel, DatabaseError> {
+        schema: RoomFromTemplateSchema,
+    ) -> Result<(RoomModel, MemberModel, Vec<MessageModel>), DatabaseError> {
+        if schema.room_type == RoomType::Direct {
+            return Err(DatabaseError::ConstraintViolation(
+                "direct rooms are opened with get_or_create_direct_room".to_string(),
+            ));
+        }
+
         let txn = self.db().begin().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to start transaction".to_string())
         })?;
 
-        if let Some(source_room_id) = schema.source_room_id {
-            if !self
+        let (exists, template) = self
+            .room_template_repository
+            .exists_tx(schema.template_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("template".to_string()))?;
+
+        let template = template
+            .filter(|t| exists && can_use_template(t, Some(schema.account_id)))
+            .ok_or_else(|| DatabaseError::RecordNotFound("template".to_string()))?;
+
+        if !self
+            .account_repository
+            .exists_tx(schema.account_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?
+            .0
+        {
+            return Err(DatabaseError::RecordNotFound("account".to_string()));
+        }
+
+        let version = match schema.template_version_id {
+            Some(version_id) => Some(
+                template_version::Entity::find_by_id(version_id)
+                    .filter(template_version::Column::TemplateId.eq(template.id))
+                    .filter(template_version::Column::DeletedAt.is_null())
+                    .one(&txn)
+                    .await
+                    .map_err(|_| DatabaseError::QueryFailed("template version".to_string()))?
+                    .ok_or_else(|| DatabaseError::RecordNotFound("template version".to_string()))?,
+            ),
+            None if template.latest_version > 0 => {
+                Some(self.get_latest_template_version_tx(&template, &txn).await?)
+            }
+            None if template.author_id == Some(schema.account_id) => None,
+            None => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "template has no published version".to_string(),
+                ));
+            }
+        };
+
+        let seeds = match (&version, template.source_room_id) {
+            (Some(version), _) => self.get_version_seed_messages_tx(version.id, &txn).await?,
+            (None, Some(source_room_id)) => {
+                self.get_room_seed_messages_tx(source_room_id, &txn).await?
+            }
+            (None, None) => Vec::new(),
+        };
+
+        let room = self
+            .room_repository
+            .create_tx(
+                &template_room_schema(&template, version.as_ref(), &schema),
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("room".to_string()))?;
+        self.open_ticket_tx(&room, schema.account_id, &txn).await?;
+
+        let member = self
+            .member_repository
+            .create_tx(
+                &MemberCreationSchema {
+                    room_id: room.id,
+                    account_id: schema.account_id,
+                    role: MemberRole::Owner,
+                    anonymize: schema.anonymize,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
+
+        let copies = seed_message_copies(room.id, seeds, now_millis());
+        let mut seed_messages: Vec<MessageModel> = Vec::with_capacity(copies.len());
+        for (copy, attachment_id) in copies {
+            let mut message = copy
+                .insert(&txn)
+                .await
+                .map_err(|_| DatabaseError::InsertionError("message".to_string()))?;
+
+            // the copy references the message, so it can only be made once it exists
+            if let Some(attachment_id) = attachment_id
+                && let Some(copy_id) = self
+                    .clone_attachment_tx(attachment_id, room.id, message.id, &txn)
+                    .await?
+            {
+                message = message::ActiveModel {
+                    id: Set(message.id),
+                    attachment_id: Set(Some(copy_id)),
+                    ..Default::default()
+                }
+                .update(&txn)
+                .await
+                .map_err(|_| DatabaseError::UpdateError("message".to_string()))?;
+            }
+
+            seed_messages.push(message);
+        }
+        if let Some(last) = seed_messages.last() {
+            self.touch_room_tx(room.id, last.created_at, &txn).await?;
+        }
+
+        template::Entity::update_many()
+            .col_expr(
+                template::Column::UsageCount,
+                Expr::col(template::Column::UsageCount).add(1),
+            )
+            .filter(template::Column::Id.eq(template.id))
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("template".to_string()))?;
+
+        if let Some(version) = &version {
+            template_version::Entity::update_many()
+                .col_expr(
+                    template_version::Column::UsageCount,
+                    Expr::col(template_version::Column::UsageCount).add(1),
+                )
+                .filter(template_version::Column::Id.eq(version.id))
+                .exec(&txn)
+                .await
+                .map_err(|_| DatabaseError::UpdateError("template version".to_string()))?;
+        }
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok((room, member, seed_messages))
+    }
+
+    pub async fn delete_room(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+    ) -> Result<RoomModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        if let Some(account_id) = trigger_account_id {
+            self.authorize(room_id, account_id, Permission::DeleteRoom)
+                .await?;
+        }
+
+        let room = self
+            .room_repository
+            .delete_tx(room_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("room".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(room)
+    }
+
+    pub async fn get_member_by_account_id(
+        &self,
+        room_id: ID,
+        account_id: ID,
+    ) -> Result<Option<MemberModel>, DatabaseError> {
+        self.get_member_by_account_id_tx(room_id, account_id, self.db())
+            .await
+    }
+
+    async fn get_member_by_account_id_tx(
+        &self,
+        room_id: ID,
+        account_id: ID,
+        txn: &impl ConnectionTrait,
+    ) -> Result<Option<MemberModel>, DatabaseError> {
+        // an account has a row per past membership
+        let member = MemberEntity::find()
+            .filter(member::Column::RoomId.eq(room_id))
+            .filter(member::Column::AccountId.eq(account_id))
+            .filter(member::Column::DeletedAt.is_null())
+            .one(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+
+        Ok(member)
+    }
+
+    pub async fn add_member(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+        account_id: ID,
+        anonymize: bool,
+    ) -> Result<MemberModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let (exists, room) = self
+            .room_repository
+            .exists_tx(room_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?;
+        if !exists {
+            return Err(DatabaseError::RecordNotFound("room".to_string()));
+        }
+        if room.is_some_and(|room| room.room_type == RoomType::Direct) {
+            return Err(DatabaseError::ConstraintViolation(
+                "direct rooms only have their two accounts".to_string(),
+            ));
+        }
+
+        if let Some(account_id) = trigger_account_id {
+            self.authorize(room_id, account_id, Permission::AddMember)
+                .await?;
+        }
+
+        if !self
+            .account_repository
+            .exists_tx(account_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?.0
+        {
+            return Err(DatabaseError::RecordNotFound("account".to_string()));
+        }
+
+        let member = self
+            .member_repository
+            .create_tx(
+                &MemberCreationSchema {
+                    room_id,
+                    account_id,
+                    role: MemberRole::Member,
+                    anonymize,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    // soft delete (repository already does this)
+    pub async fn remove_member(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+        account_id: ID,
+    ) -> Result<MemberModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let trigger_membership = match trigger_account_id {
+            Some(account_id) => Some(
+                self.authorize(room_id, account_id, Permission::RemoveMember)
+                    .await?,
+            ),
+            None => None,
+        };
+
+        let target_membership = self
+            .get_member_by_account_id(room_id, account_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+
+        if target_membership.role == MemberRole::Owner {
+            return Err(DatabaseError::ConstraintViolation(
+                "cannot remove owner".to_string(),
+            ));
+        }
+
+        if trigger_membership.is_some_and(|trigger| !trigger.role.outranks(&target_membership.role))
+        {
+            return Err(DatabaseError::ConstraintViolation(
+                "cannot remove a member with an equal or higher role".to_string(),
+            ));
+        }
+
+        let member = self
+            .member_repository
+            .delete_tx(target_membership.id, &txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("member".to_string()))?;
+
+        self.post_system_message_tx(
+            room_id,
+            Some(member.id),
+            MessageType::RecipientRemoved,
+            None,
+            &txn,
+        )
+        .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    pub async fn save_template(
+        &self,
+        schema: RoomTemplateCreationSchema,
+    ) -> Result<RoomTemplateModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        if let Some(source_room_id) = schema.source_room_id {
+            if !self
                 .room_repository
                 .exists_tx(source_room_id, &txn)
                 .await
@@ -257,6 +1142,12 @@ impl RoomService {
             {
                 return Err(DatabaseError::RecordNotFound("room".to_string()));
             }
+
+            // building a template out of a room requires managing the room templates
+            if let Some(author_id) = schema.author_id {
+                self.authorize(source_room_id, author_id, Permission::ManageTemplates)
+                    .await?;
+            }
         }
 
         let room_template = self
@@ -302,45 +1193,509 @@ impl RoomService {
         Ok(room_template)
     }
 
-    pub async fn add_message(
+    /// ## Get Template
+    ///
+    /// Returns a template if the caller can see it: private templates are only visible to
+    /// their author, unlisted and public ones to everyone.
+    pub async fn get_template(
         &self,
-        mut schema: MessageCreationSchema,
-    ) -> Result<MessageModel, DatabaseError> {
-        match (&schema.message_type, &schema.system) {
-            (MessageType::Default, true) => {
-                return Err(DatabaseError::ConstraintViolation(
-                    "system message type must be system".to_string(),
-                ));
-            }
-            (MessageType::RecipientAdded, false) => {
-                return Err(DatabaseError::ConstraintViolation(
-                    "recipient added message type must be system".to_string(),
-                ));
-            }
-            (MessageType::RecipientRemoved, false) => {
-                return Err(DatabaseError::ConstraintViolation(
-                    "recipient removed message type must be system".to_string(),
-                ));
-            }
-            (MessageType::Default, false) => {}
-            (MessageType::RecipientAdded, true) => {}
-            (MessageType::RecipientRemoved, true) => {}
-        }
-
-        let txn = self.db().begin().await.map_err(|_| {
-            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
-        })?;
-
-        if !self
-            .room_repository
-            .exists_tx(schema.room_id, &txn)
+        template_id: ID,
+        trigger_account_id: Option<ID>,
+    ) -> Result<RoomTemplateModel, DatabaseError> {
+        self.room_template_repository
+            .get_by_id(template_id)
             .await
-            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?.0
+            .map_err(|_| DatabaseError::QueryFailed("template".to_string()))?
+            .filter(|t| t.deleted_at.is_none() && can_use_template(t, trigger_account_id))
+            .ok_or_else(|| DatabaseError::RecordNotFound("template".to_string()))
+    }
+
+    /// ## Update Template
+    ///
+    /// Edits the draft of a template and its visibility. Already published versions, and
+    /// the rooms pinned to them, are not affected until a new version is published.
+    pub async fn update_template(
+        &self,
+        template_id: ID,
+        trigger_account_id: Option<ID>,
+        schema: RoomTemplateUpdateSchema,
+    ) -> Result<RoomTemplateModel, DatabaseError> {
+        if let Some(account_id) = trigger_account_id
+            && !self.has_template_ownership(template_id, account_id).await?
         {
-            return Err(DatabaseError::RecordNotFound("room".to_string()));
+            return Err(DatabaseError::ConstraintViolation(
+                "trigger account_id is not owner".to_string(),
+            ));
         }
 
-        // if there is a author, check that the author is a member of the room
+        let mut active_model = template::ActiveModel {
+            ..Default::default()
+        };
+        if let Some(name) = schema.name {
+            active_model.name = Set(Some(name));
+        }
+        if let Some(description) = schema.description {
+            active_model.description = Set(Some(description));
+        }
+        if let Some(model_tag) = schema.model_tag {
+            active_model.model_tag = Set(model_tag);
+        }
+        if let Some(system_prompt) = schema.system_prompt {
+            active_model.system_prompt = Set(Some(system_prompt));
+        }
+        if let Some(visibility) = schema.visibility {
+            active_model.visibility = Set(visibility);
+        }
+
+        self.room_template_repository
+            .update(template_id, active_model)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("template".to_string()))
+    }
+
+    /// ## Publish Template
+    ///
+    /// Freezes the current draft of the template into a new immutable version, numbered
+    /// after the latest one. The seed messages are copied from the visible messages of the
+    /// `source_room_id`, or from the version the template was forked from when it has no
+    /// source room.
+    pub async fn publish_template(
+        &self,
+        template_id: ID,
+        trigger_account_id: Option<ID>,
+    ) -> Result<(TemplateVersionModel, Vec<TemplateSeedModel>), DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let (exists, template) = self
+            .room_template_repository
+            .exists_tx(template_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("template".to_string()))?;
+
+        let template = template
+            .filter(|_| exists)
+            .ok_or_else(|| DatabaseError::RecordNotFound("template".to_string()))?;
+
+        if trigger_account_id.is_some_and(|account_id| template.author_id != Some(account_id)) {
+            return Err(DatabaseError::ConstraintViolation(
+                "trigger account_id is not owner".to_string(),
+            ));
+        }
+
+        // only bump the version if nobody published in between, the unique
+        // (template_id, version) index backs this up
+        let version_number = template.latest_version + 1;
+        let result = template::Entity::update_many()
+            .col_expr(template::Column::LatestVersion, Expr::value(version_number))
+            .col_expr(template::Column::UpdatedAt, Expr::value(now_millis()))
+            .filter(template::Column::Id.eq(template.id))
+            .filter(template::Column::LatestVersion.eq(template.latest_version))
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("template".to_string()))?;
+
+        if result.rows_affected == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "template was published concurrently".to_string(),
+            ));
+        }
+
+        let version = self
+            .room_template_version_repository
+            .create_tx(
+                &TemplateVersionCreationSchema {
+                    template_id: template.id,
+                    version: version_number,
+                    published_by: trigger_account_id,
+                    name: template.name.clone(),
+                    description: template.description.clone(),
+                    model_tag: template.model_tag.clone(),
+                    system_prompt: template.system_prompt.clone(),
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("template version".to_string()))?;
+
+        let seeds = match (template.source_room_id, template.forked_from_version_id) {
+            (Some(source_room_id), _) => {
+                self.get_room_seed_messages_tx(source_room_id, &txn).await?
+            }
+            (None, Some(forked_version_id)) => {
+                self.get_version_seed_messages_tx(forked_version_id, &txn)
+                    .await?
+            }
+            (None, None) => Vec::new(),
+        };
+
+        let created_at = now_millis();
+        let mut template_seeds = Vec::with_capacity(seeds.len());
+        for (position, seed) in seeds.into_iter().enumerate() {
+            let template_seed = template_seed::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                version_id: Set(version.id),
+                position: Set(position as i32),
+                model_tag: Set(seed.model_tag),
+                content: Set(seed.content),
+                attachment_id: Set(seed.attachment_id),
+                reply_to_position: Set(seed.reply_to_position),
+                created_at: Set(created_at),
+                updated_at: Set(created_at),
+            }
+            .insert(&txn)
+            .await
+            .map_err(|_| DatabaseError::InsertionError("template seed".to_string()))?;
+
+            template_seeds.push(template_seed);
+        }
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok((version, template_seeds))
+    }
+
+    /// ## Get Template Versions
+    ///
+    /// Lists the published versions of a template visible to the caller, newest first.
+    pub async fn get_template_versions(
+        &self,
+        template_id: ID,
+        trigger_account_id: Option<ID>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<TemplateVersionModel>, DatabaseError> {
+        if limit == 0 || limit > 50 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be between 1 and 50".to_string(),
+            ));
+        }
+
+        let template = self.get_template(template_id, trigger_account_id).await?;
+
+        let versions = template_version::Entity::find()
+            .filter(template_version::Column::TemplateId.eq(template.id))
+            .filter(template_version::Column::DeletedAt.is_null())
+            .order_by(template_version::Column::Version, Order::Desc)
+            .limit(limit)
+            .offset(offset)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("template versions".to_string()))?;
+
+        Ok(versions)
+    }
+
+    /// ## Fork Template
+    ///
+    /// Copies the latest published version of someone else's public or unlisted template
+    /// into a new private draft owned by the account. The fork keeps a link to the original
+    /// template and version, and inherits its seed messages when published.
+    pub async fn fork_template(
+        &self,
+        template_id: ID,
+        account_id: ID,
+    ) -> Result<RoomTemplateModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let (exists, template) = self
+            .room_template_repository
+            .exists_tx(template_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("template".to_string()))?;
+
+        let template = template
+            .filter(|t| exists && can_use_template(t, Some(account_id)))
+            .ok_or_else(|| DatabaseError::RecordNotFound("template".to_string()))?;
+
+        if template.author_id == Some(account_id) {
+            return Err(DatabaseError::ConstraintViolation(
+                "cannot fork your own template".to_string(),
+            ));
+        }
+        if template.visibility == TemplateVisibility::Private {
+            return Err(DatabaseError::ConstraintViolation(
+                "private templates cannot be forked".to_string(),
+            ));
+        }
+        if template.latest_version == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "template has no published version".to_string(),
+            ));
+        }
+
+        if !self
+            .account_repository
+            .exists_tx(account_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?
+            .0
+        {
+            return Err(DatabaseError::RecordNotFound("account".to_string()));
+        }
+
+        let version = self.get_latest_template_version_tx(&template, &txn).await?;
+
+        let mut fork =
+            self.room_template_repository
+                .schema_to_active_model(RoomTemplateCreationSchema {
+                    author_id: Some(account_id),
+                    model_tag: version.model_tag.clone(),
+                    source_room_id: None,
+                    name: version.name.clone(),
+                    description: version.description.clone(),
+                    system_prompt: version.system_prompt.clone(),
+                    visibility: TemplateVisibility::Private,
+                });
+        fork.forked_from_id = Set(Some(template.id));
+        fork.forked_from_version_id = Set(Some(version.id));
+
+        let fork = fork
+            .insert(&txn)
+            .await
+            .map_err(|_| DatabaseError::InsertionError("template".to_string()))?;
+
+        template::Entity::update_many()
+            .col_expr(
+                template::Column::ForkCount,
+                Expr::col(template::Column::ForkCount).add(1),
+            )
+            .filter(template::Column::Id.eq(template.id))
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("template".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(fork)
+    }
+
+    async fn get_latest_template_version_tx(
+        &self,
+        template: &RoomTemplateModel,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<TemplateVersionModel, DatabaseError> {
+        template_version::Entity::find()
+            .filter(template_version::Column::TemplateId.eq(template.id))
+            .filter(template_version::Column::Version.eq(template.latest_version))
+            .one(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("template version".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("template version".to_string()))
+    }
+
+    /// Reads the visible messages of a room, oldest first, as seed messages. Replies to
+    /// messages that are not part of the seeds are dropped.
+    async fn get_room_seed_messages_tx(
+        &self,
+        room_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<Vec<SeedMessage>, DatabaseError> {
+        let messages = message::Entity::find()
+            .filter(message::Column::RoomId.eq(room_id))
+            .filter(message::Column::MessageType.eq(MessageType::Default))
+            .filter(message::Column::IsHidden.eq(false))
+            .filter(message::Column::DeletedAt.is_null())
+            .order_by(message::Column::CreatedAt, Order::Asc)
+            .all(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+
+        let positions: HashMap<ID, i32> = messages
+            .iter()
+            .enumerate()
+            .map(|(position, m)| (m.id, position as i32))
+            .collect();
+
+        Ok(messages
+            .into_iter()
+            .map(|m| SeedMessage {
+                reply_to_position: m.reply_to.and_then(|r| positions.get(&r).copied()),
+                model_tag: m.model_tag,
+                content: m.content,
+                attachment_id: m.attachment_id,
+            })
+            .collect())
+    }
+
+    async fn get_version_seed_messages_tx(
+        &self,
+        version_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<Vec<SeedMessage>, DatabaseError> {
+        let seeds = template_seed::Entity::find()
+            .filter(template_seed::Column::VersionId.eq(version_id))
+            .order_by(template_seed::Column::Position, Order::Asc)
+            .all(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("template seeds".to_string()))?;
+
+        Ok(seeds
+            .into_iter()
+            .map(|seed| SeedMessage {
+                model_tag: seed.model_tag,
+                content: seed.content,
+                attachment_id: seed.attachment_id,
+                reply_to_position: seed.reply_to_position,
+            })
+            .collect())
+    }
+
+    /// ## Add Message
+    ///
+    /// Posts a message in the room. The content of messages written by members goes
+    /// through the `content_filters` first: a rejected message fails with
+    /// `ConstraintViolation`, and a message to hide is posted hidden along with a report
+    /// queueing it for review. Messages of members must also follow the settings of the
+    /// room, see `settings::Model::check_message`. A message with an `expires_at` is
+    /// soft-deleted by `expire_messages` once it is past.
+    pub async fn add_message(
+        &self,
+        schema: MessageCreationSchema,
+    ) -> Result<MessageModel, DatabaseError> {
+        let (schema, verdict) = self.check_message(schema).await?;
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let message = self.insert_message_tx(schema, verdict, &txn).await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(message)
+    }
+
+    /// Checks the type of a new message and runs its content through the
+    /// `content_filters`, returning the verdict of the filters when it is to be hidden.
+    async fn check_message(
+        &self,
+        mut schema: MessageCreationSchema,
+    ) -> Result<(MessageCreationSchema, Option<FilterVerdict>), DatabaseError> {
+        match (&schema.message_type, &schema.system) {
+            (MessageType::Default, true) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "system message type must be system".to_string(),
+                ));
+            }
+            (MessageType::RecipientAdded, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "recipient added message type must be system".to_string(),
+                ));
+            }
+            (MessageType::RecipientRemoved, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "recipient removed message type must be system".to_string(),
+                ));
+            }
+            (MessageType::RecipientBanned, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "recipient banned message type must be system".to_string(),
+                ));
+            }
+            (MessageType::RecipientUnbanned, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "recipient unbanned message type must be system".to_string(),
+                ));
+            }
+            (MessageType::RecipientLeft, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "recipient left message type must be system".to_string(),
+                ));
+            }
+            (MessageType::TicketUpdated, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "ticket updated message type must be system".to_string(),
+                ));
+            }
+            (MessageType::MessagesModerated, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "messages moderated message type must be system".to_string(),
+                ));
+            }
+            (MessageType::MessagePinned, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "message pinned message type must be system".to_string(),
+                ));
+            }
+            (MessageType::MessageUnpinned, false) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "message unpinned message type must be system".to_string(),
+                ));
+            }
+            (MessageType::Summary, _) => {
+                return Err(DatabaseError::ConstraintViolation(
+                    "summary messages are generated by the room model".to_string(),
+                ));
+            }
+            (MessageType::Default, false) => {}
+            (MessageType::RecipientAdded, true) => {}
+            (MessageType::RecipientRemoved, true) => {}
+            (MessageType::RecipientBanned, true) => {}
+            (MessageType::RecipientUnbanned, true) => {}
+            (MessageType::RecipientLeft, true) => {}
+            (MessageType::TicketUpdated, true) => {}
+            (MessageType::MessagesModerated, true) => {}
+            (MessageType::MessagePinned, true) => {}
+            (MessageType::MessageUnpinned, true) => {}
+        }
+
+        if schema
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now_millis())
+        {
+            return Err(DatabaseError::ConstraintViolation(
+                "message expiry must be in the future".to_string(),
+            ));
+        }
+
+        let verdict = match (&schema.content, schema.member_id, schema.system) {
+            (Some(content), Some(_), false) => self.content_filters.check(content).await,
+            _ => None,
+        };
+        if let Some(ref verdict) = verdict {
+            match verdict.action {
+                FilterAction::Reject => {
+                    return Err(DatabaseError::ConstraintViolation(format!(
+                        "message rejected by content filter {}",
+                        verdict
+                    )));
+                }
+                FilterAction::Hide => schema.is_hidden = true,
+            }
+        }
+
+        Ok((schema, verdict))
+    }
+
+    /// Inserts a message checked by `check_message`, after checking its author, reply,
+    /// attachment and the settings of the room.
+    async fn insert_message_tx(
+        &self,
+        mut schema: MessageCreationSchema,
+        verdict: Option<FilterVerdict>,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<MessageModel, DatabaseError> {
+        if !self
+            .room_repository
+            .exists_tx(schema.room_id, txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?.0
+        {
+            return Err(DatabaseError::RecordNotFound("room".to_string()));
+        }
+
+        // if there is a author, check that the author is a member of the room
+        let mut author_account_id = None;
         if let Some(ref author_id) = schema.member_id {
             let account_membership = self
                 .member_repository
@@ -361,7 +1716,58 @@ impl RoomService {
                 ));
             }
 
+            if account_membership.is_banned() {
+                return Err(DatabaseError::ConstraintViolation(
+                    "member is banned".to_string(),
+                ));
+            }
+
+            // only import placeholders have no account, and they are created removed
+            let account_id = account_membership.account_id.ok_or_else(|| {
+                DatabaseError::ConstraintViolation("member has no account".to_string())
+            })?;
+
+            if !schema.system && !account_membership.role.can(Permission::PostMessage) {
+                return Err(DatabaseError::ConstraintViolation(
+                    "member is not allowed to post".to_string(),
+                ));
+            }
+
+            if !schema.system {
+                let settings = self.get_room_settings_tx(schema.room_id, txn).await?;
+
+                // slow mode spans every membership of the account, so leaving and joining
+                // again doesn't reset it
+                let last_posted_at = if settings.slows_down(&account_membership) {
+                    message::Entity::find()
+                        .inner_join(member::Entity)
+                        .select_only()
+                        .column(message::Column::CreatedAt)
+                        .filter(message::Column::RoomId.eq(schema.room_id))
+                        .filter(member::Column::AccountId.eq(account_id))
+                        .order_by(message::Column::CreatedAt, Order::Desc)
+                        .into_tuple::<Timestamp>()
+                        .one(txn)
+                        .await
+                        .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+                } else {
+                    None
+                };
+
+                settings
+                    .check_message(
+                        &account_membership,
+                        schema.content.as_deref(),
+                        schema.reply_to.is_some(),
+                        schema.attachment_id.is_some(),
+                        last_posted_at,
+                        now_millis(),
+                    )
+                    .map_err(DatabaseError::ConstraintViolation)?;
+            }
+
             schema.member_id = Some(account_membership.id);
+            author_account_id = Some(account_id);
         }
 
         // if there is a reply_to, check that the message exists
@@ -390,113 +1796,2848 @@ impl RoomService {
             }
         }
 
-        let message = self
-            .message_repository
-            .create_tx(&schema, &txn)
-            .await
-            .map_err(|_| DatabaseError::InsertionError("message".to_string()))?;
+        // an attachment is uploaded by the author first, and can only be bound once
+        if let Some(attachment_id) = schema.attachment_id {
+            let attachment = attachment::Entity::find_by_id(attachment_id)
+                .filter(attachment::Column::DeletedAt.is_null())
+                .one(txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("attachment".to_string()))?
+                .ok_or_else(|| DatabaseError::RecordNotFound("attachment".to_string()))?;
+
+            if attachment.room_id != schema.room_id {
+                return Err(DatabaseError::ConstraintViolation(
+                    "attachment does not belong to room".to_string(),
+                ));
+            }
+            if schema.member_id.is_none() || attachment.member_id != schema.member_id {
+                return Err(DatabaseError::ConstraintViolation(
+                    "attachment was not uploaded by the author".to_string(),
+                ));
+            }
+            if attachment.message_id.is_some() {
+                return Err(DatabaseError::ConstraintViolation(
+                    "attachment is already attached to a message".to_string(),
+                ));
+            }
+        }
+
+        let message = self
+            .message_repository
+            .create_tx(&schema, txn)
+            .await
+            .map_err(|_| DatabaseError::InsertionError("message".to_string()))?;
+
+        if let Some(attachment_id) = message.attachment_id {
+            let bound = attachment::Entity::update_many()
+                .col_expr(attachment::Column::MessageId, Expr::value(message.id))
+                .col_expr(attachment::Column::UpdatedAt, Expr::value(now_millis()))
+                .filter(attachment::Column::Id.eq(attachment_id))
+                .filter(attachment::Column::MessageId.is_null())
+                .exec(txn)
+                .await
+                .map_err(|_| DatabaseError::UpdateError("attachment".to_string()))?;
+
+            if bound.rows_affected == 0 {
+                return Err(DatabaseError::ConstraintViolation(
+                    "attachment is already attached to a message".to_string(),
+                ));
+            }
+        }
+
+        if !message.is_hidden {
+            self.touch_room_tx(message.room_id, message.created_at, txn)
+                .await?;
+        }
+
+        if let Some(account_id) = author_account_id
+            && !message.system
+        {
+            self.track_ticket_message_tx(&message, account_id, txn)
+                .await?;
+        }
+
+        if let Some(verdict) = verdict {
+            report::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                room_id: Set(message.room_id),
+                message_id: Set(message.id),
+                reporter_id: Set(None),
+                reason: Set(ReportReason::ContentFilter),
+                details: Set(Some(verdict.to_string())),
+                status: Set(ReportStatus::Open),
+                action: Set(None),
+                resolved_by: Set(None),
+                resolved_at: Set(None),
+                created_at: Set(now_millis()),
+                updated_at: Set(now_millis()),
+            }
+            .insert(txn)
+            .await
+            .map_err(|_| DatabaseError::InsertionError("message report".to_string()))?;
+        }
+
+        Ok(message)
+    }
+
+    /// Copies an attachment into another room, bound to `message_id`. The copy shares the
+    /// stored object of the original. Returns `None` when the original is gone.
+    async fn clone_attachment_tx(
+        &self,
+        attachment_id: ID,
+        room_id: ID,
+        message_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<Option<ID>, DatabaseError> {
+        let Some(original) = attachment::Entity::find_by_id(attachment_id)
+            .filter(attachment::Column::DeletedAt.is_null())
+            .one(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("attachment".to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        let copy = attachment::ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            room_id: Set(room_id),
+            member_id: Set(None),
+            message_id: Set(Some(message_id)),
+            file_name: Set(original.file_name),
+            content_type: Set(original.content_type),
+            size_bytes: Set(original.size_bytes),
+            checksum: Set(original.checksum),
+            storage_key: Set(original.storage_key),
+            deleted_at: Set(None),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+        }
+        .insert(txn)
+        .await
+        .map_err(|_| DatabaseError::InsertionError("attachment".to_string()))?;
+
+        Ok(Some(copy.id))
+    }
+
+    /// ## Add Message With Completion
+    ///
+    /// Adds a message like `add_message` and, when the room has a `model_tag` and the
+    /// message was posted by a member, generates the reply of the model to it. The reply is
+    /// streamed through `chunks` while it is generated, and returned once persisted.
+    ///
+    /// The quota of the author is checked before the message is persisted, so an
+    /// over-quota author doesn't post a message that is never answered.
+    pub async fn add_message_with_completion(
+        &self,
+        schema: MessageCreationSchema,
+        provider: &dyn ModelProvider,
+        chunks: Option<mpsc::Sender<String>>,
+    ) -> Result<(MessageModel, Option<MessageModel>), CadenceError> {
+        let room = self
+            .room_repository
+            .get_by_id(schema.room_id)
+            .await
+            .map_err(|_| CadenceError::Database(DatabaseError::QueryFailed("room".to_string())))?
+            .ok_or_else(|| {
+                CadenceError::Database(DatabaseError::RecordNotFound("room".to_string()))
+            })?;
+
+        // the reply is charged to the account of the author; import placeholders have none,
+        // and `add_message` rejects them as removed members
+        let account_id = match schema.member_id {
+            Some(member_id) if !schema.system && room.model_tag.is_some() => self
+                .member_repository
+                .get_by_id(member_id)
+                .await
+                .map_err(|_| {
+                    CadenceError::Database(DatabaseError::QueryFailed("member".to_string()))
+                })?
+                .and_then(|member| member.account_id),
+            _ => None,
+        };
+        if let Some(account_id) = account_id {
+            // the message is part of the next prompt, the full context is checked later
+            let estimated_prompt_tokens = self
+                .context_builder
+                .tokenizer
+                .count_tokens(schema.content.as_deref().unwrap_or_default())
+                as i64;
+            self.usage_service
+                .check_quota(account_id, estimated_prompt_tokens)
+                .await?;
+        }
+
+        let message = self
+            .add_message(schema)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        // messages hidden by the content filters wait for a moderator before being answered
+        if message.system
+            || message.member_id.is_none()
+            || message.is_hidden
+            || room.model_tag.is_none()
+        {
+            return Ok((message, None));
+        }
+
+        let reply = self
+            .generate_completion(room.id, Some(message.id), account_id, provider, chunks)
+            .await?;
+
+        Ok((message, Some(reply)))
+    }
+
+    /// ## Generate Completion
+    ///
+    /// Asks the model of the room for its next message. The context is built by the
+    /// `context_builder` out of the system prompt of the room (copied from its template),
+    /// the latest rolling summary, the pinned messages and the messages that follow the
+    /// summary. The reply is persisted as a message without author, with the `model_tag` of
+    /// the room, replying to `reply_to` when given.
+    ///
+    /// When too many messages were left out of the context, they are folded into a new
+    /// rolling summary once the reply is persisted. A failure to summarize is only logged.
+    ///
+    /// The tokens consumed are recorded in the usage ledger and charged to
+    /// `trigger_account_id`, whose quota is checked before calling the model.
+    pub async fn generate_completion(
+        &self,
+        room_id: ID,
+        reply_to: Option<ID>,
+        trigger_account_id: Option<ID>,
+        provider: &dyn ModelProvider,
+        chunks: Option<mpsc::Sender<String>>,
+    ) -> Result<MessageModel, CadenceError> {
+        let room = self
+            .room_repository
+            .get_by_id(room_id)
+            .await
+            .map_err(|_| CadenceError::Database(DatabaseError::QueryFailed("room".to_string())))?
+            .filter(|room| room.deleted_at.is_none())
+            .ok_or_else(|| {
+                CadenceError::Database(DatabaseError::RecordNotFound("room".to_string()))
+            })?;
+
+        let model_tag = room.model_tag.clone().ok_or_else(|| {
+            CadenceError::Database(DatabaseError::ConstraintViolation(
+                "room has no model".to_string(),
+            ))
+        })?;
+
+        let (summary, history, window) = self
+            .build_context_window(&room)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        if let Some(account_id) = trigger_account_id {
+            self.usage_service
+                .check_quota(account_id, window.prompt_tokens as i64)
+                .await?;
+        }
+
+        let completion = provider
+            .complete(
+                CompletionRequest {
+                    model: model_tag.clone(),
+                    messages: window.messages.clone(),
+                    max_tokens: None,
+                    temperature: None,
+                },
+                chunks,
+            )
+            .await?;
+
+        if completion.content.trim().is_empty() {
+            return Err(CadenceError::ServerError(ServerError::ServiceUnavailable(
+                "model returned an empty reply".to_string(),
+            )));
+        }
+
+        let (prompt_tokens, completion_tokens) =
+            self.completion_tokens(&completion, window.prompt_tokens);
+
+        let reply = self
+            .add_message(MessageCreationSchema {
+                room_id: room.id,
+                member_id: None,
+                system: false,
+                model_tag: Some(model_tag.clone()),
+                content: Some(completion.content),
+                attachment_id: None,
+                reply_to,
+                message_type: MessageType::Default,
+                is_hidden: false,
+                expires_at: None,
+            })
+            .await
+            .map_err(CadenceError::Database)?;
+
+        self.usage_service
+            .record_usage(UsageRecordSchema {
+                account_id: trigger_account_id,
+                room_id: room.id,
+                message_id: Some(reply.id),
+                model_tag: model_tag.clone(),
+                kind: UsageKind::Completion,
+                prompt_tokens,
+                completion_tokens,
+            })
+            .await
+            .map_err(CadenceError::Database)?;
+
+        if window.omitted.len() >= self.context_builder.config.summarize_threshold {
+            let omitted: HashSet<ID> = window.omitted.iter().copied().collect();
+            let to_fold: Vec<MessageModel> = history
+                .into_iter()
+                .filter(|m| omitted.contains(&m.id))
+                .collect();
+
+            if let Err(e) = self
+                .append_room_summary(
+                    &room.id,
+                    &model_tag,
+                    summary.as_ref(),
+                    &to_fold,
+                    trigger_account_id,
+                    provider,
+                )
+                .await
+            {
+                tracing::warn!("Failed to summarize room {}: {:?}", room.id, e);
+            }
+        }
+
+        Ok(reply)
+    }
+
+    /// Loads the latest rolling summary, the pinned messages and the messages after the
+    /// summary of a room, and builds its context window. The history is returned oldest
+    /// first.
+    async fn build_context_window(
+        &self,
+        room: &RoomModel,
+    ) -> Result<(Option<MessageModel>, Vec<MessageModel>, ContextWindow), DatabaseError> {
+        let summary = message::Entity::find()
+            .filter(message::Column::RoomId.eq(room.id))
+            .filter(message::Column::MessageType.eq(MessageType::Summary))
+            .filter(message::Column::DeletedAt.is_null())
+            .order_by(message::Column::CreatedAt, Order::Desc)
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("summary".to_string()))?;
+
+        let mut history_query = message::Entity::find()
+            .filter(message::Column::RoomId.eq(room.id))
+            .filter(message::Column::MessageType.eq(MessageType::Default))
+            .filter(message::Column::IsHidden.eq(false))
+            .filter(message::Column::DeletedAt.is_null());
+        if let Some(summarized_until) = summary.as_ref().and_then(|s| s.summarized_until) {
+            history_query = history_query.filter(message::Column::CreatedAt.gt(summarized_until));
+        }
+
+        let mut history = history_query
+            .order_by(message::Column::CreatedAt, Order::Desc)
+            .limit(CONTEXT_HISTORY_LIMIT)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+        history.reverse();
+
+        let pinned = message::Entity::find()
+            .filter(message::Column::RoomId.eq(room.id))
+            .filter(message::Column::PinnedAt.is_not_null())
+            .filter(message::Column::MessageType.eq(MessageType::Default))
+            .filter(message::Column::IsHidden.eq(false))
+            .filter(message::Column::DeletedAt.is_null())
+            .order_by(message::Column::CreatedAt, Order::Asc)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("pinned messages".to_string()))?;
+
+        let window = self.context_builder.build(
+            room.system_prompt.as_deref(),
+            summary.as_ref().and_then(|s| s.content.as_deref()),
+            &pinned,
+            &history,
+        );
+
+        Ok((summary, history, window))
+    }
+
+    /// Folds `messages` (oldest first) and the previous summary into a new rolling summary,
+    /// stored as a hidden system message of the room.
+    async fn append_room_summary(
+        &self,
+        room_id: &ID,
+        model_tag: &str,
+        previous: Option<&MessageModel>,
+        messages: &[MessageModel],
+        trigger_account_id: Option<ID>,
+        provider: &dyn ModelProvider,
+    ) -> Result<MessageModel, CadenceError> {
+        let Some(summarized_until) = messages.iter().map(|m| m.created_at).max() else {
+            return Err(CadenceError::Database(DatabaseError::ConstraintViolation(
+                "nothing to summarize".to_string(),
+            )));
+        };
+
+        let mut transcript = String::new();
+        if let Some(content) = previous.and_then(|p| p.content.as_deref()) {
+            transcript.push_str("Previous summary:\n");
+            transcript.push_str(content);
+            transcript.push_str("\n\n");
+        }
+        transcript.push_str("New messages:\n");
+        for message in messages {
+            let role = match chat_role(message) {
+                Some(ChatRole::Assistant) => "assistant",
+                Some(_) => "user",
+                None => continue,
+            };
+            transcript.push_str(&format!(
+                "{}: {}\n",
+                role,
+                message.content.as_deref().unwrap_or_default()
+            ));
+        }
+
+        let prompt = vec![
+            ChatMessage::new(ChatRole::System, SUMMARY_INSTRUCTIONS),
+            ChatMessage::new(ChatRole::User, transcript),
+        ];
+        let estimated_prompt_tokens = prompt
+            .iter()
+            .map(|m| self.context_builder.message_tokens(&m.content))
+            .sum();
+
+        let completion = provider
+            .complete(
+                CompletionRequest {
+                    model: model_tag.to_string(),
+                    messages: prompt,
+                    max_tokens: None,
+                    temperature: None,
+                },
+                None,
+            )
+            .await?;
+
+        if completion.content.trim().is_empty() {
+            return Err(CadenceError::ServerError(ServerError::ServiceUnavailable(
+                "model returned an empty summary".to_string(),
+            )));
+        }
+
+        let (prompt_tokens, completion_tokens) =
+            self.completion_tokens(&completion, estimated_prompt_tokens);
+
+        let summary = message::ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            room_id: Set(*room_id),
+            member_id: Set(None),
+            system: Set(true),
+            model_tag: Set(Some(model_tag.to_string())),
+            content: Set(Some(completion.content)),
+            message_type: Set(MessageType::Summary),
+            is_hidden: Set(true),
+            summarized_until: Set(Some(summarized_until)),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+            ..Default::default()
+        }
+        .insert(self.db())
+        .await
+        .map_err(|_| {
+            CadenceError::Database(DatabaseError::InsertionError("summary".to_string()))
+        })?;
+
+        self.usage_service
+            .record_usage(UsageRecordSchema {
+                account_id: trigger_account_id,
+                room_id: *room_id,
+                message_id: Some(summary.id),
+                model_tag: model_tag.to_string(),
+                kind: UsageKind::Summary,
+                prompt_tokens,
+                completion_tokens,
+            })
+            .await
+            .map_err(CadenceError::Database)?;
+
+        Ok(summary)
+    }
+
+    /// Prompt and completion tokens of a completion, as reported by the provider or
+    /// estimated with the tokenizer of the `context_builder` when it doesn't report them.
+    fn completion_tokens(
+        &self,
+        completion: &Completion,
+        estimated_prompt_tokens: usize,
+    ) -> (i64, i64) {
+        match completion.usage {
+            Some(usage) => (usage.prompt_tokens as i64, usage.completion_tokens as i64),
+            None => (
+                estimated_prompt_tokens as i64,
+                self.context_builder
+                    .tokenizer
+                    .count_tokens(&completion.content) as i64,
+            ),
+        }
+    }
+
+    pub async fn remove_message(
+        &self,
+        room_id: ID,
+        message_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<MessageModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed(
+                "Failed to start remove_message transaction".to_string(),
+            )
+        })?;
+
+        let message_to_delete = self
+            .message_repository
+            .get_by_id(message_id)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(format!("Failed message query: {}", e)))?
+            .ok_or_else(|| {
+                DatabaseError::RecordNotFound(format!("Message {} not found", message_id))
+            })?;
+
+        if message_to_delete.room_id != room_id {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} does not belong to room {}",
+                message_id, room_id
+            )));
+        }
+
+        if message_to_delete.deleted_at.is_some() {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} is already deleted",
+                message_id
+            )));
+        }
+
+        if message_to_delete.member_id.is_none() {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} does not have a member_id",
+                message_id
+            )));
+        }
+
+        let member_id = message_to_delete.member_id.unwrap();
+
+        let trigger_membership = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let author_membership = self
+            .member_repository
+            .get_by_id(member_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?
+            .ok_or_else(|| {
+                DatabaseError::RecordNotFound(format!(
+                    "Message author {} not found in room {}",
+                    message_id,
+                    room_id
+                ))
+            })?;
+
+        let is_author = author_membership.account_id == Some(trigger_account_id);
+        let can_delete = trigger_membership.role.can(Permission::DeleteMessage);
+
+        if !is_author && !can_delete {
+            return Err(DatabaseError::ConstraintViolation(
+                "User is not the message author and lacks delete_message permission".to_string(),
+            ));
+        }
+
+        let deleted_message = self
+            .message_repository
+            .delete_tx(message_id, &txn)
+            .await
+            .map_err(|e| {
+                DatabaseError::DeletionError(format!(
+                    "Failed to delete message {}: {}",
+                    message_id, e
+                ))
+            })?;
+        self.refresh_last_message_at_tx([room_id], &txn).await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed(
+                "Failed to commit remove_message transaction".to_string(),
+            )
+        })?;
+
+        Ok(deleted_message)
+    }
+
+    /// ## Schedule Message
+    ///
+    /// Queues a message of the account, who must be an active member allowed to post, to be
+    /// posted at `scheduled_for` by the message worker, see `spawn_message_worker`. The
+    /// settings and content filters of the room are applied when the message is posted.
+    pub async fn schedule_message(
+        &self,
+        schema: ScheduledMessageSchema,
+    ) -> Result<ScheduledMessageModel, DatabaseError> {
+        let now = now_millis();
+        if schema.scheduled_for <= now {
+            return Err(DatabaseError::ConstraintViolation(
+                "scheduled time must be in the future".to_string(),
+            ));
+        }
+        if schema.scheduled_for > now + MAX_SCHEDULE_AHEAD_MS {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "messages can't be scheduled more than {} ms ahead",
+                MAX_SCHEDULE_AHEAD_MS
+            )));
+        }
+        if let Some(ttl_ms) = schema.ttl_ms
+            && !(1..=MAX_MESSAGE_TTL_MS).contains(&ttl_ms)
+        {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "message lifetime must be between 1 and {} ms",
+                MAX_MESSAGE_TTL_MS
+            )));
+        }
+        if schema
+            .content
+            .as_deref()
+            .is_none_or(|c| c.trim().is_empty())
+            && schema.attachment_id.is_none()
+        {
+            return Err(DatabaseError::ConstraintViolation(
+                "message must have content or an attachment".to_string(),
+            ));
+        }
+
+        let membership = self
+            .get_active_membership(schema.room_id, schema.account_id)
+            .await?;
+        if !membership.role.can(Permission::PostMessage) {
+            return Err(DatabaseError::ConstraintViolation(
+                "member is not allowed to post".to_string(),
+            ));
+        }
+
+        scheduled_message::ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            room_id: Set(schema.room_id),
+            member_id: Set(membership.id),
+            content: Set(schema.content),
+            attachment_id: Set(schema.attachment_id),
+            reply_to: Set(schema.reply_to),
+            scheduled_for: Set(schema.scheduled_for),
+            ttl_ms: Set(schema.ttl_ms),
+            status: Set(ScheduledMessageStatus::Pending),
+            message_id: Set(None),
+            error: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db())
+        .await
+        .map_err(|_| DatabaseError::InsertionError("scheduled message".to_string()))
+    }
+
+    /// ## Cancel Scheduled Message
+    ///
+    /// Withdraws a pending message scheduled by the trigger account.
+    pub async fn cancel_scheduled_message(
+        &self,
+        scheduled_message_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<ScheduledMessageModel, DatabaseError> {
+        let scheduled = scheduled_message::Entity::find_by_id(scheduled_message_id)
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("scheduled message".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("scheduled message".to_string()))?;
+
+        let author = self
+            .member_repository
+            .get_by_id(scheduled.member_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+        if author.account_id != Some(trigger_account_id) {
+            return Err(DatabaseError::ConstraintViolation(
+                "only the author can cancel a scheduled message".to_string(),
+            ));
+        }
+
+        // the worker may be posting it right now, so only a pending message is cancelled
+        let cancelled = scheduled_message::Entity::update_many()
+            .col_expr(
+                scheduled_message::Column::Status,
+                Expr::value(ScheduledMessageStatus::Cancelled),
+            )
+            .col_expr(
+                scheduled_message::Column::UpdatedAt,
+                Expr::value(now_millis()),
+            )
+            .filter(scheduled_message::Column::Id.eq(scheduled.id))
+            .filter(scheduled_message::Column::Status.eq(ScheduledMessageStatus::Pending))
+            .exec(self.db())
+            .await
+            .map_err(|_| DatabaseError::UpdateError("scheduled message".to_string()))?;
+        if cancelled.rows_affected == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "scheduled message is not pending".to_string(),
+            ));
+        }
+
+        Ok(ScheduledMessageModel {
+            status: ScheduledMessageStatus::Cancelled,
+            ..scheduled
+        })
+    }
+
+    /// ## Get Scheduled Messages
+    ///
+    /// The messages of the account waiting to be posted in the room, soonest first.
+    pub async fn get_scheduled_messages(
+        &self,
+        room_id: ID,
+        account_id: ID,
+    ) -> Result<Vec<ScheduledMessageModel>, DatabaseError> {
+        scheduled_message::Entity::find()
+            .inner_join(member::Entity)
+            .filter(scheduled_message::Column::RoomId.eq(room_id))
+            .filter(member::Column::AccountId.eq(account_id))
+            .filter(scheduled_message::Column::Status.eq(ScheduledMessageStatus::Pending))
+            .order_by(scheduled_message::Column::ScheduledFor, Order::Asc)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("scheduled messages".to_string()))
+    }
+
+    /// ## Spawn Message Worker
+    ///
+    /// Starts the background worker that posts the due scheduled messages and deletes the
+    /// expired ones every `interval`. Its state lives in the database, so a restarted
+    /// worker picks up where the previous one stopped, and several instances may run it.
+    pub fn spawn_message_worker(&self, interval: Duration) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.deliver_due_messages().await {
+                    tracing::warn!("Failed to deliver scheduled messages: {:?}", e);
+                }
+                if let Err(e) = service.expire_messages().await {
+                    tracing::warn!("Failed to expire messages: {:?}", e);
+                }
+            }
+        })
+    }
+
+    /// ## Deliver Due Messages
+    ///
+    /// Posts up to `MESSAGE_WORKER_BATCH_SIZE` due scheduled messages, oldest first, and
+    /// returns how many were posted. The ones that can't be posted anymore are marked
+    /// `Failed`, the ones hitting a database error are retried on the next run.
+    pub async fn deliver_due_messages(&self) -> Result<u64, DatabaseError> {
+        let due = scheduled_message::Entity::find()
+            .filter(scheduled_message::Column::Status.eq(ScheduledMessageStatus::Pending))
+            .filter(scheduled_message::Column::ScheduledFor.lte(now_millis()))
+            .order_by(scheduled_message::Column::ScheduledFor, Order::Asc)
+            .limit(MESSAGE_WORKER_BATCH_SIZE)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("scheduled messages".to_string()))?;
+
+        let mut delivered = 0;
+        for scheduled in due {
+            let scheduled_id = scheduled.id;
+            match self.deliver_scheduled_message(scheduled).await {
+                Ok(Some(_)) => delivered += 1,
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to deliver scheduled message {}: {:?}",
+                        scheduled_id,
+                        e
+                    )
+                }
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Posts a scheduled message, `None` when it is not pending anymore. The message is
+    /// claimed in the transaction that posts it, so it is posted exactly once even when
+    /// several workers pick it up or a worker stops midway.
+    async fn deliver_scheduled_message(
+        &self,
+        scheduled: ScheduledMessageModel,
+    ) -> Result<Option<MessageModel>, DatabaseError> {
+        let schema = MessageCreationSchema {
+            room_id: scheduled.room_id,
+            member_id: Some(scheduled.member_id),
+            system: false,
+            model_tag: None,
+            content: scheduled.content,
+            attachment_id: scheduled.attachment_id,
+            reply_to: scheduled.reply_to,
+            message_type: MessageType::Default,
+            is_hidden: false,
+            expires_at: scheduled.ttl_ms.map(|ttl_ms| now_millis() + ttl_ms),
+        };
+
+        let result = match self.check_message(schema).await {
+            Ok((schema, verdict)) => {
+                self.post_scheduled_message(scheduled.id, schema, verdict)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        // the author left, or the message breaks the rules of the room: retrying won't help
+        if let Err(
+            ref e @ (DatabaseError::ConstraintViolation(_) | DatabaseError::RecordNotFound(_)),
+        ) = result
+        {
+            scheduled_message::Entity::update_many()
+                .col_expr(
+                    scheduled_message::Column::Status,
+                    Expr::value(ScheduledMessageStatus::Failed),
+                )
+                .col_expr(
+                    scheduled_message::Column::Error,
+                    Expr::value(format!("{:?}", e)),
+                )
+                .col_expr(
+                    scheduled_message::Column::UpdatedAt,
+                    Expr::value(now_millis()),
+                )
+                .filter(scheduled_message::Column::Id.eq(scheduled.id))
+                .filter(scheduled_message::Column::Status.eq(ScheduledMessageStatus::Pending))
+                .exec(self.db())
+                .await
+                .map_err(|_| DatabaseError::UpdateError("scheduled message".to_string()))?;
+        }
+        result
+    }
+
+    /// Claims a pending scheduled message and posts it in the same transaction, `None`
+    /// when it is not pending anymore.
+    async fn post_scheduled_message(
+        &self,
+        scheduled_message_id: ID,
+        schema: MessageCreationSchema,
+        verdict: Option<FilterVerdict>,
+    ) -> Result<Option<MessageModel>, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        // a concurrent claim waits for this transaction and then finds nothing to update
+        let claimed = scheduled_message::Entity::update_many()
+            .col_expr(
+                scheduled_message::Column::Status,
+                Expr::value(ScheduledMessageStatus::Delivered),
+            )
+            .col_expr(
+                scheduled_message::Column::UpdatedAt,
+                Expr::value(now_millis()),
+            )
+            .filter(scheduled_message::Column::Id.eq(scheduled_message_id))
+            .filter(scheduled_message::Column::Status.eq(ScheduledMessageStatus::Pending))
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("scheduled message".to_string()))?;
+        if claimed.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        let message = self.insert_message_tx(schema, verdict, &txn).await?;
+
+        scheduled_message::Entity::update_many()
+            .col_expr(
+                scheduled_message::Column::MessageId,
+                Expr::value(message.id),
+            )
+            .filter(scheduled_message::Column::Id.eq(scheduled_message_id))
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("scheduled message".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(Some(message))
+    }
+
+    /// ## Expire Messages
+    ///
+    /// Soft-deletes the messages past their `expires_at` and returns how many were deleted.
+    pub async fn expire_messages(&self) -> Result<u64, DatabaseError> {
+        let now = now_millis();
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let room_ids: Vec<ID> = message::Entity::find()
+            .select_only()
+            .column(message::Column::RoomId)
+            .distinct()
+            .filter(message::Column::ExpiresAt.lte(now))
+            .filter(message::Column::DeletedAt.is_null())
+            .into_tuple()
+            .all(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+        if room_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let expired = message::Entity::update_many()
+            .col_expr(message::Column::DeletedAt, Expr::value(now))
+            .col_expr(message::Column::UpdatedAt, Expr::value(now))
+            .filter(message::Column::ExpiresAt.lte(now))
+            .filter(message::Column::DeletedAt.is_null())
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("messages".to_string()))?;
+        self.refresh_last_message_at_tx(room_ids, &txn).await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(expired.rows_affected)
+    }
+
+    /// ## Bulk Update Messages
+    ///
+    /// Applies `action` to the messages of the room selected by `filter`, oldest first and
+    /// up to `MAX_BULK_MESSAGES`, in a single transaction, and posts one `MessagesModerated`
+    /// system message about it. With `dry_run`, only counts the messages that would change.
+    /// Messages of members the trigger doesn't outrank are left alone, see
+    /// `bulk_author_condition`.
+    pub async fn bulk_update_messages(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        action: BulkMessageAction,
+        filter: BulkMessageFilter,
+        dry_run: bool,
+    ) -> Result<BulkMessageReport, DatabaseError> {
+        if filter.is_empty() {
+            return Err(DatabaseError::ConstraintViolation(
+                "bulk operations need at least one filter".to_string(),
+            ));
+        }
+        if filter
+            .message_ids
+            .as_ref()
+            .is_some_and(|ids| ids.is_empty() || ids.len() as u64 > MAX_BULK_MESSAGES)
+        {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "message_ids must hold between 1 and {} ids",
+                MAX_BULK_MESSAGES
+            )));
+        }
+        if let (Some(from), Some(to)) = (filter.from, filter.to)
+            && from > to
+        {
+            return Err(DatabaseError::ConstraintViolation(
+                "time range must end after it starts".to_string(),
+            ));
+        }
+
+        let permission = match action {
+            BulkMessageAction::Hide | BulkMessageAction::Unhide => Permission::HideMessage,
+            BulkMessageAction::Delete | BulkMessageAction::Purge => Permission::DeleteMessage,
+        };
+        let membership = self
+            .authorize(room_id, trigger_account_id, permission)
+            .await?;
+        if action == BulkMessageAction::Purge && membership.role != MemberRole::Owner {
+            return Err(DatabaseError::ConstraintViolation(
+                "only owners can purge messages".to_string(),
+            ));
+        }
+
+        let mut condition = Condition::all()
+            .add(message::Column::RoomId.eq(room_id))
+            .add(message::Column::MessageType.eq(MessageType::Default))
+            .add(bulk_author_condition(&membership));
+        condition = match action {
+            BulkMessageAction::Hide => condition
+                .add(message::Column::DeletedAt.is_null())
+                .add(message::Column::IsHidden.eq(false)),
+            BulkMessageAction::Unhide => condition
+                .add(message::Column::DeletedAt.is_null())
+                .add(message::Column::IsHidden.eq(true)),
+            BulkMessageAction::Delete => condition.add(message::Column::DeletedAt.is_null()),
+            BulkMessageAction::Purge => condition,
+        };
+        if let Some(message_ids) = filter.message_ids {
+            condition = condition.add(message::Column::Id.is_in(message_ids));
+        }
+        if let Some(member_id) = filter.member_id {
+            condition = condition.add(message::Column::MemberId.eq(member_id));
+        }
+        if let Some(from) = filter.from {
+            condition = condition.add(message::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = filter.to {
+            condition = condition.add(message::Column::CreatedAt.lte(to));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let total = message::Entity::find()
+            .filter(condition.clone())
+            .count(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+        let message_ids: Vec<ID> = message::Entity::find()
+            .select_only()
+            .column(message::Column::Id)
+            .filter(condition)
+            .order_by(message::Column::CreatedAt, Order::Asc)
+            .limit(MAX_BULK_MESSAGES)
+            .into_tuple()
+            .all(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+
+        let count = message_ids.len() as u64;
+        let mut report = BulkMessageReport {
+            action,
+            dry_run,
+            count,
+            remaining: total.saturating_sub(count),
+            summary: None,
+        };
+        // nothing is written, the transaction is dropped
+        if dry_run || message_ids.is_empty() {
+            return Ok(report);
+        }
+
+        let now = now_millis();
+        match action {
+            BulkMessageAction::Hide | BulkMessageAction::Unhide => {
+                message::Entity::update_many()
+                    .col_expr(
+                        message::Column::IsHidden,
+                        Expr::value(action == BulkMessageAction::Hide),
+                    )
+                    .col_expr(message::Column::UpdatedAt, Expr::value(now))
+                    .filter(message::Column::Id.is_in(message_ids))
+                    .exec(&txn)
+                    .await
+                    .map_err(|_| DatabaseError::UpdateError("messages".to_string()))?;
+            }
+            BulkMessageAction::Delete => {
+                message::Entity::update_many()
+                    .col_expr(message::Column::DeletedAt, Expr::value(now))
+                    .col_expr(message::Column::UpdatedAt, Expr::value(now))
+                    .filter(message::Column::Id.is_in(message_ids))
+                    .exec(&txn)
+                    .await
+                    .map_err(|_| DatabaseError::DeletionError("messages".to_string()))?;
+            }
+            BulkMessageAction::Purge => self.purge_messages_tx(&message_ids, &txn).await?,
+        }
+        self.refresh_last_message_at_tx([room_id], &txn).await?;
+
+        report.summary = Some(
+            self.post_system_message_tx(
+                room_id,
+                Some(membership.id),
+                MessageType::MessagesModerated,
+                Some(format!(
+                    "{} {} message{}",
+                    action.past_tense(),
+                    count,
+                    if count == 1 { "" } else { "s" }
+                )),
+                &txn,
+            )
+            .await?,
+        );
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(report)
+    }
+
+    /// Removes messages for good. Their reactions and reports go with them, their
+    /// attachments are deleted, and whatever still references them is unlinked.
+    async fn purge_messages_tx(
+        &self,
+        message_ids: &[ID],
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<(), DatabaseError> {
+        let now = now_millis();
+
+        message_reaction::Entity::delete_many()
+            .filter(message_reaction::Column::MessageId.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("reactions".to_string()))?;
+        report::Entity::delete_many()
+            .filter(report::Column::MessageId.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("message reports".to_string()))?;
+
+        attachment::Entity::update_many()
+            .col_expr(
+                attachment::Column::MessageId,
+                Expr::value(Option::<ID>::None),
+            )
+            .col_expr(attachment::Column::DeletedAt, Expr::value(now))
+            .col_expr(attachment::Column::UpdatedAt, Expr::value(now))
+            .filter(attachment::Column::MessageId.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("attachments".to_string()))?;
+        message::Entity::update_many()
+            .col_expr(message::Column::ReplyTo, Expr::value(Option::<ID>::None))
+            .filter(message::Column::ReplyTo.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("replies".to_string()))?;
+        member::Entity::update_many()
+            .col_expr(
+                member::Column::LastReadMessageId,
+                Expr::value(Option::<ID>::None),
+            )
+            .filter(member::Column::LastReadMessageId.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("read markers".to_string()))?;
+        scheduled_message::Entity::update_many()
+            .col_expr(
+                scheduled_message::Column::ReplyTo,
+                Expr::value(Option::<ID>::None),
+            )
+            .filter(scheduled_message::Column::ReplyTo.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("scheduled messages".to_string()))?;
+        usage_record::Entity::update_many()
+            .col_expr(
+                usage_record::Column::MessageId,
+                Expr::value(Option::<ID>::None),
+            )
+            .filter(usage_record::Column::MessageId.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("usage records".to_string()))?;
+
+        message::Entity::delete_many()
+            .filter(message::Column::Id.is_in(message_ids.to_vec()))
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("messages".to_string()))?;
+
+        Ok(())
+    }
+
+    /// ## Ban Member
+    ///
+    /// Bans a member from the room with an optional reason and expiry, and posts a
+    /// `RecipientBanned` system message. Requires the `BanMember` permission and a role
+    /// above the target's. Owners can't be banned.
+    pub async fn ban_member(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+        account_id: ID,
+        reason: Option<String>,
+        expires_at: Option<Timestamp>,
+    ) -> Result<MemberModel, DatabaseError> {
+        let trigger_membership = match trigger_account_id {
+            Some(account_id) => Some(
+                self.authorize(room_id, account_id, Permission::BanMember)
+                    .await?,
+            ),
+            None => None,
+        };
+
+        if expires_at.is_some_and(|expires_at| expires_at <= now_millis()) {
+            return Err(DatabaseError::ConstraintViolation(
+                "ban expiry must be in the future".to_string(),
+            ));
+        }
+
+        let target_membership = self
+            .get_member_by_account_id(room_id, account_id)
+            .await?
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+
+        member::check_can_ban(trigger_membership.as_ref(), &target_membership)
+            .map_err(DatabaseError::ConstraintViolation)?;
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let member = self
+            .member_repository
+            .update_tx(
+                target_membership.id,
+                member::ActiveModel {
+                    banned_at: Set(Some(now_millis())),
+                    ban_reason: Set(reason.clone()),
+                    ban_expires_at: Set(expires_at),
+                    ..Default::default()
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("member".to_string()))?;
+
+        self.post_system_message_tx(
+            room_id,
+            Some(member.id),
+            MessageType::RecipientBanned,
+            reason,
+            &txn,
+        )
+        .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    /// ## Unban Member
+    ///
+    /// Lifts the ban of a member, also when they left or were removed since, and posts a
+    /// `RecipientUnbanned` system message. Requires the `BanMember` permission.
+    pub async fn unban_member(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+        account_id: ID,
+    ) -> Result<MemberModel, DatabaseError> {
+        if let Some(account_id) = trigger_account_id {
+            self.authorize(room_id, account_id, Permission::BanMember)
+                .await?;
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        // a banned member who left or was removed keeps the ban on their last membership
+        let memberships = MemberEntity::find()
+            .filter(member::Column::RoomId.eq(room_id))
+            .filter(member::Column::AccountId.eq(account_id))
+            .all(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+        let target_membership = member::latest_membership(&memberships)
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+
+        if !target_membership.is_banned() {
+            return Err(DatabaseError::ConstraintViolation(
+                "member is not banned".to_string(),
+            ));
+        }
+
+        let member = self
+            .member_repository
+            .update_tx(
+                target_membership.id,
+                member::ActiveModel {
+                    banned_at: Set(None),
+                    ban_reason: Set(None),
+                    ban_expires_at: Set(None),
+                    ..Default::default()
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("member".to_string()))?;
+
+        self.post_system_message_tx(
+            room_id,
+            Some(member.id),
+            MessageType::RecipientUnbanned,
+            None,
+            &txn,
+        )
+        .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    /// Creates the ticket of a support room, opened by `requester_id`. Other rooms have none.
+    async fn open_ticket_tx(
+        &self,
+        room: &RoomModel,
+        requester_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<(), DatabaseError> {
+        if room.room_type != RoomType::Support {
+            return Ok(());
+        }
+
+        let now = now_millis();
+        let priority = ticket::TicketPriority::default();
+        let (first_response_due_at, resolution_due_at) = self.sla_policy.due_dates(priority, now);
+        ticket::ActiveModel {
+            room_id: Set(room.id),
+            requester_id: Set(requester_id),
+            assignee_id: Set(None),
+            status: Set(TicketStatus::Open),
+            priority: Set(priority),
+            first_response_due_at: Set(first_response_due_at),
+            resolution_due_at: Set(resolution_due_at),
+            first_response_at: Set(None),
+            resolved_at: Set(None),
+            closed_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(txn)
+        .await
+        .map_err(|_| DatabaseError::InsertionError("ticket".to_string()))?;
+        Ok(())
+    }
+
+    /// Updates the ticket of a support room after a message of `author_account_id`: the
+    /// first message of someone else than the requester is the first response, and a message
+    /// of the requester opens a pending or resolved ticket again.
+    async fn track_ticket_message_tx(
+        &self,
+        message: &MessageModel,
+        author_account_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<(), DatabaseError> {
+        let Some(current) = ticket::Entity::find_by_id(message.room_id)
+            .one(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("ticket".to_string()))?
+        else {
+            return Ok(());
+        };
+
+        if author_account_id != current.requester_id {
+            if current.first_response_at.is_none() {
+                ticket::ActiveModel {
+                    room_id: Set(current.room_id),
+                    first_response_at: Set(Some(message.created_at)),
+                    updated_at: Set(now_millis()),
+                    ..Default::default()
+                }
+                .update(txn)
+                .await
+                .map_err(|_| DatabaseError::UpdateError("ticket".to_string()))?;
+            }
+            return Ok(());
+        }
+
+        if matches!(
+            current.status,
+            TicketStatus::Pending | TicketStatus::Resolved
+        ) {
+            ticket::ActiveModel {
+                room_id: Set(current.room_id),
+                status: Set(TicketStatus::Open),
+                resolved_at: Set(None),
+                updated_at: Set(now_millis()),
+                ..Default::default()
+            }
+            .update(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("ticket".to_string()))?;
+
+            self.post_system_message_tx(
+                current.room_id,
+                message.member_id,
+                MessageType::TicketUpdated,
+                Some(format!(
+                    "Status changed from {} to {}",
+                    current.status.as_str(),
+                    TicketStatus::Open.as_str()
+                )),
+                txn,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// ## Post System Message
+    ///
+    /// Records a system message (e.g. a membership change) within an ongoing transaction.
+    /// `member_id` references the member the message is about.
+    pub(crate) async fn post_system_message_tx(
+        &self,
+        room_id: ID,
+        member_id: Option<ID>,
+        message_type: MessageType,
+        content: Option<String>,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<MessageModel, DatabaseError> {
+        let message = self
+            .message_repository
+            .create_tx(
+                &MessageCreationSchema {
+                    room_id,
+                    member_id,
+                    system: true,
+                    model_tag: None,
+                    content,
+                    attachment_id: None,
+                    reply_to: None,
+                    message_type,
+                    is_hidden: false,
+                    expires_at: None,
+                },
+                txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("message".to_string()))?;
+
+        self.touch_room_tx(room_id, message.created_at, txn).await?;
+        Ok(message)
+    }
+
+    /// Moves the `last_message_at` of the room forward to `at`. Never moves it back, so
+    /// messages committed out of order keep the latest time.
+    async fn touch_room_tx(
+        &self,
+        room_id: ID,
+        at: Timestamp,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<(), DatabaseError> {
+        room::Entity::update_many()
+            .col_expr(room::Column::LastMessageAt, Expr::value(at))
+            .filter(room::Column::Id.eq(room_id))
+            .filter(
+                Condition::any()
+                    .add(room::Column::LastMessageAt.is_null())
+                    .add(room::Column::LastMessageAt.lt(at)),
+            )
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("room".to_string()))?;
+        Ok(())
+    }
+
+    /// Recomputes the `last_message_at` of the rooms from their latest visible message, once
+    /// messages were hidden, unhidden or deleted.
+    pub async fn refresh_last_message_at_tx(
+        &self,
+        room_ids: impl IntoIterator<Item = ID>,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<(), DatabaseError> {
+        refresh_last_message_at(room_ids)
+            .exec(txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("room".to_string()))?;
+        Ok(())
+    }
+
+    /// ## Authorize
+    ///
+    /// Central authorization check for room operations. Returns the membership of the account
+    /// when it is an active, non-banned member whose role grants `permission`.
+    pub async fn authorize(
+        &self,
+        room_id: ID,
+        account_id: ID,
+        permission: Permission,
+    ) -> Result<MemberModel, DatabaseError> {
+        let membership = self.get_active_membership(room_id, account_id).await?;
+
+        if !membership.role.can(permission) {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "member lacks {:?} permission",
+                permission
+            )));
+        }
+
+        Ok(membership)
+    }
+
+    /// ## Update Room
+    ///
+    /// Edits the room details. Requires the `EditRoom` permission.
+    pub async fn update_room(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+        schema: RoomServiceUpdateSchema,
+    ) -> Result<RoomModel, DatabaseError> {
+        if let Some(account_id) = trigger_account_id {
+            self.authorize(room_id, account_id, Permission::EditRoom)
+                .await?;
+        }
+
+        if !self
+            .room_repository
+            .exists(room_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?
+            .0
+        {
+            return Err(DatabaseError::RecordNotFound("room".to_string()));
+        }
+
+        let mut active_model = room::ActiveModel {
+            ..Default::default()
+        };
+        if let Some(name) = schema.name {
+            active_model.name = Set(Some(name));
+        }
+        if let Some(description) = schema.description {
+            active_model.description = Set(Some(description));
+        }
+        if let Some(icon_url) = schema.icon_url {
+            active_model.icon_url = Set(Some(icon_url));
+        }
+        if let Some(background_url) = schema.background_url {
+            active_model.background_url = Set(Some(background_url));
+        }
+        if let Some(visibility) = schema.visibility {
+            active_model.visibility = Set(visibility);
+        }
+        if let Some(model_tag) = schema.model_tag {
+            active_model.model_tag = Set(Some(model_tag));
+        }
+        if let Some(system_prompt) = schema.system_prompt {
+            active_model.system_prompt = Set(Some(system_prompt));
+        }
+
+        self.room_repository
+            .update(room_id, active_model)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("room".to_string()))
+    }
+
+    /// ## Get Room Settings
+    ///
+    /// The posting rules of the room, the defaults when they were never edited.
+    pub async fn get_room_settings(&self, room_id: ID) -> Result<RoomSettingsModel, DatabaseError> {
+        self.get_room_settings_tx(room_id, self.db()).await
+    }
+
+    async fn get_room_settings_tx(
+        &self,
+        room_id: ID,
+        txn: &impl ConnectionTrait,
+    ) -> Result<RoomSettingsModel, DatabaseError> {
+        Ok(settings::Entity::find_by_id(room_id)
+            .one(txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room settings".to_string()))?
+            .unwrap_or_else(|| RoomSettingsModel::defaults(room_id)))
+    }
+
+    /// ## Update Room Settings
+    ///
+    /// Edits the posting rules of the room. Requires the `EditRoom` permission.
+    pub async fn update_room_settings(
+        &self,
+        room_id: ID,
+        trigger_account_id: Option<ID>,
+        schema: RoomSettingsUpdateSchema,
+    ) -> Result<RoomSettingsModel, DatabaseError> {
+        if let Some(account_id) = trigger_account_id {
+            self.authorize(room_id, account_id, Permission::EditRoom)
+                .await?;
+        }
+
+        if let Some(interval) = schema.slow_mode_interval_ms
+            && !(0..=MAX_SLOW_MODE_INTERVAL_MS).contains(&interval)
+        {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "slow mode interval must be between 0 and {} ms",
+                MAX_SLOW_MODE_INTERVAL_MS
+            )));
+        }
+        if let Some(max_length) = schema.max_message_length
+            && !(0..=MAX_MESSAGE_LENGTH).contains(&max_length)
+        {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "max message length must be between 0 and {}",
+                MAX_MESSAGE_LENGTH
+            )));
+        }
+        if schema.pin_role == Some(MemberRole::ReadOnly) {
+            return Err(DatabaseError::ConstraintViolation(
+                "read-only members can't pin messages".to_string(),
+            ));
+        }
+        if let Some(max_pinned) = schema.max_pinned_messages
+            && !(1..=MAX_PINNED_MESSAGES).contains(&max_pinned)
+        {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "max pinned messages must be between 1 and {}",
+                MAX_PINNED_MESSAGES
+            )));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        if !self
+            .room_repository
+            .exists_tx(room_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?
+            .0
+        {
+            return Err(DatabaseError::RecordNotFound("room".to_string()));
+        }
+
+        let current = settings::Entity::find_by_id(room_id)
+            .one(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room settings".to_string()))?;
+        let exists = current.is_some();
+        let mut updated = current.unwrap_or_else(|| RoomSettingsModel::defaults(room_id));
+
+        if let Some(interval) = schema.slow_mode_interval_ms {
+            updated.slow_mode_interval_ms = (interval > 0).then_some(interval);
+        }
+        if let Some(max_length) = schema.max_message_length {
+            updated.max_message_length = (max_length > 0).then_some(max_length);
+        }
+        if let Some(allow_replies) = schema.allow_replies {
+            updated.allow_replies = allow_replies;
+        }
+        if let Some(allow_attachments) = schema.allow_attachments {
+            updated.allow_attachments = allow_attachments;
+        }
+        if let Some(announcement_mode) = schema.announcement_mode {
+            updated.announcement_mode = announcement_mode;
+        }
+        if let Some(pin_role) = schema.pin_role {
+            updated.pin_role = pin_role;
+        }
+        if let Some(max_pinned) = schema.max_pinned_messages {
+            updated.max_pinned_messages = max_pinned;
+        }
+
+        let now = now_millis();
+        let active_model = settings::ActiveModel {
+            room_id: Set(room_id),
+            slow_mode_interval_ms: Set(updated.slow_mode_interval_ms),
+            max_message_length: Set(updated.max_message_length),
+            allow_replies: Set(updated.allow_replies),
+            allow_attachments: Set(updated.allow_attachments),
+            announcement_mode: Set(updated.announcement_mode),
+            pin_role: Set(updated.pin_role),
+            max_pinned_messages: Set(updated.max_pinned_messages),
+            created_at: if exists {
+                sea_orm::ActiveValue::NotSet
+            } else {
+                Set(now)
+            },
+            updated_at: Set(now),
+        };
+        let saved = if exists {
+            active_model.update(&txn).await
+        } else {
+            active_model.insert(&txn).await
+        }
+        .map_err(|_| DatabaseError::UpdateError("room settings".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(saved)
+    }
+
+    /// ## Set Message Hidden
+    ///
+    /// Hides or unhides a message of the room. Requires the `HideMessage` permission.
+    pub async fn set_message_hidden(
+        &self,
+        room_id: ID,
+        message_id: ID,
+        trigger_account_id: ID,
+        is_hidden: bool,
+    ) -> Result<MessageModel, DatabaseError> {
+        self.authorize(room_id, trigger_account_id, Permission::HideMessage)
+            .await?;
+
+        let message = self
+            .message_repository
+            .get_by_id(message_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+            .ok_or_else(|| {
+                DatabaseError::RecordNotFound(format!("Message {} not found", message_id))
+            })?;
+
+        if message.room_id != room_id {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} does not belong to room {}",
+                message_id, room_id
+            )));
+        }
+
+        if message.deleted_at.is_some() {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} is already deleted",
+                message_id
+            )));
+        }
+
+        if message.message_type == MessageType::Summary {
+            return Err(DatabaseError::ConstraintViolation(
+                "summary messages are always hidden".to_string(),
+            ));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let message = self
+            .message_repository
+            .update_tx(
+                message_id,
+                message::ActiveModel {
+                    is_hidden: Set(is_hidden),
+                    ..Default::default()
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("message".to_string()))?;
+        self.refresh_last_message_at_tx([room_id], &txn).await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(message)
+    }
+
+    /// ## Set Member Role
+    ///
+    /// Changes the role of a member. Requires the `ManageRoles` permission, and both the
+    /// current and the new role of the target must be below the trigger's own role.
+    /// Ownership can only be given through `transfer_ownership`.
+    pub async fn set_member_role(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        account_id: ID,
+        role: MemberRole,
+    ) -> Result<MemberModel, DatabaseError> {
+        let trigger_membership = self
+            .authorize(room_id, trigger_account_id, Permission::ManageRoles)
+            .await?;
+
+        if role == MemberRole::Owner {
+            return Err(DatabaseError::ConstraintViolation(
+                "ownership must be transferred".to_string(),
+            ));
+        }
+
+        let target_membership = self
+            .get_member_by_account_id(room_id, account_id)
+            .await?
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+
+        if !trigger_membership.role.outranks(&target_membership.role)
+            || !trigger_membership.role.outranks(&role)
+        {
+            return Err(DatabaseError::ConstraintViolation(
+                "cannot assign a role equal or higher than your own".to_string(),
+            ));
+        }
+
+        self.member_repository
+            .update(
+                target_membership.id,
+                member::ActiveModel {
+                    role: Set(role),
+                    is_owner: Set(false),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("member".to_string()))
+    }
+
+    /// ## Transfer Ownership
+    ///
+    /// Makes another active member the owner of the room. The previous owner becomes an admin.
+    pub async fn transfer_ownership(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        account_id: ID,
+    ) -> Result<(MemberModel, MemberModel), DatabaseError> {
+        let trigger_membership = self
+            .authorize(room_id, trigger_account_id, Permission::TransferOwnership)
+            .await?;
+
+        let target_membership = self.get_active_membership(room_id, account_id).await?;
+
+        if target_membership.id == trigger_membership.id {
+            return Err(DatabaseError::ConstraintViolation(
+                "member is already owner".to_string(),
+            ));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let new_owner = self
+            .member_repository
+            .update_tx(
+                target_membership.id,
+                member::ActiveModel {
+                    role: Set(MemberRole::Owner),
+                    is_owner: Set(true),
+                    ..Default::default()
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("member".to_string()))?;
+
+        let previous_owner = self
+            .member_repository
+            .update_tx(
+                trigger_membership.id,
+                member::ActiveModel {
+                    role: Set(MemberRole::Admin),
+                    is_owner: Set(false),
+                    ..Default::default()
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("member".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok((new_owner, previous_owner))
+    }
+
+    /// ## Create Invite
+    ///
+    /// Invites an account to the room when `invitee_account_id` is given, otherwise creates a
+    /// shareable invite link. Requires the `AddMember` permission. Direct invites can only be
+    /// used once.
+    pub async fn create_invite(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        invitee_account_id: Option<ID>,
+        max_uses: Option<i32>,
+        expires_at: Option<Timestamp>,
+    ) -> Result<InviteModel, DatabaseError> {
+        self.authorize(room_id, trigger_account_id, Permission::AddMember)
+            .await?;
+
+        if max_uses.is_some_and(|max_uses| max_uses <= 0) {
+            return Err(DatabaseError::ConstraintViolation(
+                "max_uses must be greater than 0".to_string(),
+            ));
+        }
+
+        if expires_at.is_some_and(|expires_at| expires_at <= now_millis()) {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite expiry must be in the future".to_string(),
+            ));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let (token, max_uses) = match invitee_account_id {
+            Some(invitee_account_id) => {
+                if !self
+                    .account_repository
+                    .exists_tx(invitee_account_id, &txn)
+                    .await
+                    .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?
+                    .0
+                {
+                    return Err(DatabaseError::RecordNotFound("account".to_string()));
+                }
+
+                if self
+                    .get_member_by_account_id(room_id, invitee_account_id)
+                    .await?
+                    .is_some()
+                {
+                    return Err(DatabaseError::ConstraintViolation(
+                        "account is already a member".to_string(),
+                    ));
+                }
+
+                let pending = invite::Entity::find()
+                    .filter(invite::Column::RoomId.eq(room_id))
+                    .filter(invite::Column::InviteeAccountId.eq(invitee_account_id))
+                    .all(&txn)
+                    .await
+                    .map_err(|_| DatabaseError::QueryFailed("invite".to_string()))?;
+
+                if pending.iter().any(|invite| invite.is_redeemable()) {
+                    return Err(DatabaseError::ConstraintViolation(
+                        "account is already invited".to_string(),
+                    ));
+                }
+
+                (None, Some(1))
+            }
+            None => (Some(uuid::Uuid::new_v4().simple().to_string()), max_uses),
+        };
+
+        let invite = self
+            .room_invite_repository
+            .create_tx(
+                &InviteCreationSchema {
+                    room_id,
+                    inviter_account_id: trigger_account_id,
+                    invitee_account_id,
+                    token,
+                    max_uses,
+                    expires_at,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("invite".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(invite)
+    }
+
+    /// ## Revoke Invite
+    ///
+    /// Revokes an invite so it can't be accepted anymore. Requires the `AddMember` permission.
+    pub async fn revoke_invite(
+        &self,
+        room_id: ID,
+        invite_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<InviteModel, DatabaseError> {
+        self.authorize(room_id, trigger_account_id, Permission::AddMember)
+            .await?;
+
+        let invite = self
+            .room_invite_repository
+            .get_by_id(invite_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("invite".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("invite".to_string()))?;
+
+        if invite.room_id != room_id {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Invite {} does not belong to room {}",
+                invite_id, room_id
+            )));
+        }
+
+        if invite.revoked_at.is_some() {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite is already revoked".to_string(),
+            ));
+        }
+
+        self.room_invite_repository
+            .update(
+                invite_id,
+                invite::ActiveModel {
+                    revoked_at: Set(Some(now_millis())),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("invite".to_string()))
+    }
+
+    /// ## Accept Invite
+    ///
+    /// Accepts a direct invite addressed to `account_id`.
+    pub async fn accept_invite(
+        &self,
+        invite_id: ID,
+        account_id: ID,
+        anonymize: bool,
+    ) -> Result<MemberModel, DatabaseError> {
+        let invite = self
+            .room_invite_repository
+            .get_by_id(invite_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("invite".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("invite".to_string()))?;
+
+        if invite.invitee_account_id != Some(account_id) {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite is not addressed to this account".to_string(),
+            ));
+        }
+
+        self.redeem_invite(invite, account_id, anonymize).await
+    }
+
+    /// ## Accept Invite Link
+    ///
+    /// Joins the room of the invite link identified by `token`.
+    pub async fn accept_invite_link(
+        &self,
+        token: String,
+        account_id: ID,
+        anonymize: bool,
+    ) -> Result<MemberModel, DatabaseError> {
+        let invite = invite::Entity::find()
+            .filter(invite::Column::Token.eq(token))
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("invite".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("invite".to_string()))?;
+
+        self.redeem_invite(invite, account_id, anonymize).await
+    }
+
+    /// ## Decline Invite
+    ///
+    /// Declines a direct invite addressed to `account_id`.
+    pub async fn decline_invite(
+        &self,
+        invite_id: ID,
+        account_id: ID,
+    ) -> Result<InviteModel, DatabaseError> {
+        let invite = self
+            .room_invite_repository
+            .get_by_id(invite_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("invite".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("invite".to_string()))?;
+
+        if invite.invitee_account_id != Some(account_id) {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite is not addressed to this account".to_string(),
+            ));
+        }
+
+        if !invite.is_redeemable() {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite is no longer valid".to_string(),
+            ));
+        }
+
+        self.room_invite_repository
+            .update(
+                invite_id,
+                invite::ActiveModel {
+                    declined_at: Set(Some(now_millis())),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|_| DatabaseError::UpdateError("invite".to_string()))
+    }
+
+    /// ## Get Pending Invites
+    ///
+    /// Lists the direct invites addressed to the account that can still be accepted.
+    pub async fn get_pending_invites(
+        &self,
+        account_id: ID,
+    ) -> Result<Vec<InviteModel>, DatabaseError> {
+        let invites = invite::Entity::find()
+            .filter(invite::Column::InviteeAccountId.eq(account_id))
+            .filter(invite::Column::RevokedAt.is_null())
+            .filter(invite::Column::DeclinedAt.is_null())
+            .filter(invite::Column::DeletedAt.is_null())
+            .order_by(invite::Column::CreatedAt, Order::Desc)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("invites".to_string()))?;
+
+        Ok(invites
+            .into_iter()
+            .filter(|invite| invite.is_redeemable())
+            .collect())
+    }
+
+    /// ## Redeem Invite
+    ///
+    /// Consumes one use of the invite, creates the membership and posts a `RecipientAdded`
+    /// system message, all in one transaction. The use counter is incremented with a guarded
+    /// update so concurrent redemptions can't exceed `max_uses`. Like self-joins, a ban on a
+    /// membership the account has left still applies.
+    async fn redeem_invite(
+        &self,
+        invite: InviteModel,
+        account_id: ID,
+        anonymize: bool,
+    ) -> Result<MemberModel, DatabaseError> {
+        if !invite.is_redeemable() {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite is no longer valid".to_string(),
+            ));
+        }
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        if !self
+            .room_repository
+            .exists_tx(invite.room_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?
+            .0
+        {
+            return Err(DatabaseError::RecordNotFound("room".to_string()));
+        }
+
+        // previous memberships are checked too, so leaving doesn't lift a ban
+        let memberships = MemberEntity::find()
+            .filter(member::Column::RoomId.eq(invite.room_id))
+            .filter(member::Column::AccountId.eq(account_id))
+            .all(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+        member::check_can_join(&memberships).map_err(DatabaseError::ConstraintViolation)?;
+
+        let result = invite::Entity::update_many()
+            .col_expr(invite::Column::Uses, Expr::col(invite::Column::Uses).add(1))
+            .col_expr(invite::Column::UpdatedAt, Expr::value(now_millis()))
+            .filter(invite::Column::Id.eq(invite.id))
+            .filter(
+                Condition::any()
+                    .add(invite::Column::MaxUses.is_null())
+                    .add(Expr::col(invite::Column::Uses).lt(Expr::col(invite::Column::MaxUses))),
+            )
+            .exec(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("invite".to_string()))?;
+
+        if result.rows_affected == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "invite has no uses left".to_string(),
+            ));
+        }
+
+        let member = self
+            .member_repository
+            .create_tx(
+                &MemberCreationSchema {
+                    room_id: invite.room_id,
+                    account_id,
+                    role: MemberRole::Member,
+                    anonymize,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
+
+        self.post_system_message_tx(
+            invite.room_id,
+            Some(member.id),
+            MessageType::RecipientAdded,
+            None,
+            &txn,
+        )
+        .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    /// ## Search Public Rooms
+    ///
+    /// Public room directory. Matches `query` against the room name and description
+    /// (case-insensitive), optionally filtered by room type and model tag.
+    pub async fn search_public_rooms(
+        &self,
+        query: Option<String>,
+        room_type: Option<RoomType>,
+        model_tag: Option<String>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<RoomModel>, DatabaseError> {
+        if limit > 100 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be less than 100".to_string(),
+            ));
+        }
+        if offset > 1000 {
+            return Err(DatabaseError::ConstraintViolation(
+                "offset must be less than 1000".to_string(),
+            ));
+        }
+        if limit == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be greater than 0".to_string(),
+            ));
+        }
+
+        let mut select = room::Entity::find()
+            .filter(room::Column::DeletedAt.is_null())
+            .filter(room::Column::Visibility.eq(RoomVisibility::Public));
+
+        if let Some(query) = query.filter(|q| !q.trim().is_empty()) {
+            let pattern = format!(
+                "%{}%",
+                query
+                    .trim()
+                    .to_lowercase()
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            );
+            select = select.filter(
+                Condition::any()
+                    .add(
+                        Expr::expr(Func::lower(Expr::col(room::Column::Name)))
+                            .like(pattern.clone()),
+                    )
+                    .add(
+                        Expr::expr(Func::lower(Expr::col(room::Column::Description))).like(pattern),
+                    ),
+            );
+        }
+
+        if let Some(room_type) = room_type {
+            select = select.filter(room::Column::RoomType.eq(room_type));
+        }
+
+        if let Some(model_tag) = model_tag {
+            select = select.filter(room::Column::ModelTag.eq(model_tag));
+        }
+
+        select
+            .order_by(room::Column::UpdatedAt, Order::Desc)
+            .limit(limit)
+            .offset(offset)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("rooms".to_string()))
+    }
+
+    /// ## Join Public Room
+    ///
+    /// Lets an account join a public room by itself and posts a `RecipientAdded` system message.
+    /// Accounts with an active ban in the room can't join again.
+    pub async fn join_public_room(
+        &self,
+        room_id: ID,
+        account_id: ID,
+        anonymize: bool,
+    ) -> Result<MemberModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let (exists, room) = self
+            .room_repository
+            .exists_tx(room_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("room".to_string()))?;
+
+        let room = room
+            .filter(|_| exists)
+            .ok_or_else(|| DatabaseError::RecordNotFound("room".to_string()))?;
+
+        if room.visibility != RoomVisibility::Public {
+            return Err(DatabaseError::ConstraintViolation(
+                "room is not public".to_string(),
+            ));
+        }
+
+        if !self
+            .account_repository
+            .exists_tx(account_id, &txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?
+            .0
+        {
+            return Err(DatabaseError::RecordNotFound("account".to_string()));
+        }
+
+        // previous memberships are checked too, so leaving doesn't lift a ban
+        let memberships = MemberEntity::find()
+            .filter(member::Column::RoomId.eq(room_id))
+            .filter(member::Column::AccountId.eq(account_id))
+            .all(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+        member::check_can_join(&memberships).map_err(DatabaseError::ConstraintViolation)?;
+
+        let member = self
+            .member_repository
+            .create_tx(
+                &MemberCreationSchema {
+                    room_id,
+                    account_id,
+                    role: MemberRole::Member,
+                    anonymize,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("member".to_string()))?;
+
+        self.post_system_message_tx(
+            room_id,
+            Some(member.id),
+            MessageType::RecipientAdded,
+            None,
+            &txn,
+        )
+        .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    /// ## Leave Room
+    ///
+    /// Removes the account from the room by its own decision and posts a `RecipientLeft`
+    /// system message, as opposed to `remove_member` which is performed by someone else.
+    /// The last owner of a room has to transfer the ownership before leaving.
+    pub async fn leave_room(
+        &self,
+        room_id: ID,
+        account_id: ID,
+    ) -> Result<MemberModel, DatabaseError> {
+        let membership = self
+            .get_member_by_account_id(room_id, account_id)
+            .await?
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        if membership.role == MemberRole::Owner {
+            let other_owners = MemberEntity::find()
+                .filter(member::Column::RoomId.eq(room_id))
+                .filter(member::Column::Role.eq(MemberRole::Owner))
+                .filter(member::Column::DeletedAt.is_null())
+                .filter(member::Column::Id.ne(membership.id))
+                .count(&txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?;
+
+            if other_owners == 0 {
+                return Err(DatabaseError::ConstraintViolation(
+                    "the last owner must transfer the ownership before leaving".to_string(),
+                ));
+            }
+        }
+
+        let member = self
+            .member_repository
+            .delete_tx(membership.id, &txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("member".to_string()))?;
+
+        self.post_system_message_tx(
+            room_id,
+            Some(member.id),
+            MessageType::RecipientLeft,
+            None,
+            &txn,
+        )
+        .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    /// ## Get Active Membership
+    ///
+    /// Returns the membership of the account in the room, failing when the account
+    /// is not a member of the room or has been banned from it.
+    pub async fn get_active_membership(
+        &self,
+        room_id: ID,
+        account_id: ID,
+    ) -> Result<MemberModel, DatabaseError> {
+        self.get_active_membership_tx(room_id, account_id, self.db())
+            .await
+    }
+
+    async fn get_active_membership_tx(
+        &self,
+        room_id: ID,
+        account_id: ID,
+        txn: &impl ConnectionTrait,
+    ) -> Result<MemberModel, DatabaseError> {
+        let membership = self
+            .get_member_by_account_id_tx(room_id, account_id, txn)
+            .await?
+            .ok_or_else(|| DatabaseError::RecordNotFound("membership".to_string()))?;
+
+        if membership.is_banned() {
+            return Err(DatabaseError::ConstraintViolation(
+                "member is banned".to_string(),
+            ));
+        }
+
+        Ok(membership)
+    }
+
+    /// ## Add Reaction
+    ///
+    /// Reacts to a message with an emoji or shortcode. The trigger account must be an active,
+    /// non-banned member of the room, and can only react once per emoji to the same message.
+    pub async fn add_reaction(
+        &self,
+        room_id: ID,
+        message_id: ID,
+        trigger_account_id: ID,
+        emoji: String,
+    ) -> Result<ReactionModel, DatabaseError> {
+        if !is_valid_reaction(&emoji) {
+            return Err(DatabaseError::ConstraintViolation(
+                "reaction must be an emoji or a :shortcode:".to_string(),
+            ));
+        }
+
+        let membership = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let message = message::Entity::find_by_id(message_id)
+            .one(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+            .ok_or_else(|| {
+                DatabaseError::RecordNotFound(format!("Message {} not found", message_id))
+            })?;
+
+        if message.room_id != room_id {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} does not belong to room {}",
+                message_id, room_id
+            )));
+        }
+        if message.deleted_at.is_some() {
+            return Err(DatabaseError::ConstraintViolation(
+                "message is deleted".to_string(),
+            ));
+        }
+        if message.is_hidden {
+            return Err(DatabaseError::ConstraintViolation(
+                "message is hidden".to_string(),
+            ));
+        }
+
+        let existing =
+            message_reaction::Entity::find_by_id((message_id, membership.id, emoji.clone()))
+                .one(&txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("reaction".to_string()))?;
+
+        if existing.is_some() {
+            return Err(DatabaseError::ConstraintViolation(
+                "reaction already exists".to_string(),
+            ));
+        }
+
+        let reaction = message_reaction::ActiveModel {
+            message_id: Set(message_id),
+            member_id: Set(membership.id),
+            emoji: Set(emoji),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+        }
+        .insert(&txn)
+        .await
+        .map_err(|_| DatabaseError::InsertionError("reaction".to_string()))?;
 
         txn.commit().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
         })?;
 
-        Ok(message)
+        Ok(reaction)
     }
 
-    pub async fn remove_message(
+    /// ## Remove Reaction
+    ///
+    /// Removes a reaction previously left by the trigger account on a message.
+    pub async fn remove_reaction(
         &self,
         room_id: ID,
         message_id: ID,
         trigger_account_id: ID,
-    ) -> Result<MessageModel, DatabaseError> {
+        emoji: String,
+    ) -> Result<ReactionModel, DatabaseError> {
+        let membership = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
         let txn = self.db().begin().await.map_err(|_| {
-            DatabaseError::TransactionFailed(
-                "Failed to start remove_message transaction".to_string(),
-            )
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
         })?;
 
-        let message_to_delete = self
-            .message_repository
-            .get_by_id(message_id)
+        let message = message::Entity::find_by_id(message_id)
+            .one(&txn)
             .await
-            .map_err(|e| DatabaseError::QueryFailed(format!("Failed message query: {}", e)))?
+            .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
             .ok_or_else(|| {
                 DatabaseError::RecordNotFound(format!("Message {} not found", message_id))
             })?;
 
-        if message_to_delete.room_id != room_id {
+        if message.room_id != room_id {
             return Err(DatabaseError::ConstraintViolation(format!(
                 "Message {} does not belong to room {}",
                 message_id, room_id
             )));
         }
 
-        if message_to_delete.deleted_at.is_some() {
-            return Err(DatabaseError::ConstraintViolation(format!(
-                "Message {} is already deleted",
-                message_id
-            )));
-        }
+        let reaction = message_reaction::Entity::find_by_id((message_id, membership.id, emoji))
+            .one(&txn)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("reaction".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("reaction".to_string()))?;
 
-        if message_to_delete.member_id.is_none() {
-            return Err(DatabaseError::ConstraintViolation(format!(
-                "Message {} does not have a member_id",
-                message_id
-            )));
-        }
+        reaction
+            .clone()
+            .delete(&txn)
+            .await
+            .map_err(|_| DatabaseError::DeletionError("reaction".to_string()))?;
 
-        let member_id = message_to_delete.member_id.unwrap();
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
 
-        let trigger_membership = self
-            .get_member_by_account_id(room_id, trigger_account_id)
-            .await?
-            .ok_or_else(|| {
-                DatabaseError::RecordNotFound(format!(
-                    "Trigger account {} not found in room {}",
-                    trigger_account_id, room_id
-                ))
-            })?;
+        Ok(reaction)
+    }
 
-        let author_membership = self
+    /// ## Mark As Read
+    ///
+    /// Advances the read marker of the trigger account up to `message_id`, or up to the latest
+    /// message of the room when no message is given. Only messages `get_messages` shows with
+    /// their content can be read markers. The marker never moves backwards.
+    pub async fn mark_as_read(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        message_id: Option<ID>,
+    ) -> Result<MemberModel, DatabaseError> {
+        let membership = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let message = match message_id {
+            Some(message_id) => message::Entity::find_by_id(message_id)
+                .filter(visible_messages_condition(room_id))
+                .one(&txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+                .ok_or_else(|| {
+                    DatabaseError::RecordNotFound(format!(
+                        "Message {} not found in room {}",
+                        message_id, room_id
+                    ))
+                })?,
+            None => message::Entity::find()
+                .filter(visible_messages_condition(room_id))
+                .order_by(message::Column::CreatedAt, Order::Desc)
+                .one(&txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+                .ok_or_else(|| DatabaseError::RecordNotFound("message".to_string()))?,
+        };
+
+        if membership
+            .last_read_at
+            .is_some_and(|last_read_at| last_read_at >= message.created_at)
+        {
+            return Ok(membership);
+        }
+
+        let member = self
             .member_repository
-            .get_by_id(member_id)
+            .update_tx(
+                membership.id,
+                member::ActiveModel {
+                    last_read_message_id: Set(Some(message.id)),
+                    last_read_at: Set(Some(message.created_at)),
+                    ..Default::default()
+                },
+                &txn,
+            )
             .await
-            .map_err(|_| DatabaseError::QueryFailed("membership".to_string()))?
-            .ok_or_else(|| {
-                DatabaseError::RecordNotFound(format!(
-                    "Message author {} not found in room {}",
-                    message_id,
-                    room_id
-                ))
-            })?;
+            .map_err(|_| DatabaseError::UpdateError("member".to_string()))?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(member)
+    }
+
+    /// ## Get Unread Count
+    ///
+    /// Counts the visible messages of the room created after the read marker of the trigger
+    /// account, excluding the ones the account wrote itself.
+    pub async fn get_unread_count(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<u64, DatabaseError> {
+        let membership = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let mut query = message::Entity::find()
+            .filter(message::Column::RoomId.eq(room_id))
+            .filter(message::Column::DeletedAt.is_null())
+            .filter(message::Column::IsHidden.eq(false))
+            .filter(
+                Condition::any()
+                    .add(message::Column::MemberId.is_null())
+                    .add(message::Column::MemberId.ne(membership.id)),
+            );
+
+        if let Some(last_read_at) = membership.last_read_at {
+            query = query.filter(message::Column::CreatedAt.gt(last_read_at));
+        }
 
-        let is_author = author_membership.account_id == trigger_account_id;
-        let is_owner = trigger_membership.is_owner;
+        query
+            .count(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))
+    }
 
-        if !is_author && !is_owner {
+    /// ## Get Rooms With Unread Messages
+    ///
+    /// Lists every room the account is an active member of that has unread messages, with
+    /// their unread count, most recently active first. Computed in a single aggregate query.
+    pub async fn get_rooms_with_unread_messages(
+        &self,
+        account_id: ID,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<RoomUnreadCount>, DatabaseError> {
+        if limit > 100 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be less than 100".to_string(),
+            ));
+        }
+        if offset > 1000 {
+            return Err(DatabaseError::ConstraintViolation(
+                "offset must be less than 1000".to_string(),
+            ));
+        }
+        if limit == 0 {
             return Err(DatabaseError::ConstraintViolation(
-                "User is not the message author or room owner".to_string(),
+                "limit must be greater than 0".to_string(),
             ));
         }
 
-        let deleted_message = self
-            .message_repository
-            .delete_tx(message_id, &txn)
+        let statement = Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            r#"
+            SELECT mb.room_id AS room_id,
+                   COUNT(msg.id) AS unread_count,
+                   MAX(msg.created_at) AS last_message_at
+            FROM member mb
+            INNER JOIN room r ON r.id = mb.room_id AND r.deleted_at IS NULL
+            INNER JOIN message msg ON msg.room_id = mb.room_id
+                AND msg.created_at > COALESCE(mb.last_read_at, 0)
+                AND msg.deleted_at IS NULL
+                AND msg.is_hidden = FALSE
+                AND (msg.member_id IS NULL OR msg.member_id <> mb.id)
+            WHERE mb.account_id = $1
+                AND mb.deleted_at IS NULL
+                AND (mb.banned_at IS NULL OR mb.ban_expires_at <= $2)
+            GROUP BY mb.room_id
+            ORDER BY last_message_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            [
+                account_id.into(),
+                now_millis().into(),
+                (limit as i64).into(),
+                (offset as i64).into(),
+            ],
+        );
+
+        RoomUnreadCount::find_by_statement(statement)
+            .all(self.db())
             .await
-            .map_err(|e| {
-                DatabaseError::DeletionError(format!(
-                    "Failed to delete message {}: {}",
-                    message_id, e
-                ))
-            })?;
+            .map_err(|_| DatabaseError::QueryFailed("unread rooms".to_string()))
+    }
 
-        txn.commit().await.map_err(|_| {
-            DatabaseError::TransactionFailed(
-                "Failed to commit remove_message transaction".to_string(),
-            )
-        })?;
+    /// ## List Rooms For Account
+    ///
+    /// The rooms the account is an active member of, most recently active first, each with
+    /// its latest visible message, unread count and number of members who aren't banned.
+    /// Pages are chained with the `next_cursor` of the previous one.
+    pub async fn list_rooms_for_account(
+        &self,
+        account_id: ID,
+        limit: u64,
+        cursor: Option<RoomActivityCursor>,
+    ) -> Result<RoomActivityPage, DatabaseError> {
+        if limit > 100 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be less than 100".to_string(),
+            ));
+        }
+        if limit == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be greater than 0".to_string(),
+            ));
+        }
 
-        Ok(deleted_message)
+        let mut values: Vec<sea_orm::Value> = vec![
+            account_id.into(),
+            now_millis().into(),
+            (limit as i64 + 1).into(),
+        ];
+        let after_cursor = match cursor {
+            Some(cursor) => {
+                values.push(cursor.last_activity_at.into());
+                values.push(cursor.room_id.into());
+                "AND (COALESCE(r.last_message_at, r.created_at), r.id) < ($4, $5)"
+            }
+            None => "",
+        };
+
+        let statement = Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            format!(
+                r#"
+                SELECT r.id AS room_id,
+                       COALESCE(r.last_message_at, r.created_at) AS last_activity_at,
+                       (SELECT msg.id FROM message msg
+                        WHERE msg.room_id = r.id
+                            AND msg.deleted_at IS NULL
+                            AND msg.is_hidden = FALSE
+                        ORDER BY msg.created_at DESC, msg.id DESC
+                        LIMIT 1) AS last_message_id,
+                       (SELECT COUNT(*) FROM message msg
+                        WHERE msg.room_id = r.id
+                            AND msg.created_at > COALESCE(mb.last_read_at, 0)
+                            AND msg.deleted_at IS NULL
+                            AND msg.is_hidden = FALSE
+                            AND (msg.member_id IS NULL OR msg.member_id <> mb.id)) AS unread_count,
+                       (SELECT COUNT(*) FROM member other
+                        WHERE other.room_id = r.id
+                            AND other.deleted_at IS NULL
+                            AND (other.banned_at IS NULL OR other.ban_expires_at <= $2)) AS member_count
+                FROM member mb
+                INNER JOIN room r ON r.id = mb.room_id AND r.deleted_at IS NULL
+                WHERE mb.account_id = $1
+                    AND mb.deleted_at IS NULL
+                    AND (mb.banned_at IS NULL OR mb.ban_expires_at <= $2)
+                    {}
+                ORDER BY last_activity_at DESC, r.id DESC
+                LIMIT $3
+                "#,
+                after_cursor
+            ),
+            values,
+        );
+
+        let mut rows = RoomActivityRow::find_by_statement(statement)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("rooms".to_string()))?;
+
+        let next_cursor = if rows.len() as u64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().map(|row| {
+                RoomActivityCursor {
+                    last_activity_at: row.last_activity_at,
+                    room_id: row.room_id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        let mut rooms: HashMap<ID, RoomModel> = room::Entity::find()
+            .filter(room::Column::Id.is_in(rows.iter().map(|row| row.room_id)))
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("rooms".to_string()))?
+            .into_iter()
+            .map(|room| (room.id, room))
+            .collect();
+        let mut last_messages: HashMap<ID, MessageModel> = message::Entity::find()
+            .filter(message::Column::Id.is_in(rows.iter().filter_map(|row| row.last_message_id)))
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect();
+
+        Ok(RoomActivityPage {
+            rooms: rows
+                .into_iter()
+                .filter_map(|row| {
+                    rooms.remove(&row.room_id).map(|room| RoomActivity {
+                        room,
+                        last_activity_at: row.last_activity_at,
+                        last_message: row.last_message_id.and_then(|id| last_messages.remove(&id)),
+                        unread_count: row.unread_count,
+                        member_count: row.member_count,
+                    })
+                })
+                .collect(),
+            next_cursor,
+        })
     }
 
     pub async fn has_room_ownership(
@@ -518,7 +4659,7 @@ impl RoomService {
             return Ok(false);
         }
 
-        Ok(member.is_owner)
+        Ok(member.role == MemberRole::Owner)
     }
 
     pub async fn has_template_ownership(
@@ -530,26 +4671,264 @@ impl RoomService {
             .room_template_repository
             .get_by_id(template_id)
             .await
-            .map_err(|_| DatabaseError::QueryFailed("template".to_string()))?;
+            .map_err(|_| DatabaseError::QueryFailed("template".to_string()))?;
+
+        if template.is_none() {
+            return Ok(false);
+        }
+
+        let template = template.unwrap();
+        if template.deleted_at.is_some() {
+            return Ok(false);
+        }
+
+        Ok(template.author_id == Some(account_id))
+    }
+
+    /// ## Get Messages
+    ///
+    /// Fetches a page of the messages of the room, newest first, as seen by the trigger
+    /// account, which must be an active member. Hidden and deleted messages keep their place
+    /// as tombstones.
+    pub async fn get_messages(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        limit: u64,
+        offset: u64,
+    ) -> Result<MessagePage, DatabaseError> {
+        if limit > 100 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be less than 100".to_string(),
+            ));
+        }
+        if offset > 1000 {
+            return Err(DatabaseError::ConstraintViolation(
+                "offset must be less than 1000".to_string(),
+            ));
+        }
+        if limit == 0 {
+            return Err(DatabaseError::ConstraintViolation(
+                "limit must be greater than 0".to_string(),
+            ));
+        }
+        let viewer = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let messages = message::Entity::find()
+            .filter(message::Column::RoomId.eq(room_id))
+            .filter(message::Column::MessageType.ne(MessageType::Summary))
+            .order_by(message::Column::CreatedAt, Order::Desc)
+            .limit(limit)
+            .offset(offset)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+
+        let messages: Vec<RoomMessage> = self
+            .build_room_messages(messages, Some(viewer.id))
+            .await?
+            .into_iter()
+            .map(tombstone_if_removed)
+            .collect();
+        let identities = self
+            .get_member_identities(room_id, &message_member_ids(&messages), Some(&viewer))
+            .await?;
+
+        Ok(MessagePage {
+            messages,
+            identities,
+        })
+    }
+
+    /// ## Build Room Messages
+    ///
+    /// Aggregates the reactions of the given messages per emoji, flagging the ones left by
+    /// `trigger_member_id`, and counts their visible direct replies.
+    async fn build_room_messages(
+        &self,
+        messages: Vec<MessageModel>,
+        trigger_member_id: Option<ID>,
+    ) -> Result<Vec<RoomMessage>, DatabaseError> {
+        let message_ids: Vec<ID> = messages.iter().map(|m| m.id).collect();
+        if message_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let counts = message_reaction::Entity::find()
+            .select_only()
+            .column(message_reaction::Column::MessageId)
+            .column(message_reaction::Column::Emoji)
+            .column_as(message_reaction::Column::MemberId.count(), "count")
+            .filter(message_reaction::Column::MessageId.is_in(message_ids.clone()))
+            .group_by(message_reaction::Column::MessageId)
+            .group_by(message_reaction::Column::Emoji)
+            .order_by(message_reaction::Column::Emoji, Order::Asc)
+            .into_model::<ReactionCount>()
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("reactions".to_string()))?;
+
+        let mut own_reactions: HashSet<(ID, String)> = HashSet::new();
+        if let Some(member_id) = trigger_member_id {
+            own_reactions = message_reaction::Entity::find()
+                .filter(message_reaction::Column::MemberId.eq(member_id))
+                .filter(message_reaction::Column::MessageId.is_in(message_ids.clone()))
+                .all(self.db())
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("reactions".to_string()))?
+                .into_iter()
+                .map(|r| (r.message_id, r.emoji))
+                .collect();
+        }
+
+        let reply_counts: HashMap<ID, i64> = message::Entity::find()
+            .select_only()
+            .column(message::Column::ReplyTo)
+            .column_as(message::Column::Id.count(), "count")
+            .filter(message::Column::ReplyTo.is_in(message_ids))
+            .filter(message::Column::DeletedAt.is_null())
+            .filter(message::Column::IsHidden.eq(false))
+            .group_by(message::Column::ReplyTo)
+            .into_model::<ReplyCount>()
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("replies".to_string()))?
+            .into_iter()
+            .map(|r| (r.reply_to, r.count))
+            .collect();
+
+        let mut reactions: HashMap<ID, Vec<ReactionSummary>> = HashMap::new();
+        for count in counts {
+            let reacted = own_reactions.contains(&(count.message_id, count.emoji.clone()));
+            reactions
+                .entry(count.message_id)
+                .or_default()
+                .push(ReactionSummary {
+                    emoji: count.emoji,
+                    count: count.count,
+                    reacted,
+                });
+        }
+
+        Ok(messages
+            .into_iter()
+            .map(|message| RoomMessage {
+                reactions: reactions.remove(&message.id).unwrap_or_default(),
+                reply_count: reply_counts.get(&message.id).copied().unwrap_or(0),
+                tombstone: false,
+                message,
+            })
+            .collect())
+    }
+
+    /// ## Get Thread
+    ///
+    /// Fetches a page of the replies to `message_id`, including nested replies up to `max_depth`
+    /// levels, oldest first. Hidden or deleted messages that still lead to a visible reply are
+    /// returned as tombstones so the thread doesn't break; the others are left out. The
+    /// trigger account must be an active member.
+    pub async fn get_thread(
+        &self,
+        room_id: ID,
+        message_id: ID,
+        trigger_account_id: ID,
+        max_depth: u32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<MessageThread, DatabaseError> {
+        validate_thread_page(max_depth, limit, offset)?;
+        let viewer = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let root = self
+            .message_repository
+            .get_by_id(message_id)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
+            .ok_or_else(|| {
+                DatabaseError::RecordNotFound(format!("Message {} not found", message_id))
+            })?;
+
+        if root.room_id != room_id {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "Message {} does not belong to room {}",
+                message_id, room_id
+            )));
+        }
+
+        let statement = thread_statement(
+            self.db().get_database_backend(),
+            room_id,
+            message_id,
+            max_depth,
+            limit,
+            offset,
+        );
+
+        let rows = self
+            .db()
+            .query_all(statement)
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("thread".to_string()))?;
 
-        if template.is_none() {
-            return Ok(false);
+        let mut replies = Vec::with_capacity(rows.len());
+        let mut depths = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let reply = MessageModel::from_query_result(&row, "")
+                .map_err(|_| DatabaseError::RetrievalError("thread".to_string()))?;
+            let depth: i32 = row
+                .try_get("", "depth")
+                .map_err(|_| DatabaseError::RetrievalError("thread".to_string()))?;
+            depths.insert(reply.id, depth);
+            replies.push(reply);
         }
 
-        let template = template.unwrap();
-        if template.deleted_at.is_some() {
-            return Ok(false);
-        }
+        let root = self
+            .build_room_messages(vec![root], Some(viewer.id))
+            .await?
+            .into_iter()
+            .map(tombstone_if_removed)
+            .next()
+            .ok_or_else(|| DatabaseError::RetrievalError("thread".to_string()))?;
 
-        Ok(template.author_id == Some(account_id))
+        let replies: Vec<ThreadMessage> = self
+            .build_room_messages(replies, Some(viewer.id))
+            .await?
+            .into_iter()
+            .map(|reply| ThreadMessage {
+                depth: depths.get(&reply.message.id).copied().unwrap_or(1),
+                message: tombstone_if_removed(reply),
+            })
+            .collect();
+
+        let member_ids =
+            message_member_ids(std::iter::once(&root).chain(replies.iter().map(|r| &r.message)));
+        let identities = self
+            .get_member_identities(room_id, &member_ids, Some(&viewer))
+            .await?;
+
+        Ok(MessageThread {
+            root,
+            replies,
+            identities,
+        })
     }
 
-    pub async fn get_messages(
+    /// ## Get Members
+    ///
+    /// Lists a page of the active members of the room, most recent first, with their
+    /// identities as seen by the trigger account, which must be an active member. Accounts
+    /// are only revealed as far as `privacy_policy` allows.
+    pub async fn get_members(
         &self,
         room_id: ID,
+        trigger_account_id: ID,
         limit: u64,
         offset: u64,
-    ) -> Result<Vec<MessageModel>, DatabaseError> {
+    ) -> Result<Vec<(MemberModel, MemberIdentity)>, DatabaseError> {
         if limit > 100 {
             return Err(DatabaseError::ConstraintViolation(
                 "limit must be less than 100".to_string(),
@@ -565,128 +4944,630 @@ impl RoomService {
                 "limit must be greater than 0".to_string(),
             ));
         }
+        let viewer = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
 
-        let messages = message::Entity::find()
-            .filter(message::Column::RoomId.eq(room_id))
-            .order_by(message::Column::CreatedAt, Order::Desc)
+        let members = member::Entity::find()
+            .filter(member::Column::RoomId.eq(room_id))
+            .order_by(member::Column::CreatedAt, Order::Desc)
+            .filter(member::Column::DeletedAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(member::Column::BannedAt.is_null())
+                    .add(member::Column::BanExpiresAt.lte(now_millis())),
+            )
             .limit(limit)
             .offset(offset)
             .all(self.db())
             .await
-            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+            .map_err(|_| DatabaseError::QueryFailed("members".to_string()))?;
 
-        Ok(messages)
+        let member_ids: HashSet<ID> = members.iter().map(|member| member.id).collect();
+        let mut identities = self
+            .get_member_identities(room_id, &member_ids, Some(&viewer))
+            .await?;
+        Ok(members
+            .into_iter()
+            .filter_map(|member| {
+                identities
+                    .remove(&member.id)
+                    .map(|identity| (member, identity))
+            })
+            .collect())
     }
 
-    pub async fn get_members(
+    /// ## Get Member Identities
+    ///
+    /// How the members `member_ids` of the room appear to `viewer`, by member id:
+    /// handles and avatars for anonymized members, and accounts only as far as
+    /// `privacy_policy` allows. Without a viewer, no anonymized account is revealed.
+    pub async fn get_member_identities(
         &self,
         room_id: ID,
-        limit: u64,
-        offset: u64,
-    ) -> Result<Vec<MemberModel>, DatabaseError> {
-        if limit > 100 {
-            return Err(DatabaseError::ConstraintViolation(
-                "limit must be less than 100".to_string(),
-            ));
-        }
-        if offset > 1000 {
-            return Err(DatabaseError::ConstraintViolation(
-                "offset must be less than 1000".to_string(),
-            ));
-        }
-        if limit == 0 {
-            return Err(DatabaseError::ConstraintViolation(
-                "limit must be greater than 0".to_string(),
-            ));
+        member_ids: &HashSet<ID>,
+        viewer: Option<&MemberModel>,
+    ) -> Result<HashMap<ID, MemberIdentity>, DatabaseError> {
+        if member_ids.is_empty() {
+            return Ok(HashMap::new());
         }
 
         let members = member::Entity::find()
             .filter(member::Column::RoomId.eq(room_id))
-            .order_by(member::Column::CreatedAt, Order::Desc)
-            .filter(member::Column::DeletedAt.is_null())
-            .filter(member::Column::BannedAt.is_null())
-            .limit(limit)
-            .offset(offset)
+            .filter(member::Column::Id.is_in(member_ids.iter().copied()))
+            .find_also_related(account::Entity)
             .all(self.db())
             .await
             .map_err(|_| DatabaseError::QueryFailed("members".to_string()))?;
 
-        Ok(members)
+        Ok(members
+            .into_iter()
+            .map(|(member, account)| {
+                let identity = self.identity_generator.member_identity(
+                    &member,
+                    account.as_ref().and_then(|a| a.name.as_deref()),
+                    viewer,
+                    self.privacy_policy,
+                );
+                (member.id, identity)
+            })
+            .collect())
     }
 
+    /// ## Search Templates
+    ///
+    /// Full-text search of the public templates with at least one published version,
+    /// matching their name and, with a lower weight, their description. Results are ranked
+    /// by relevance, the last term of the query matching as a prefix. An empty query lists
+    /// the most recent templates.
     pub async fn search_templates(
         &self,
         query: String,
         limit: u64,
         offset: u64,
-    ) -> Result<Vec<RoomTemplateModel>, DatabaseError> {
-        if limit > 10 {
-            return Err(DatabaseError::ConstraintViolation(
-                "limit must be less than 10".to_string(),
-            ));
-        }
-        if offset > 100 {
-            return Err(DatabaseError::ConstraintViolation(
-                "offset must be less than 1000".to_string(),
-            ));
-        }
-        if limit == 0 {
-            return Err(DatabaseError::ConstraintViolation(
-                "limit must be greater than 0".to_string(),
-            ));
-        }
+    ) -> Result<Vec<TemplateSearchHit>, DatabaseError> {
+        validate_search_page(limit, offset)?;
 
-        let templates = template::Entity::find()
-            .filter(template::Column::DeletedAt.is_null())
-            .filter(template::Column::Name.contains(query))
-            .order_by(template::Column::CreatedAt, Order::Desc)
-            .limit(limit)
-            .offset(offset)
-            .all(self.db())
+        let Some(tsquery) = prefix_tsquery(&query) else {
+            let templates = template::Entity::find()
+                .filter(template::Column::DeletedAt.is_null())
+                .filter(template::Column::Visibility.eq(TemplateVisibility::Public))
+                .filter(template::Column::LatestVersion.gt(0))
+                .order_by(template::Column::CreatedAt, Order::Desc)
+                .limit(limit)
+                .offset(offset)
+                .all(self.db())
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("templates".to_string()))?;
+
+            return Ok(templates
+                .into_iter()
+                .map(|template| TemplateSearchHit {
+                    template,
+                    rank: 0.0,
+                    snippet: None,
+                })
+                .collect());
+        };
+
+        let statement = Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            format!(
+                r#"
+                SELECT t.*,
+                       ts_rank_cd(t.search_vector, q) AS rank,
+                       CASE WHEN t.description IS NULL THEN NULL
+                            ELSE ts_headline('{0}', t.description, q, $2)
+                       END AS snippet
+                FROM room_template t, to_tsquery('{0}', $1) q
+                WHERE t.search_vector @@ q
+                    AND t.deleted_at IS NULL
+                    AND t.visibility = $3
+                    AND t.latest_version > 0
+                ORDER BY rank DESC, t.created_at DESC
+                LIMIT $4 OFFSET $5
+                "#,
+                self.search_language.regconfig()
+            ),
+            [
+                tsquery.into(),
+                headline_options().into(),
+                TemplateVisibility::Public.into(),
+                (limit as i64).into(),
+                (offset as i64).into(),
+            ],
+        );
+
+        let rows = self
+            .db()
+            .query_all(statement)
             .await
             .map_err(|_| DatabaseError::QueryFailed("templates".to_string()))?;
 
-        Ok(templates)
+        rows.iter()
+            .map(|row| {
+                Ok(TemplateSearchHit {
+                    template: RoomTemplateModel::from_query_result(row, "")?,
+                    rank: row.try_get("", "rank")?,
+                    snippet: row.try_get("", "snippet")?,
+                })
+            })
+            .collect::<Result<Vec<_>, DbErr>>()
+            .map_err(|_| DatabaseError::RetrievalError("templates".to_string()))
     }
 
+    /// ## Search Messages
+    ///
+    /// Full-text search of the visible messages of a room, ranked by relevance with
+    /// highlighted snippets. The trigger account must be an active member of the room.
     pub async fn search_messages(
         &self,
         room_id: ID,
+        trigger_account_id: ID,
         query: String,
         limit: u64,
         offset: u64,
-    ) -> Result<Vec<MessageModel>, DatabaseError> {
-        if limit > 10 {
-            return Err(DatabaseError::ConstraintViolation(
-                "limit must be less than 100".to_string(),
-            ));
+    ) -> Result<Vec<MessageSearchHit>, DatabaseError> {
+        validate_search_page(limit, offset)?;
+        self.get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let Some(tsquery) = prefix_tsquery(&query) else {
+            return Ok(Vec::new());
+        };
+
+        self.query_message_hits(
+            "m.room_id = $3",
+            vec![room_id.into()],
+            tsquery,
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    /// ## Search Account Messages
+    ///
+    /// Like `search_messages`, across every room the account is an active, non-banned
+    /// member of.
+    pub async fn search_account_messages(
+        &self,
+        account_id: ID,
+        query: String,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<MessageSearchHit>, DatabaseError> {
+        validate_search_page(limit, offset)?;
+
+        let Some(tsquery) = prefix_tsquery(&query) else {
+            return Ok(Vec::new());
+        };
+
+        self.query_message_hits(
+            r#"EXISTS (
+                SELECT 1 FROM member mb
+                INNER JOIN room r ON r.id = mb.room_id AND r.deleted_at IS NULL
+                WHERE mb.room_id = m.room_id
+                    AND mb.account_id = $3
+                    AND mb.deleted_at IS NULL
+                    AND (mb.banned_at IS NULL OR mb.ban_expires_at <= $4)
+            )"#,
+            vec![account_id.into(), now_millis().into()],
+            tsquery,
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    /// Runs a message search restricted by `scope`, a condition on the message `m` whose
+    /// parameters start at `$3`.
+    async fn query_message_hits(
+        &self,
+        scope: &str,
+        scope_values: Vec<sea_orm::Value>,
+        tsquery: String,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<MessageSearchHit>, DatabaseError> {
+        let next = scope_values.len() + 3;
+        let sql = format!(
+            r#"
+            SELECT m.*,
+                   ts_rank_cd(m.search_vector, q) AS rank,
+                   ts_headline('{0}', coalesce(m.content, ''), q, $2) AS snippet
+            FROM message m, to_tsquery('{0}', $1) q
+            WHERE m.search_vector @@ q
+                AND {1}
+                AND m.deleted_at IS NULL
+                AND m.is_hidden = FALSE
+                AND m.type <> ${2}
+            ORDER BY rank DESC, m.created_at DESC
+            LIMIT ${3} OFFSET ${4}
+            "#,
+            self.search_language.regconfig(),
+            scope,
+            next,
+            next + 1,
+            next + 2
+        );
+
+        let mut values: Vec<sea_orm::Value> = vec![tsquery.into(), headline_options().into()];
+        values.extend(scope_values);
+        values.extend([
+            MessageType::Summary.into(),
+            (limit as i64).into(),
+            (offset as i64).into(),
+        ]);
+
+        let rows = self
+            .db()
+            .query_all(Statement::from_sql_and_values(
+                self.db().get_database_backend(),
+                sql,
+                values,
+            ))
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(MessageSearchHit {
+                    message: MessageModel::from_query_result(row, "")?,
+                    rank: row.try_get("", "rank")?,
+                    snippet: row.try_get("", "snippet")?,
+                })
+            })
+            .collect::<Result<Vec<_>, DbErr>>()
+            .map_err(|_| DatabaseError::RetrievalError("messages".to_string()))
+    }
+
+    /// ## Import Room
+    ///
+    /// Creates a room out of an archive parsed by `parse_import`, with the account as
+    /// owner. Messages keep their timestamps, replies and pins. Every author of the archive
+    /// other than `own_author` gets an anonymized placeholder member without account, see
+    /// `member::placeholder`. The imported messages are marked as read for the owner.
+    /// Everything is created in a single transaction.
+    pub async fn import_room(
+        &self,
+        schema: RoomImportSchema,
+    ) -> Result<ImportReport, CadenceError> {
+        if schema.room_type == RoomType::Direct {
+            return Err(CadenceError::Database(DatabaseError::ConstraintViolation(
+                "direct rooms are opened with get_or_create_direct_room".to_string(),
+            )));
         }
-        if offset > 100 {
-            return Err(DatabaseError::ConstraintViolation(
-                "offset must be less than 1000".to_string(),
-            ));
+
+        let mut plan = ImportPlan::new(schema.conversation, schema.own_author.as_deref())?;
+        if schema.name.is_some() {
+            plan.name = schema.name;
         }
-        if limit == 0 {
-            return Err(DatabaseError::ConstraintViolation(
-                "limit must be greater than 0".to_string(),
-            ));
+        if schema.model_tag.is_some() {
+            plan.model_tag = schema.model_tag;
         }
 
-        let messages = message::Entity::find()
-            .filter(message::Column::IsHidden.eq(false))
-            .filter(message::Column::DeletedAt.is_null())
-            .filter(message::Column::RoomId.eq(room_id))
-            .filter(message::Column::Content.contains(query))
-            .order_by(message::Column::CreatedAt, Order::Desc)
-            .limit(limit)
-            .offset(offset)
-            .all(self.db())
+        let txn = self.db().begin().await.map_err(|_| {
+            CadenceError::Database(DatabaseError::TransactionFailed(
+                "Failed to start transaction".to_string(),
+            ))
+        })?;
+
+        if !self
+            .account_repository
+            .exists_tx(schema.account_id, &txn)
             .await
-            .map_err(|_| DatabaseError::QueryFailed("messages".to_string()))?;
+            .map_err(|_| CadenceError::Database(DatabaseError::QueryFailed("account".to_string())))?
+            .0
+        {
+            return Err(CadenceError::Database(DatabaseError::RecordNotFound(
+                "account".to_string(),
+            )));
+        }
+
+        if schema.dry_run {
+            return Ok(plan.report(None));
+        }
+
+        let room = self
+            .room_repository
+            .create_tx(
+                &RoomCreationSchema {
+                    name: plan
+                        .name
+                        .clone()
+                        .or_else(|| Some("Imported conversation".to_string())),
+                    description: plan.description.clone(),
+                    icon_url: None,
+                    background_url: None,
+                    visibility: schema.visibility,
+                    template_id: None,
+                    template_version_id: None,
+                    model_tag: plan.model_tag.clone(),
+                    system_prompt: plan.system_prompt.clone(),
+                    room_type: schema.room_type,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::InsertionError("room".to_string()))
+            })?;
+        self.open_ticket_tx(&room, schema.account_id, &txn)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        let owner = self
+            .member_repository
+            .create_tx(
+                &MemberCreationSchema {
+                    room_id: room.id,
+                    account_id: schema.account_id,
+                    role: MemberRole::Owner,
+                    anonymize: false,
+                },
+                &txn,
+            )
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::InsertionError("member".to_string()))
+            })?;
+
+        let now = now_millis();
+        let mut placeholder_ids = Vec::with_capacity(plan.placeholders.len());
+        for _ in &plan.placeholders {
+            let placeholder = member::placeholder(room.id, now)
+                .insert(&txn)
+                .await
+                .map_err(|_| {
+                    CadenceError::Database(DatabaseError::InsertionError("placeholder".to_string()))
+                })?;
+            placeholder_ids.push(placeholder.id);
+        }
+
+        let message_ids: Vec<ID> = plan.messages.iter().map(|_| uuid::Uuid::new_v4()).collect();
+        let messages: Vec<message::ActiveModel> = plan
+            .messages
+            .iter()
+            .zip(&message_ids)
+            .map(|(planned, id)| message::ActiveModel {
+                id: Set(*id),
+                room_id: Set(room.id),
+                member_id: Set(planned.member.map(|member| match member {
+                    PlannedMember::Own => owner.id,
+                    PlannedMember::Placeholder(index) => placeholder_ids[index],
+                })),
+                system: Set(planned.system),
+                model_tag: Set(planned.model_tag.clone()),
+                content: Set(planned.content.clone()),
+                attachment_id: Set(None),
+                reply_to: Set(planned.reply_to.map(|index| message_ids[index])),
+                message_type: Set(planned.message_type.clone()),
+                is_hidden: Set(false),
+                pinned_at: Set(planned.pinned_at),
+                pinned_by: Set(None),
+                summarized_until: Set(None),
+                expires_at: Set(None),
+                deleted_at: Set(None),
+                created_at: Set(planned.created_at),
+                updated_at: Set(now),
+            })
+            .collect();
+
+        // replies always point to earlier messages, so batches are inserted in order
+        for batch in messages.chunks(IMPORT_BATCH_SIZE) {
+            message::Entity::insert_many(batch.to_vec())
+                .exec(&txn)
+                .await
+                .map_err(|_| {
+                    CadenceError::Database(DatabaseError::InsertionError("messages".to_string()))
+                })?;
+        }
+        // the importing account already knows the conversation, so it is marked as read
+        if let Some((index, last)) = plan
+            .messages
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, m)| m.created_at)
+        {
+            self.touch_room_tx(room.id, last.created_at, &txn)
+                .await
+                .map_err(CadenceError::Database)?;
+
+            member::ActiveModel {
+                id: Set(owner.id),
+                last_read_message_id: Set(Some(message_ids[index])),
+                last_read_at: Set(Some(last.created_at)),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await
+            .map_err(|_| {
+                CadenceError::Database(DatabaseError::UpdateError("member".to_string()))
+            })?;
+        }
+
+        txn.commit().await.map_err(|_| {
+            CadenceError::Database(DatabaseError::TransactionFailed(
+                "Failed to commit transaction".to_string(),
+            ))
+        })?;
 
-        Ok(messages)
+        Ok(plan.report(Some(room)))
     }
 
+    /// ## Export Room
+    ///
+    /// Streams the conversation of a room to `writer`: every visible, non-deleted message
+    /// in chronological order with the display name of its author, read `EXPORT_BATCH_SIZE`
+    /// messages at a time. Anonymized members are named by placeholders, and attachments
+    /// are linked under `attachment_base_url`. The trigger account must be an active member
+    /// of the room.
+    ///
+    /// Returns the number of exported messages.
+    pub async fn export_room(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        attachment_base_url: &str,
+        writer: &mut dyn ExportWriter,
+    ) -> Result<u64, CadenceError> {
+        let write_failed = |e: std::io::Error| {
+            CadenceError::ServerError(ServerError::InternalError(e.to_string()))
+        };
+
+        self.get_active_membership(room_id, trigger_account_id)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        let room = self
+            .room_repository
+            .get_by_id(room_id)
+            .await
+            .map_err(|_| CadenceError::Database(DatabaseError::QueryFailed("room".to_string())))?
+            .filter(|room| room.deleted_at.is_none())
+            .ok_or_else(|| {
+                CadenceError::Database(DatabaseError::RecordNotFound("room".to_string()))
+            })?;
+
+        let mut authors: HashMap<ID, MemberIdentity> = HashMap::new();
+
+        writer
+            .begin(&ExportedRoom {
+                id: room.id,
+                name: room.name,
+                description: room.description,
+                model_tag: room.model_tag,
+                exported_at: now_millis(),
+            })
+            .map_err(write_failed)?;
+
+        let mut count = 0;
+        let mut cursor: Option<(Timestamp, ID)> = None;
+        loop {
+            let mut query = message::Entity::find()
+                .filter(message::Column::RoomId.eq(room_id))
+                .filter(message::Column::DeletedAt.is_null())
+                .filter(message::Column::IsHidden.eq(false))
+                .filter(message::Column::MessageType.ne(MessageType::Summary));
+            if let Some((created_at, id)) = cursor {
+                query = query.filter(
+                    Condition::any()
+                        .add(message::Column::CreatedAt.gt(created_at))
+                        .add(
+                            Condition::all()
+                                .add(message::Column::CreatedAt.eq(created_at))
+                                .add(message::Column::Id.gt(id)),
+                        ),
+                );
+            }
+
+            let messages = query
+                .order_by(message::Column::CreatedAt, Order::Asc)
+                .order_by(message::Column::Id, Order::Asc)
+                .limit(EXPORT_BATCH_SIZE)
+                .all(self.db())
+                .await
+                .map_err(|_| {
+                    CadenceError::Database(DatabaseError::QueryFailed("messages".to_string()))
+                })?;
+            let Some(last) = messages.last() else {
+                break;
+            };
+            cursor = Some((last.created_at, last.id));
+
+            let attachment_ids: Vec<ID> = messages.iter().filter_map(|m| m.attachment_id).collect();
+            let attachments: HashMap<ID, attachment::Model> = if attachment_ids.is_empty() {
+                HashMap::new()
+            } else {
+                attachment::Entity::find()
+                    .filter(attachment::Column::Id.is_in(attachment_ids))
+                    .filter(attachment::Column::DeletedAt.is_null())
+                    .all(self.db())
+                    .await
+                    .map_err(|_| {
+                        CadenceError::Database(DatabaseError::QueryFailed(
+                            "attachments".to_string(),
+                        ))
+                    })?
+                    .into_iter()
+                    .map(|a| (a.id, a))
+                    .collect()
+            };
+
+            let unseen_authors: HashSet<ID> = messages
+                .iter()
+                .filter_map(|m| m.member_id)
+                .filter(|id| !authors.contains_key(id))
+                .collect();
+            authors.extend(
+                self.get_member_identities(room_id, &unseen_authors, None)
+                    .await
+                    .map_err(CadenceError::Database)?,
+            );
+
+            let batch_size = messages.len() as u64;
+            for message in messages {
+                let member_name = message
+                    .member_id
+                    .and_then(|id| authors.get(&id))
+                    .map(|identity| identity.display_name.clone());
+                let author = match (message.system, member_name, &message.model_tag) {
+                    (false, Some(name), _) => ExportedAuthor {
+                        kind: AuthorKind::Member,
+                        display_name: name,
+                    },
+                    (false, None, Some(model_tag)) => ExportedAuthor {
+                        kind: AuthorKind::Model,
+                        display_name: model_tag.clone(),
+                    },
+                    (_, name, _) => ExportedAuthor {
+                        kind: AuthorKind::System,
+                        display_name: name.unwrap_or_else(|| "Someone".to_string()),
+                    },
+                };
+
+                let attachment = message
+                    .attachment_id
+                    .and_then(|id| attachments.get(&id))
+                    .map(|a| ExportedAttachment {
+                        id: a.id,
+                        file_name: a.file_name.clone(),
+                        content_type: a.content_type.clone(),
+                        size_bytes: a.size_bytes,
+                        url: format!("{}/{}", attachment_base_url.trim_end_matches('/'), a.id),
+                    });
+
+                writer
+                    .message(&ExportedMessage {
+                        id: message.id,
+                        author,
+                        message_type: message.message_type,
+                        content: message.content,
+                        reply_to: message.reply_to,
+                        pinned_at: message.pinned_at,
+                        attachment,
+                        created_at: message.created_at,
+                    })
+                    .map_err(write_failed)?;
+            }
+
+            count += batch_size;
+            if batch_size < EXPORT_BATCH_SIZE {
+                break;
+            }
+        }
+
+        writer.finish().map_err(write_failed)?;
+        Ok(count)
+    }
+
+    /// ## Toggle Pin Message
+    ///
+    /// Pins the message, or unpins it if it is already pinned, and posts a `MessagePinned` or
+    /// `MessageUnpinned` system message. The member must hold at least the `pin_role` of the
+    /// room settings, and a room can't pin more than `max_pinned_messages` messages. Hidden
+    /// pinned messages don't count towards the limit, as they aren't listed.
     pub async fn toggle_pin_message(
         &self,
         room_id: ID,
@@ -697,9 +5578,8 @@ impl RoomService {
             DatabaseError::TransactionFailed("Failed to start transaction".to_string())
         })?;
 
-        let mut message_to_pin = self
-            .message_repository
-            .get_by_id(message_id)
+        let mut message_to_pin = message::Entity::find_by_id(message_id)
+            .one(&txn)
             .await
             .map_err(|_| DatabaseError::QueryFailed("message".to_string()))?
             .ok_or_else(|| {
@@ -720,29 +5600,44 @@ impl RoomService {
             )));
         }
 
-        let trigger_membership = self
-            .get_member_by_account_id(room_id, trigger_account_id)
-            .await?
-            .ok_or_else(|| {
-                DatabaseError::RecordNotFound(format!(
-                    "Trigger account {} not found in room {}",
-                    trigger_account_id, room_id
-                ))
-            })?;
-
-        let is_owner = trigger_membership.is_owner;
-
-        if !is_owner {
+        let membership = self
+            .get_active_membership_tx(room_id, trigger_account_id, &txn)
+            .await?;
+        let settings = self.get_room_settings_tx(room_id, &txn).await?;
+        if !settings.can_pin(&membership) {
             return Err(DatabaseError::ConstraintViolation(
-                "User is not the room owner".to_string(),
+                "member can't pin messages in this room".to_string(),
             ));
         }
 
-        if message_to_pin.pinned_at.is_some() {
+        let message_type = if message_to_pin.pinned_at.is_some() {
             message_to_pin.pinned_at = None;
+            message_to_pin.pinned_by = None;
+            MessageType::MessageUnpinned
         } else {
+            if message_to_pin.is_hidden {
+                return Err(DatabaseError::ConstraintViolation(format!(
+                    "Message {} is hidden",
+                    message_id
+                )));
+            }
+
+            let pinned = message::Entity::find()
+                .filter(pinned_messages_condition(room_id))
+                .count(&txn)
+                .await
+                .map_err(|_| DatabaseError::QueryFailed("pinned messages".to_string()))?;
+            if pinned >= settings.max_pinned_messages as u64 {
+                return Err(DatabaseError::ConstraintViolation(format!(
+                    "room can't pin more than {} messages",
+                    settings.max_pinned_messages
+                )));
+            }
+
             message_to_pin.pinned_at = Some(now_millis());
-        }
+            message_to_pin.pinned_by = Some(membership.id);
+            MessageType::MessagePinned
+        };
 
         let pinned_message = self
             .message_repository
@@ -750,12 +5645,329 @@ impl RoomService {
             .await
             .map_err(|_| DatabaseError::UpdateError("message".to_string()))?;
 
+        self.post_system_message_tx(
+            room_id,
+            Some(membership.id),
+            message_type,
+            Some(message_id.to_string()),
+            &txn,
+        )
+        .await?;
+
         txn.commit().await.map_err(|_| {
             DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
         })?;
 
         Ok(pinned_message)
     }
+
+    /// ## Get Pinned Messages
+    ///
+    /// Lists the visible pinned messages of the room, most recently pinned first. Only
+    /// active members can see them.
+    pub async fn get_pinned_messages(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<MessagePage, DatabaseError> {
+        let viewer = self
+            .get_active_membership(room_id, trigger_account_id)
+            .await?;
+
+        let messages = message::Entity::find()
+            .filter(pinned_messages_condition(room_id))
+            .order_by(message::Column::PinnedAt, Order::Desc)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("pinned messages".to_string()))?;
+
+        let messages = self.build_room_messages(messages, Some(viewer.id)).await?;
+        let identities = self
+            .get_member_identities(room_id, &message_member_ids(&messages), Some(&viewer))
+            .await?;
+
+        Ok(MessagePage {
+            messages,
+            identities,
+        })
+    }
+}
+
+/// Room created out of `template`, or out of its published `version` when given. The room
+/// records both, and `schema.name` overrides the template name.
+pub(crate) fn template_room_schema(
+    template: &RoomTemplateModel,
+    version: Option<&TemplateVersionModel>,
+    schema: &RoomFromTemplateSchema,
+) -> RoomCreationSchema {
+    let (name, description, model_tag, system_prompt) = match version {
+        Some(version) => (
+            &version.name,
+            &version.description,
+            &version.model_tag,
+            &version.system_prompt,
+        ),
+        None => (
+            &template.name,
+            &template.description,
+            &template.model_tag,
+            &template.system_prompt,
+        ),
+    };
+
+    RoomCreationSchema {
+        name: schema.name.clone().or_else(|| name.clone()),
+        description: description.clone(),
+        icon_url: None,
+        background_url: None,
+        visibility: schema.visibility.clone(),
+        template_id: Some(template.id),
+        template_version_id: version.map(|v| v.id),
+        model_tag: Some(model_tag.clone()),
+        system_prompt: system_prompt.clone(),
+        room_type: schema.room_type.clone(),
+    }
+}
+
+/// Copies of the seed messages for the room, each with the attachment to clone once it is
+/// inserted. Seeds are one millisecond apart from `created_at` so they keep their order, and
+/// replies point to the copy of the seed at `reply_to_position` when it comes before them.
+pub(crate) fn seed_message_copies(
+    room_id: ID,
+    seeds: Vec<SeedMessage>,
+    created_at: Timestamp,
+) -> Vec<(message::ActiveModel, Option<ID>)> {
+    let ids: Vec<ID> = seeds.iter().map(|_| uuid::Uuid::new_v4()).collect();
+    seeds
+        .into_iter()
+        .enumerate()
+        .map(|(index, seed)| {
+            let reply_to = seed
+                .reply_to_position
+                .filter(|&position| position >= 0 && (position as usize) < index)
+                .map(|position| ids[position as usize]);
+
+            let copy = message::ActiveModel {
+                id: Set(ids[index]),
+                room_id: Set(room_id),
+                member_id: Set(None),
+                system: Set(false),
+                model_tag: Set(seed.model_tag),
+                content: Set(seed.content),
+                attachment_id: Set(None),
+                reply_to: Set(reply_to),
+                message_type: Set(MessageType::Default),
+                is_hidden: Set(false),
+                created_at: Set(created_at + index as i64),
+                updated_at: Set(created_at + index as i64),
+                ..Default::default()
+            };
+            (copy, seed.attachment_id)
+        })
+        .collect()
+}
+
+/// Rejects thread pages out of 1 to 10 levels of replies, or past the usual page bounds.
+pub(crate) fn validate_thread_page(
+    max_depth: u32,
+    limit: u64,
+    offset: u64,
+) -> Result<(), DatabaseError> {
+    if max_depth == 0 || max_depth > 10 {
+        return Err(DatabaseError::ConstraintViolation(
+            "max_depth must be between 1 and 10".to_string(),
+        ));
+    }
+    if limit > 100 {
+        return Err(DatabaseError::ConstraintViolation(
+            "limit must be less than 100".to_string(),
+        ));
+    }
+    if offset > 1000 {
+        return Err(DatabaseError::ConstraintViolation(
+            "offset must be less than 1000".to_string(),
+        ));
+    }
+    if limit == 0 {
+        return Err(DatabaseError::ConstraintViolation(
+            "limit must be greater than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Recursive query fetching a page of the replies to `message_id` with their `depth`, down
+/// to `max_depth` levels. Hidden or deleted replies are only kept when at least one of their
+/// replies, direct or nested, is visible, so a chain of tombstones is left out. `path` holds
+/// the ids from the first-level reply down to the reply itself.
+pub(crate) fn thread_statement(
+    db_backend: DbBackend,
+    room_id: ID,
+    message_id: ID,
+    max_depth: u32,
+    limit: u64,
+    offset: u64,
+) -> Statement {
+    Statement::from_sql_and_values(
+        db_backend,
+        r#"
+        WITH RECURSIVE thread AS (
+            SELECT m.*, 1 AS depth, ARRAY[m.id] AS path
+            FROM message m
+            WHERE m.reply_to_id = $1 AND m.room_id = $2
+            UNION ALL
+            SELECT m.*, t.depth + 1 AS depth, t.path || m.id AS path
+            FROM message m
+            INNER JOIN thread t ON m.reply_to_id = t.id
+            WHERE t.depth < $3
+        )
+        SELECT t.*
+        FROM thread t
+        WHERE (t.deleted_at IS NULL AND t.is_hidden = FALSE)
+            OR EXISTS (
+                SELECT 1 FROM thread d
+                WHERE d.id <> t.id
+                    AND t.id = ANY(d.path)
+                    AND d.deleted_at IS NULL
+                    AND d.is_hidden = FALSE
+            )
+        ORDER BY t.created_at ASC
+        LIMIT $4 OFFSET $5
+        "#,
+        [
+            message_id.into(),
+            room_id.into(),
+            (max_depth as i32).into(),
+            (limit as i64).into(),
+            (offset as i64).into(),
+        ],
+    )
+}
+
+/// Rejects search pages out of `MAX_SEARCH_LIMIT` and `MAX_SEARCH_OFFSET`.
+fn validate_search_page(limit: u64, offset: u64) -> Result<(), DatabaseError> {
+    if limit == 0 {
+        return Err(DatabaseError::ConstraintViolation(
+            "limit must be greater than 0".to_string(),
+        ));
+    }
+    if limit > MAX_SEARCH_LIMIT {
+        return Err(DatabaseError::ConstraintViolation(format!(
+            "limit must be at most {}",
+            MAX_SEARCH_LIMIT
+        )));
+    }
+    if offset > MAX_SEARCH_OFFSET {
+        return Err(DatabaseError::ConstraintViolation(format!(
+            "offset must be at most {}",
+            MAX_SEARCH_OFFSET
+        )));
+    }
+    Ok(())
+}
+
+/// Private templates can only be used by their author, unlisted and public ones by everyone.
+fn can_use_template(template: &RoomTemplateModel, account_id: Option<ID>) -> bool {
+    template.visibility != TemplateVisibility::Private
+        || account_id.is_some_and(|id| template.author_id == Some(id))
+}
+
+/// The messages of the room that `get_messages` shows with their content: neither
+/// summaries, nor hidden or deleted messages, which are only tombstones.
+pub(crate) fn visible_messages_condition(room_id: ID) -> Condition {
+    Condition::all()
+        .add(message::Column::RoomId.eq(room_id))
+        .add(message::Column::MessageType.ne(MessageType::Summary))
+        .add(message::Column::IsHidden.eq(false))
+        .add(message::Column::DeletedAt.is_null())
+}
+
+/// The pinned messages of the room that `get_pinned_messages` lists and the pin limit
+/// counts: hidden and deleted ones are left out.
+pub(crate) fn pinned_messages_condition(room_id: ID) -> Condition {
+    Condition::all()
+        .add(message::Column::RoomId.eq(room_id))
+        .add(message::Column::PinnedAt.is_not_null())
+        .add(message::Column::DeletedAt.is_null())
+        .add(message::Column::IsHidden.eq(false))
+}
+
+/// Sets the `last_message_at` of the rooms to the creation time of their latest visible
+/// message, or `NULL` when they have none left.
+pub(crate) fn refresh_last_message_at(
+    room_ids: impl IntoIterator<Item = ID>,
+) -> UpdateMany<room::Entity> {
+    let latest = Query::select()
+        .expr(Func::max(Expr::col((
+            message::Entity,
+            message::Column::CreatedAt,
+        ))))
+        .from(message::Entity)
+        .and_where(
+            Expr::col((message::Entity, message::Column::RoomId))
+                .equals((room::Entity, room::Column::Id)),
+        )
+        .and_where(message::Column::DeletedAt.is_null())
+        .and_where(message::Column::IsHidden.eq(false))
+        .to_owned();
+
+    room::Entity::update_many()
+        .col_expr(
+            room::Column::LastMessageAt,
+            SimpleExpr::SubQuery(None, Box::new(latest.into_sub_query_statement())),
+        )
+        .filter(room::Column::Id.is_in(room_ids))
+}
+
+/// Restricts bulk moderation to the messages of the model, of `trigger` itself and of the
+/// members whose role `trigger` outranks, so a moderator can't sweep the messages of an
+/// admin or an owner.
+pub(crate) fn bulk_author_condition(trigger: &MemberModel) -> Condition {
+    let protected_roles: Vec<MemberRole> = MemberRole::iter()
+        .filter(|role| !trigger.role.outranks(role))
+        .collect();
+
+    Condition::any()
+        .add(message::Column::MemberId.is_null())
+        .add(message::Column::MemberId.eq(trigger.id))
+        .add(
+            message::Column::MemberId.not_in_subquery(
+                Query::select()
+                    .column(member::Column::Id)
+                    .from(member::Entity)
+                    .and_where(member::Column::RoomId.eq(trigger.room_id))
+                    .and_where(member::Column::Role.is_in(protected_roles))
+                    .to_owned(),
+            ),
+        )
+}
+
+/// Ids of the members the messages refer to, their authors and pinners, whose identities
+/// the read paths resolve.
+pub(crate) fn message_member_ids<'a>(
+    messages: impl IntoIterator<Item = &'a RoomMessage>,
+) -> HashSet<ID> {
+    messages
+        .into_iter()
+        .flat_map(|m| [m.message.member_id, m.message.pinned_by])
+        .flatten()
+        .collect()
+}
+
+/// Strips the content of hidden or deleted messages, keeping only what is needed to place
+/// them in a thread.
+pub(crate) fn tombstone_if_removed(mut room_message: RoomMessage) -> RoomMessage {
+    let message = &mut room_message.message;
+    if message.deleted_at.is_none() && !message.is_hidden {
+        return room_message;
+    }
+
+    message.content = None;
+    message.attachment_id = None;
+    message.model_tag = None;
+    room_message.reactions = Vec::new();
+    room_message.tombstone = true;
+    room_message
 }
 
 impl BasicApplicationService for RoomService {
@@ -768,7 +5980,16 @@ impl BasicApplicationService for RoomService {
             member_repository: MemberRepository::new(db.clone()),
             room_repository: RoomRepository::new(db.clone()),
             room_template_repository: RoomTemplateRepository::new(db.clone()),
+            room_template_version_repository: RoomTemplateVersionRepository::new(db.clone()),
             message_repository: MessageRepository::new(db.clone()),
+            room_invite_repository: RoomInviteRepository::new(db.clone()),
+            context_builder: ContextBuilder::default(),
+            usage_service: UsageService::new(db.clone()),
+            search_language: SearchLanguage::default(),
+            identity_generator: IdentityGenerator::default(),
+            privacy_policy: PrivacyPolicy::default(),
+            content_filters: ContentFilterPipeline::default(),
+            sla_policy: SlaPolicy::default(),
         }
     }
 
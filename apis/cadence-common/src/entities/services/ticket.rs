@@ -0,0 +1,445 @@
+use crate::entities::room::member::{MemberRole, Model as MemberModel};
+use crate::entities::room::message::MessageType;
+use crate::entities::room::repositories::member::CreationSchema as MemberCreationSchema;
+use crate::entities::room::room::{self, Model as RoomModel};
+use crate::entities::room::ticket::{self, Model as TicketModel, TicketPriority, TicketStatus};
+use crate::entities::services::account::AccountService;
+use crate::entities::services::room::RoomService;
+use crate::error::DatabaseError;
+use crate::repository_traits::{BasicApplicationService, CrudEntityRepository};
+use crate::time::now_millis;
+use crate::types::ID;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use sea_orm::{Condition, Order, QueryOrder, QuerySelect, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+/// Name of the account flag of support staff.
+pub const DEFAULT_STAFF_FLAG: &str = "staff";
+
+/// Maximum number of tickets of a staff queue page.
+pub const MAX_TICKET_QUEUE_LIMIT: u64 = 100;
+
+/// # Ticket Service
+///
+/// This struct provides a service for handling the tickets of support rooms.
+///
+/// Tickets are handled by the staff, the accounts with the `staff_flag` flag, who can move
+/// them through every status, change their priority and assign them. The requester can only
+/// close their ticket or open it again once resolved. Every change is announced in the room
+/// by a `TicketUpdated` system message.
+#[derive(Clone, Debug)]
+pub struct TicketService {
+    pub db: sea_orm::DatabaseConnection,
+    pub room_service: RoomService,
+    pub account_service: AccountService,
+    pub staff_flag: String,
+}
+
+/// # Assignee Filter
+///
+/// - `Unassigned`: tickets nobody handles.
+/// - `Account`: tickets assigned to the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssigneeFilter {
+    Unassigned,
+    Account(ID),
+}
+
+/// # Ticket Queue Filter
+///
+/// Filters of the staff queue, all of them optional. `statuses` defaults to the tickets
+/// that still need work, `Open` and `Pending`. `breached` keeps the tickets that missed
+/// one of their deadlines.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TicketQueueFilter {
+    pub statuses: Vec<TicketStatus>,
+    pub priority: Option<TicketPriority>,
+    pub assignee: Option<AssigneeFilter>,
+    pub breached: bool,
+}
+
+/// # Ticket Queue Item
+///
+/// A ticket of the staff queue with its room.
+#[derive(Debug, Clone, Serialize)]
+pub struct TicketQueueItem {
+    pub ticket: TicketModel,
+    pub room: RoomModel,
+}
+
+impl TicketService {
+    /// Whether the account holds the `staff_flag` flag.
+    pub async fn is_staff(&self, account_id: ID) -> Result<bool, DatabaseError> {
+        self.account_service
+            .has_flag(account_id, &self.staff_flag)
+            .await
+    }
+
+    async fn require_staff(&self, account_id: ID) -> Result<(), DatabaseError> {
+        if !self.is_staff(account_id).await? {
+            return Err(DatabaseError::ConstraintViolation(
+                "account is not support staff".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// ## Get Ticket
+    ///
+    /// The ticket of a support room, for the staff and the members of the room.
+    pub async fn get_ticket(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<TicketModel, DatabaseError> {
+        if !self.is_staff(trigger_account_id).await? {
+            self.room_service
+                .get_active_membership(room_id, trigger_account_id)
+                .await?;
+        }
+
+        ticket::Entity::find_by_id(room_id)
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("ticket".to_string()))?
+            .ok_or_else(|| DatabaseError::RecordNotFound("ticket".to_string()))
+    }
+
+    /// ## Set Ticket Status
+    ///
+    /// Moves the ticket to `status`, see `TicketStatus::can_become` for the allowed
+    /// transitions. Resolving records `resolved_at`, opening again clears it, and closing
+    /// records `closed_at`.
+    pub async fn set_status(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        status: TicketStatus,
+    ) -> Result<TicketModel, DatabaseError> {
+        let current = self.get_ticket(room_id, trigger_account_id).await?;
+
+        if !current.status.can_become(status) {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "ticket can't go from {} to {}",
+                current.status.as_str(),
+                status.as_str()
+            )));
+        }
+
+        let is_requester = current.requester_id == trigger_account_id;
+        let requester_may = status == TicketStatus::Closed
+            || (current.status == TicketStatus::Resolved && status == TicketStatus::Open);
+        if !(is_requester && requester_may) {
+            self.require_staff(trigger_account_id).await?;
+        }
+
+        let now = now_millis();
+        let mut update = ticket::ActiveModel {
+            room_id: Set(room_id),
+            status: Set(status),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        match status {
+            TicketStatus::Open => update.resolved_at = Set(None),
+            TicketStatus::Pending => {}
+            TicketStatus::Resolved => update.resolved_at = Set(Some(now)),
+            TicketStatus::Closed => update.closed_at = Set(Some(now)),
+        }
+
+        self.update_ticket(
+            room_id,
+            trigger_account_id,
+            update,
+            format!(
+                "Status changed from {} to {}",
+                current.status.as_str(),
+                status.as_str()
+            ),
+        )
+        .await
+    }
+
+    /// ## Set Ticket Priority
+    ///
+    /// Changes the priority of the ticket, and its deadlines accordingly. Staff only.
+    pub async fn set_priority(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        priority: TicketPriority,
+    ) -> Result<TicketModel, DatabaseError> {
+        self.require_staff(trigger_account_id).await?;
+        let current = self.get_open_ticket(room_id, trigger_account_id).await?;
+
+        if current.priority == priority {
+            return Ok(current);
+        }
+
+        let (first_response_due_at, resolution_due_at) = self
+            .room_service
+            .sla_policy
+            .due_dates(priority, current.created_at);
+
+        self.update_ticket(
+            room_id,
+            trigger_account_id,
+            ticket::ActiveModel {
+                room_id: Set(room_id),
+                priority: Set(priority),
+                first_response_due_at: Set(first_response_due_at),
+                resolution_due_at: Set(resolution_due_at),
+                updated_at: Set(now_millis()),
+                ..Default::default()
+            },
+            format!(
+                "Priority changed from {} to {}",
+                current.priority.as_str(),
+                priority.as_str()
+            ),
+        )
+        .await
+    }
+
+    /// ## Assign Ticket
+    ///
+    /// Assigns the ticket to a staff account, or to nobody. The assignee joins the room as a
+    /// moderator when they are not a member of it yet. Staff only.
+    pub async fn assign_ticket(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        assignee_id: Option<ID>,
+    ) -> Result<TicketModel, DatabaseError> {
+        self.require_staff(trigger_account_id).await?;
+        let current = self.get_open_ticket(room_id, trigger_account_id).await?;
+
+        if current.assignee_id == assignee_id {
+            return Ok(current);
+        }
+
+        let text = match assignee_id {
+            Some(assignee_id) => {
+                if !self.is_staff(assignee_id).await? {
+                    return Err(DatabaseError::ConstraintViolation(
+                        "assignee is not support staff".to_string(),
+                    ));
+                }
+                let assignee = self
+                    .account_service
+                    .account_repository
+                    .get_by_id(assignee_id)
+                    .await
+                    .map_err(|_| DatabaseError::QueryFailed("account".to_string()))?
+                    .ok_or_else(|| DatabaseError::RecordNotFound("account".to_string()))?;
+                format!(
+                    "Assigned to {}",
+                    assignee.name.as_deref().unwrap_or("a staff member")
+                )
+            }
+            None => "Unassigned".to_string(),
+        };
+
+        self.update_ticket(
+            room_id,
+            trigger_account_id,
+            ticket::ActiveModel {
+                room_id: Set(room_id),
+                assignee_id: Set(assignee_id),
+                updated_at: Set(now_millis()),
+                ..Default::default()
+            },
+            text,
+        )
+        .await
+    }
+
+    /// ## Get Staff Queue
+    ///
+    /// The tickets matching `filter`, the closest resolution deadline first. Staff only.
+    pub async fn get_staff_queue(
+        &self,
+        trigger_account_id: ID,
+        filter: TicketQueueFilter,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<TicketQueueItem>, DatabaseError> {
+        self.require_staff(trigger_account_id).await?;
+
+        if limit == 0 || limit > MAX_TICKET_QUEUE_LIMIT {
+            return Err(DatabaseError::ConstraintViolation(format!(
+                "limit must be between 1 and {}",
+                MAX_TICKET_QUEUE_LIMIT
+            )));
+        }
+
+        let statuses = if filter.statuses.is_empty() {
+            vec![TicketStatus::Open, TicketStatus::Pending]
+        } else {
+            filter.statuses
+        };
+
+        let mut query = ticket::Entity::find()
+            .find_also_related(room::Entity)
+            .filter(ticket::Column::Status.is_in(statuses))
+            .filter(room::Column::DeletedAt.is_null());
+
+        if let Some(priority) = filter.priority {
+            query = query.filter(ticket::Column::Priority.eq(priority));
+        }
+        match filter.assignee {
+            Some(AssigneeFilter::Unassigned) => {
+                query = query.filter(ticket::Column::AssigneeId.is_null());
+            }
+            Some(AssigneeFilter::Account(account_id)) => {
+                query = query.filter(ticket::Column::AssigneeId.eq(account_id));
+            }
+            None => {}
+        }
+        if filter.breached {
+            let now = now_millis();
+            query = query.filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(ticket::Column::FirstResponseAt.is_null())
+                            .add(ticket::Column::FirstResponseDueAt.lt(now)),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(ticket::Column::ResolvedAt.is_null())
+                            .add(ticket::Column::ClosedAt.is_null())
+                            .add(ticket::Column::ResolutionDueAt.lt(now)),
+                    ),
+            );
+        }
+
+        let tickets = query
+            .order_by(ticket::Column::ResolutionDueAt, Order::Asc)
+            .order_by(ticket::Column::CreatedAt, Order::Asc)
+            .limit(limit)
+            .offset(offset)
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("tickets".to_string()))?;
+
+        Ok(tickets
+            .into_iter()
+            .filter_map(|(ticket, room)| {
+                Some(TicketQueueItem {
+                    ticket,
+                    room: room?,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_open_ticket(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+    ) -> Result<TicketModel, DatabaseError> {
+        let current = self.get_ticket(room_id, trigger_account_id).await?;
+        if current.status == TicketStatus::Closed {
+            return Err(DatabaseError::ConstraintViolation(
+                "ticket is closed".to_string(),
+            ));
+        }
+        Ok(current)
+    }
+
+    /// Saves a change of the ticket and announces it in the room. The trigger account, and
+    /// the assignee when there is a new one, are made members of the room if they aren't.
+    async fn update_ticket(
+        &self,
+        room_id: ID,
+        trigger_account_id: ID,
+        update: ticket::ActiveModel,
+        text: String,
+    ) -> Result<TicketModel, DatabaseError> {
+        let txn = self.db().begin().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to start transaction".to_string())
+        })?;
+
+        let mut member_id = self
+            .ensure_membership_tx(room_id, trigger_account_id, &txn)
+            .await?
+            .id;
+        if let sea_orm::ActiveValue::Set(Some(assignee_id)) = update.assignee_id {
+            member_id = self
+                .ensure_membership_tx(room_id, assignee_id, &txn)
+                .await?
+                .id;
+        }
+
+        let ticket = update
+            .update(&txn)
+            .await
+            .map_err(|_| DatabaseError::UpdateError("ticket".to_string()))?;
+
+        self.room_service
+            .post_system_message_tx(
+                room_id,
+                Some(member_id),
+                MessageType::TicketUpdated,
+                Some(text),
+                &txn,
+            )
+            .await?;
+
+        txn.commit().await.map_err(|_| {
+            DatabaseError::TransactionFailed("Failed to commit transaction".to_string())
+        })?;
+
+        Ok(ticket)
+    }
+
+    /// The active membership of the account in the room, staff joining as moderators.
+    async fn ensure_membership_tx(
+        &self,
+        room_id: ID,
+        account_id: ID,
+        txn: &(impl TransactionTrait + ConnectionTrait),
+    ) -> Result<MemberModel, DatabaseError> {
+        if let Some(membership) = self
+            .room_service
+            .get_member_by_account_id(room_id, account_id)
+            .await?
+        {
+            return Ok(membership);
+        }
+
+        self.room_service
+            .member_repository
+            .create_tx(
+                &MemberCreationSchema {
+                    room_id,
+                    account_id,
+                    role: MemberRole::Moderator,
+                    anonymize: false,
+                },
+                txn,
+            )
+            .await
+            .map_err(|_| DatabaseError::InsertionError("member".to_string()))
+    }
+}
+
+impl BasicApplicationService for TicketService {
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        TicketService {
+            db: db.clone(),
+            room_service: RoomService::new(db.clone()),
+            account_service: AccountService::new(db),
+            staff_flag: DEFAULT_STAFF_FLAG.to_string(),
+        }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+}
@@ -0,0 +1,356 @@
+use crate::entities::account::account_flag;
+use crate::entities::usage::billing_period::BillingPeriod;
+use crate::entities::usage::usage_quota::{self, Model as UsageQuotaModel, UsagePeriod};
+use crate::entities::usage::usage_record::{self, Model as UsageRecordModel, UsageKind};
+use crate::error::{CadenceError, DatabaseError, EntityError};
+use crate::repository_traits::BasicApplicationService;
+use crate::time::now_millis;
+use crate::types::{ID, Timestamp};
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use sea_orm::{FromQueryResult, Statement};
+use serde::{Deserialize, Serialize};
+
+/// # Usage Service
+///
+/// This struct provides a service for metering the model usage of accounts and enforcing
+/// their quotas.
+#[derive(Clone, Debug)]
+pub struct UsageService {
+    pub db: sea_orm::DatabaseConnection,
+}
+
+/// # Usage Record Schema
+///
+/// Tokens consumed by a single generated message.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UsageRecordSchema {
+    pub account_id: Option<ID>,
+    pub room_id: ID,
+    pub message_id: Option<ID>,
+    pub model_tag: String,
+    pub kind: UsageKind,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+/// # Usage Totals
+#[derive(Debug, Clone, Default, Serialize, FromQueryResult)]
+pub struct UsageTotals {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    /// Number of model replies, summaries excluded.
+    pub messages: i64,
+}
+
+/// # Model Usage
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct ModelUsage {
+    pub model_tag: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub messages: i64,
+}
+
+/// # Room Usage
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct RoomUsage {
+    pub room_id: ID,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub messages: i64,
+}
+
+/// # Usage Summary
+///
+/// Usage of an account during a billing period, with the quota that currently applies to
+/// it (`None` when unlimited).
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSummary {
+    pub account_id: ID,
+    pub period: BillingPeriod,
+    pub quota: Option<UsageQuotaModel>,
+    pub totals: UsageTotals,
+    pub by_model: Vec<ModelUsage>,
+    pub by_room: Vec<RoomUsage>,
+}
+
+const USAGE_COLUMNS: &str = r#"
+    COALESCE(SUM(prompt_tokens), 0)::BIGINT AS prompt_tokens,
+    COALESCE(SUM(completion_tokens), 0)::BIGINT AS completion_tokens,
+    COALESCE(SUM(prompt_tokens + completion_tokens), 0)::BIGINT AS total_tokens,
+    COUNT(*) FILTER (WHERE kind = 'completion')::BIGINT AS messages
+"#;
+
+impl UsageService {
+    /// ## Set Quota
+    ///
+    /// Creates or replaces the quota of a flag, or the default quota when `flag_id` is
+    /// `None`.
+    pub async fn set_quota(
+        &self,
+        flag_id: Option<ID>,
+        period: UsagePeriod,
+        max_tokens: Option<i64>,
+        max_messages: Option<i64>,
+    ) -> Result<UsageQuotaModel, DatabaseError> {
+        if max_tokens.is_some_and(|m| m < 0) || max_messages.is_some_and(|m| m < 0) {
+            return Err(DatabaseError::ConstraintViolation(
+                "quota limits cannot be negative".to_string(),
+            ));
+        }
+
+        let existing = usage_quota::Entity::find()
+            .filter(match flag_id {
+                Some(flag_id) => usage_quota::Column::FlagId.eq(flag_id),
+                None => usage_quota::Column::FlagId.is_null(),
+            })
+            .filter(usage_quota::Column::DeletedAt.is_null())
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("usage quota".to_string()))?;
+
+        match existing {
+            Some(quota) => usage_quota::ActiveModel {
+                id: Set(quota.id),
+                period: Set(period),
+                max_tokens: Set(max_tokens),
+                max_messages: Set(max_messages),
+                updated_at: Set(now_millis()),
+                ..Default::default()
+            }
+            .update(self.db())
+            .await
+            .map_err(|_| DatabaseError::UpdateError("usage quota".to_string())),
+            None => usage_quota::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                flag_id: Set(flag_id),
+                period: Set(period),
+                max_tokens: Set(max_tokens),
+                max_messages: Set(max_messages),
+                deleted_at: Set(None),
+                created_at: Set(now_millis()),
+                updated_at: Set(now_millis()),
+            }
+            .insert(self.db())
+            .await
+            .map_err(|_| DatabaseError::InsertionError("usage quota".to_string())),
+        }
+    }
+
+    /// ## Get Effective Quota
+    ///
+    /// The most generous quota among the flags of the account, or the default quota when
+    /// none of its flags has one. `None` means unlimited.
+    pub async fn get_effective_quota(
+        &self,
+        account_id: ID,
+    ) -> Result<Option<UsageQuotaModel>, DatabaseError> {
+        let flag_ids: Vec<ID> = account_flag::Entity::find()
+            .filter(account_flag::Column::AccountId.eq(account_id))
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("account flags".to_string()))?
+            .into_iter()
+            .map(|f| f.flag_id)
+            .collect();
+
+        let flag_quotas = usage_quota::Entity::find()
+            .filter(usage_quota::Column::FlagId.is_in(flag_ids))
+            .filter(usage_quota::Column::DeletedAt.is_null())
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("usage quotas".to_string()))?;
+
+        if let Some(best) = flag_quotas.into_iter().reduce(|best, quota| {
+            if quota.is_at_least(&best) {
+                quota
+            } else {
+                best
+            }
+        }) {
+            return Ok(Some(best));
+        }
+
+        usage_quota::Entity::find()
+            .filter(usage_quota::Column::FlagId.is_null())
+            .filter(usage_quota::Column::DeletedAt.is_null())
+            .one(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("usage quota".to_string()))
+    }
+
+    /// ## Check Quota
+    ///
+    /// Fails with `QuotaExceeded` when the account can't afford a generation whose prompt
+    /// is estimated at `estimated_prompt_tokens`, given what it consumed during the current
+    /// period of its quota.
+    pub async fn check_quota(
+        &self,
+        account_id: ID,
+        estimated_prompt_tokens: i64,
+    ) -> Result<(), CadenceError> {
+        let Some(quota) = self
+            .get_effective_quota(account_id)
+            .await
+            .map_err(CadenceError::Database)?
+        else {
+            return Ok(());
+        };
+
+        let period = BillingPeriod::containing(quota.period, now_millis());
+        let used = self
+            .get_usage_totals(account_id, period.start, period.end)
+            .await
+            .map_err(CadenceError::Database)?;
+
+        let period_name = match quota.period {
+            UsagePeriod::Daily => "daily",
+            UsagePeriod::Monthly => "monthly",
+        };
+
+        if let Some(max_messages) = quota.max_messages
+            && used.messages >= max_messages
+        {
+            return Err(CadenceError::Entity(EntityError::QuotaExceeded(format!(
+                "{} message quota exhausted: {} of {} messages used, resets at {}",
+                period_name, used.messages, max_messages, period.end
+            ))));
+        }
+
+        if let Some(max_tokens) = quota.max_tokens
+            && used.total_tokens + estimated_prompt_tokens > max_tokens
+        {
+            return Err(CadenceError::Entity(EntityError::QuotaExceeded(format!(
+                "{} token quota exhausted: {} of {} tokens used, resets at {}",
+                period_name, used.total_tokens, max_tokens, period.end
+            ))));
+        }
+
+        Ok(())
+    }
+
+    /// ## Record Usage
+    ///
+    /// Appends a record to the usage ledger.
+    pub async fn record_usage(
+        &self,
+        schema: UsageRecordSchema,
+    ) -> Result<UsageRecordModel, DatabaseError> {
+        usage_record::ActiveModel {
+            id: Set(uuid::Uuid::new_v4()),
+            account_id: Set(schema.account_id),
+            room_id: Set(schema.room_id),
+            message_id: Set(schema.message_id),
+            model_tag: Set(schema.model_tag),
+            kind: Set(schema.kind),
+            prompt_tokens: Set(schema.prompt_tokens),
+            completion_tokens: Set(schema.completion_tokens),
+            created_at: Set(now_millis()),
+            updated_at: Set(now_millis()),
+        }
+        .insert(self.db())
+        .await
+        .map_err(|_| DatabaseError::InsertionError("usage record".to_string()))
+    }
+
+    /// ## Get Usage Summary
+    ///
+    /// Usage of an account during a billing period, in total, per `model_tag` and per room.
+    pub async fn get_usage_summary(
+        &self,
+        account_id: ID,
+        period: BillingPeriod,
+    ) -> Result<UsageSummary, DatabaseError> {
+        let totals = self
+            .get_usage_totals(account_id, period.start, period.end)
+            .await?;
+
+        let by_model = ModelUsage::find_by_statement(Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            format!(
+                "SELECT model_tag, {} FROM usage_record \
+                 WHERE account_id = $1 AND created_at >= $2 AND created_at < $3 \
+                 GROUP BY model_tag ORDER BY total_tokens DESC",
+                USAGE_COLUMNS
+            ),
+            [account_id.into(), period.start.into(), period.end.into()],
+        ))
+        .all(self.db())
+        .await
+        .map_err(|_| DatabaseError::QueryFailed("usage per model".to_string()))?;
+
+        let by_room = RoomUsage::find_by_statement(Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            format!(
+                "SELECT room_id, {} FROM usage_record \
+                 WHERE account_id = $1 AND created_at >= $2 AND created_at < $3 \
+                 GROUP BY room_id ORDER BY total_tokens DESC",
+                USAGE_COLUMNS
+            ),
+            [account_id.into(), period.start.into(), period.end.into()],
+        ))
+        .all(self.db())
+        .await
+        .map_err(|_| DatabaseError::QueryFailed("usage per room".to_string()))?;
+
+        Ok(UsageSummary {
+            account_id,
+            period,
+            quota: self.get_effective_quota(account_id).await?,
+            totals,
+            by_model,
+            by_room,
+        })
+    }
+
+    /// ## Get Message Usage
+    ///
+    /// The ledger records of the given generated messages.
+    pub async fn get_message_usage(
+        &self,
+        message_ids: Vec<ID>,
+    ) -> Result<Vec<UsageRecordModel>, DatabaseError> {
+        usage_record::Entity::find()
+            .filter(usage_record::Column::MessageId.is_in(message_ids))
+            .all(self.db())
+            .await
+            .map_err(|_| DatabaseError::QueryFailed("usage records".to_string()))
+    }
+
+    async fn get_usage_totals(
+        &self,
+        account_id: ID,
+        start: Timestamp,
+        end: Timestamp,
+    ) -> Result<UsageTotals, DatabaseError> {
+        UsageTotals::find_by_statement(Statement::from_sql_and_values(
+            self.db().get_database_backend(),
+            format!(
+                "SELECT {} FROM usage_record \
+                 WHERE account_id = $1 AND created_at >= $2 AND created_at < $3",
+                USAGE_COLUMNS
+            ),
+            [account_id.into(), start.into(), end.into()],
+        ))
+        .one(self.db())
+        .await
+        .map_err(|_| DatabaseError::QueryFailed("usage totals".to_string()))
+        .map(Option::unwrap_or_default)
+    }
+}
+
+impl BasicApplicationService for UsageService {
+    type DatabaseConnection = sea_orm::DatabaseConnection;
+
+    fn new(db: sea_orm::DatabaseConnection) -> Self {
+        UsageService { db }
+    }
+
+    fn db(&self) -> &Self::DatabaseConnection {
+        &self.db
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::entities::usage::usage_quota::UsagePeriod;
+use crate::types::Timestamp;
+
+/// # Billing Period
+///
+/// A UTC calendar day or month, as `[start, end)` in milliseconds since the Unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BillingPeriod {
+    pub period: UsagePeriod,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl BillingPeriod {
+    /// The period of the given kind that contains `at`.
+    pub fn containing(period: UsagePeriod, at: Timestamp) -> Self {
+        let date = DateTime::<Utc>::from_timestamp_millis(at)
+            .unwrap_or_default()
+            .date_naive();
+
+        match period {
+            UsagePeriod::Daily => BillingPeriod {
+                period,
+                start: start_of_day(date),
+                end: start_of_day(date.succ_opt().unwrap_or(date)),
+            },
+            UsagePeriod::Monthly => {
+                Self::month(date.year(), date.month()).unwrap_or(BillingPeriod {
+                    period,
+                    start: start_of_day(date),
+                    end: start_of_day(date),
+                })
+            }
+        }
+    }
+
+    /// The calendar month `month` (1 to 12) of `year`, `None` when out of range.
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let next = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)?
+        };
+
+        Some(BillingPeriod {
+            period: UsagePeriod::Monthly,
+            start: start_of_day(first),
+            end: start_of_day(next),
+        })
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> Timestamp {
+    date.and_hms_opt(0, 0, 0)
+        .map(|d| d.and_utc().timestamp_millis())
+        .unwrap_or_default()
+}
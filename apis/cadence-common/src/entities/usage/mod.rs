@@ -0,0 +1,5 @@
+pub mod usage_record;
+pub mod usage_quota;
+pub mod billing_period;
+#[cfg(test)]
+pub mod tests;
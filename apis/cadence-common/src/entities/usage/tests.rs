@@ -0,0 +1,74 @@
+#![cfg(test)] // Ensure this file is only compiled for tests
+
+use chrono::{TimeZone, Utc};
+
+use super::billing_period::BillingPeriod;
+use super::usage_quota::{Model as UsageQuotaModel, UsagePeriod};
+
+fn quota(max_tokens: Option<i64>, max_messages: Option<i64>) -> UsageQuotaModel {
+    UsageQuotaModel {
+        id: uuid::Uuid::new_v4(),
+        flag_id: None,
+        period: UsagePeriod::Monthly,
+        max_tokens,
+        max_messages,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+// --- Billing Period Tests ---
+
+#[test]
+fn test_monthly_period_wraps_year() {
+    let at = Utc.with_ymd_and_hms(2025, 12, 31, 23, 59, 59).unwrap();
+    let period = BillingPeriod::containing(UsagePeriod::Monthly, at.timestamp_millis());
+
+    assert_eq!(
+        period.start,
+        Utc.with_ymd_and_hms(2025, 12, 1, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis()
+    );
+    assert_eq!(
+        period.end,
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis()
+    );
+}
+
+#[test]
+fn test_daily_period() {
+    let at = Utc.with_ymd_and_hms(2024, 2, 28, 13, 0, 0).unwrap();
+    let period = BillingPeriod::containing(UsagePeriod::Daily, at.timestamp_millis());
+
+    assert_eq!(period.end - period.start, 24 * 60 * 60 * 1000);
+    assert_eq!(
+        period.end,
+        Utc.with_ymd_and_hms(2024, 2, 29, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis()
+    );
+}
+
+#[test]
+fn test_invalid_month() {
+    assert!(BillingPeriod::month(2025, 13).is_none());
+    assert!(BillingPeriod::month(2025, 0).is_none());
+}
+
+// --- Quota Tests ---
+
+#[test]
+fn test_quota_generosity() {
+    let free = quota(Some(10_000), Some(50));
+    let pro = quota(Some(1_000_000), None);
+    let unlimited = quota(None, None);
+
+    assert!(pro.is_at_least(&free));
+    assert!(!free.is_at_least(&pro));
+    assert!(unlimited.is_at_least(&pro));
+    assert!(!pro.is_at_least(&unlimited));
+}
@@ -0,0 +1,109 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    PartialOrd,
+    Eq,
+    Ord,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum UsagePeriod {
+    #[sea_orm(string_value = "daily")]
+    Daily,
+    #[sea_orm(string_value = "monthly")]
+    Monthly,
+}
+
+/// # Usage Quota
+///
+/// The `usage_quota` table stores how much model usage the accounts holding a flag
+/// (e.g. `free`, `pro`) can consume per period. `NULL` limits are unlimited.
+///
+/// The quota without `flag_id` is the default one, applied to accounts that hold no flag
+/// with a quota. An account holding several flags with quotas gets the most generous one.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(table_name = "usage_quota")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "flag_id", nullable, unique)]
+    pub flag_id: Option<ID>,
+
+    #[sea_orm(column_type = "Text", column_name = "period")]
+    pub period: UsagePeriod,
+
+    /// # Max Tokens
+    ///
+    /// Prompt and completion tokens that can be consumed per period.
+    #[sea_orm(column_type = "BigInteger", column_name = "max_tokens", nullable)]
+    pub max_tokens: Option<i64>,
+
+    /// # Max Messages
+    ///
+    /// Model replies that can be generated per period. Summaries are not counted.
+    #[sea_orm(column_type = "BigInteger", column_name = "max_messages", nullable)]
+    pub max_messages: Option<i64>,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "deleted_at", nullable)]
+    pub deleted_at: Option<Timestamp>,
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Flag,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Flag => Entity::belongs_to(crate::entities::account::flag::Entity)
+                .from(Column::FlagId)
+                .to(crate::entities::account::flag::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::account::flag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Flag.def()
+    }
+}
+
+impl Model {
+    /// Whether this quota allows at least as much usage as `other`.
+    pub fn is_at_least(&self, other: &Model) -> bool {
+        fn ge(a: Option<i64>, b: Option<i64>) -> bool {
+            match (a, b) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(a), Some(b)) => a >= b,
+            }
+        }
+
+        ge(self.max_tokens, other.max_tokens) && ge(self.max_messages, other.max_messages)
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,102 @@
+use sea_orm::entity::prelude::*;
+use serde::{self, Deserialize, Serialize};
+
+use crate::types::{ID, Timestamp};
+
+#[derive(
+    Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, EnumIter, DeriveActiveEnum,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum UsageKind {
+    /// A reply of the model posted in a room.
+    #[sea_orm(string_value = "completion")]
+    Completion,
+    /// A rolling summary of the conversation of a room.
+    #[sea_orm(string_value = "summary")]
+    Summary,
+}
+
+/// # Usage Record
+///
+/// The `usage_record` table is the ledger of the tokens consumed by model generated
+/// messages. There is one record per generated message, charged to the account whose
+/// message triggered the generation (`NULL` for generations without a trigger account).
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(table_name = "usage_record")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "Uuid",
+        column_name = "id"
+    )]
+    pub id: ID,
+
+    #[sea_orm(column_type = "Uuid", column_name = "account_id", indexed, nullable)]
+    pub account_id: Option<ID>,
+    #[sea_orm(column_type = "Uuid", column_name = "room_id", indexed)]
+    pub room_id: ID,
+    #[sea_orm(column_type = "Uuid", column_name = "message_id", nullable)]
+    pub message_id: Option<ID>,
+    #[sea_orm(column_type = "Text", column_name = "model_tag")]
+    pub model_tag: String,
+    #[sea_orm(column_type = "Text", column_name = "kind")]
+    pub kind: UsageKind,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "prompt_tokens")]
+    pub prompt_tokens: i64,
+    #[sea_orm(column_type = "BigInteger", column_name = "completion_tokens")]
+    pub completion_tokens: i64,
+
+    #[sea_orm(column_type = "BigInteger", column_name = "created_at", auto_now_add)]
+    pub created_at: Timestamp,
+    #[sea_orm(column_type = "BigInteger", column_name = "updated_at", auto_now)]
+    pub updated_at: Timestamp,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Account,
+    Room,
+    Message,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Account => Entity::belongs_to(crate::entities::account::account::Entity)
+                .from(Column::AccountId)
+                .to(crate::entities::account::account::Column::Id)
+                .into(),
+            Self::Room => Entity::belongs_to(crate::entities::room::room::Entity)
+                .from(Column::RoomId)
+                .to(crate::entities::room::room::Column::Id)
+                .into(),
+            Self::Message => Entity::belongs_to(crate::entities::room::message::Entity)
+                .from(Column::MessageId)
+                .to(crate::entities::room::message::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<crate::entities::account::account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Account.def()
+    }
+}
+
+impl Related<crate::entities::room::room::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Room.def()
+    }
+}
+
+impl Related<crate::entities::room::message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,10 +1,19 @@
+use sea_orm::sea_query::{Index, IndexCreateStatement};
 use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, Schema};
 use tracing::info;
 
 // Import all the Entity types from your entities modules
 use crate::entities::{
-    account::{account, account_email, account_flag, email, external_identity, flag}, country, room::{member, message, room, template}, tag
+    account::{account, account_email, account_flag, email, external_identity, flag},
+    country,
+    room::{
+        attachment, direct_room, export, invite, member, message, message_reaction, report, room,
+        scheduled_message, settings, ticket, template, template_seed, template_version,
+    },
+    tag,
+    usage::{usage_quota, usage_record},
 };
+use crate::entities::room::search::SearchLanguage;
 
 /// Creates all necessary database tables for the application entities if they don't exist.
 ///
@@ -56,8 +65,160 @@ pub async fn create_tables_if_not_exists(db: &DatabaseConnection) -> Result<(),
     create_table::<room::Entity>(db, &schema_manager, db_backend).await?;
     create_table::<member::Entity>(db, &schema_manager, db_backend).await?;
     create_table::<template::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<template_version::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<template_seed::Entity>(db, &schema_manager, db_backend).await?;
     create_table::<message::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<message_reaction::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<invite::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<attachment::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<export::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<report::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<settings::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<ticket::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<scheduled_message::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<direct_room::Entity>(db, &schema_manager, db_backend).await?;
+
+    // --- Usage Related Tables ---
+    create_table::<usage_record::Entity>(db, &schema_manager, db_backend).await?;
+    create_table::<usage_quota::Entity>(db, &schema_manager, db_backend).await?;
+
+    // --- Indexes ---
+    create_index(
+        db,
+        db_backend,
+        "idx_message_room_id_created_at",
+        Index::create()
+            .table(message::Entity)
+            .col(message::Column::RoomId)
+            .col(message::Column::CreatedAt)
+            .to_owned(),
+    )
+    .await?;
+
+    create_index(
+        db,
+        db_backend,
+        "idx_room_template_version_template_id_version",
+        Index::create()
+            .table(template_version::Entity)
+            .col(template_version::Column::TemplateId)
+            .col(template_version::Column::Version)
+            .unique()
+            .to_owned(),
+    )
+    .await?;
+
+    create_index(
+        db,
+        db_backend,
+        "idx_usage_record_account_id_created_at",
+        Index::create()
+            .table(usage_record::Entity)
+            .col(usage_record::Column::AccountId)
+            .col(usage_record::Column::CreatedAt)
+            .to_owned(),
+    )
+    .await?;
+
+    create_member_role_column(db).await?;
+    allow_placeholder_members(db).await?;
+    create_search_columns(db, SearchLanguage::default()).await?;
 
     info!("Database table setup complete.");
     Ok(())
 }
+
+/// Adds the `role` column (if it doesn't exist) to the members created before roles, the
+/// owners being given the `owner` role from `is_owner` and everyone else the `member` one.
+///
+/// `is_owner` is kept in sync with `role`, so the backfill is a no-op once done. Only
+/// Postgres supports `ADD COLUMN IF NOT EXISTS`, so nothing is done for other backends.
+pub async fn create_member_role_column(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let db_backend = db.get_database_backend();
+    if db_backend != DbBackend::Postgres {
+        return Ok(());
+    }
+
+    info!("Creating column (if not exists): member.role");
+    let statements = [
+        "ALTER TABLE member ADD COLUMN IF NOT EXISTS role text NOT NULL DEFAULT 'member'",
+        "UPDATE member SET role = 'owner' WHERE is_owner AND role <> 'owner'",
+    ];
+    for statement in statements {
+        db.execute_unprepared(statement).await?;
+    }
+    Ok(())
+}
+
+/// Lets `member.account_id` be null (if it isn't already), for the placeholders created by
+/// imports. As with the other column changes, nothing is done for backends other than Postgres.
+pub async fn allow_placeholder_members(db: &DatabaseConnection) -> Result<(), DbErr> {
+    if db.get_database_backend() != DbBackend::Postgres {
+        return Ok(());
+    }
+
+    info!("Dropping not null (if set): member.account_id");
+    db.execute_unprepared("ALTER TABLE member ALTER COLUMN account_id DROP NOT NULL")
+        .await?;
+    Ok(())
+}
+
+/// Adds the full-text search columns (if they don't exist) to the messages and templates,
+/// with their GIN indexes.
+///
+/// The `search_vector` columns are generated by Postgres from the content, stemmed with
+/// `language`. As they are only added once, switching to another language requires dropping
+/// them first. Other backends have no full-text search, so nothing is done for them.
+pub async fn create_search_columns(
+    db: &DatabaseConnection,
+    language: SearchLanguage,
+) -> Result<(), DbErr> {
+    let db_backend = db.get_database_backend();
+    if db_backend != DbBackend::Postgres {
+        return Ok(());
+    }
+
+    info!(
+        "Creating search columns (if not exists): {}",
+        language.regconfig()
+    );
+    let statements = [
+        format!(
+            "ALTER TABLE message ADD COLUMN IF NOT EXISTS search_vector tsvector \
+             GENERATED ALWAYS AS (to_tsvector('{0}', coalesce(content, ''))) STORED",
+            language.regconfig()
+        ),
+        format!(
+            "ALTER TABLE room_template ADD COLUMN IF NOT EXISTS search_vector tsvector \
+             GENERATED ALWAYS AS (\
+                 setweight(to_tsvector('{0}', coalesce(name, '')), 'A') || \
+                 setweight(to_tsvector('{0}', coalesce(description, '')), 'B')\
+             ) STORED",
+            language.regconfig()
+        ),
+        "CREATE INDEX IF NOT EXISTS idx_message_search_vector \
+         ON message USING GIN (search_vector)"
+            .to_string(),
+        "CREATE INDEX IF NOT EXISTS idx_room_template_search_vector \
+         ON room_template USING GIN (search_vector)"
+            .to_string(),
+    ];
+    for statement in statements {
+        db.execute_unprepared(&statement).await?;
+    }
+    Ok(())
+}
+
+/// Creates an index (if it doesn't exist) that can't be expressed through the entity attributes,
+/// such as composite indexes.
+async fn create_index(
+    db: &DatabaseConnection,
+    db_backend: DbBackend,
+    name: &str,
+    mut stmt: IndexCreateStatement,
+) -> Result<(), DbErr> {
+    info!("Creating index (if not exists): {}", name);
+    db.execute(db_backend.build(stmt.name(name).if_not_exists()))
+        .await?;
+    Ok(())
+}
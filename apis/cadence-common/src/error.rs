@@ -102,6 +102,8 @@ pub enum EntityError {
     InvalidIntegrity(String),
     #[schema(example = "Data type mismatch")]
     InvalidDataType(String),
+    #[schema(example = "Monthly token quota exhausted")]
+    QuotaExceeded(String),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
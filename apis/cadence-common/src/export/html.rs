@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+
+use crate::export::{
+    AuthorKind, ExportWriter, ExportedMessage, ExportedRoom, format_size, format_timestamp,
+};
+
+/// Inlined so the page renders without any other file.
+const STYLE: &str = "\
+body{margin:0;background:#f6f7f9;color:#1d2330;font:15px/1.5 system-ui,sans-serif}\
+main{max-width:760px;margin:0 auto;padding:24px 16px}\
+header{border-bottom:1px solid #dde1e7;margin-bottom:16px}\
+article{background:#fff;border:1px solid #e3e6eb;border-radius:8px;margin:10px 0;padding:10px 14px}\
+article.model{background:#f0f5ff}\
+article.system{background:none;border:none;color:#68707d;font-style:italic;padding:2px 14px}\
+.meta{color:#68707d;font-size:13px}\
+.author{color:#1d2330;font-weight:600;margin-right:8px}\
+.pinned{margin-left:8px}\
+.reply{border-left:3px solid #c6ccd6;font-size:13px;margin:6px 0;padding-left:8px}\
+.content{white-space:pre-wrap;word-wrap:break-word}\
+.attachment{font-size:13px;margin-top:6px}";
+
+/// # HTML Export Writer
+///
+/// Writes the conversation as a self-contained HTML page: styles are inlined and nothing
+/// is loaded from elsewhere, except attachments which are links. All the text is escaped.
+pub struct HtmlExportWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> HtmlExportWriter<W> {
+    pub fn new(out: W) -> Self {
+        HtmlExportWriter { out }
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn iso_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+impl<W: Write + Send> ExportWriter for HtmlExportWriter<W> {
+    fn begin(&mut self, room: &ExportedRoom) -> io::Result<()> {
+        let name = escape(room.name.as_deref().unwrap_or("Untitled room"));
+        write!(
+            self.out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{0}</title>\n<style>{1}</style>\n</head>\n<body>\n<main>\n\
+             <header>\n<h1>{0}</h1>\n",
+            name, STYLE
+        )?;
+        if let Some(description) = &room.description {
+            writeln!(self.out, "<p>{}</p>", escape(description))?;
+        }
+        writeln!(
+            self.out,
+            "<p class=\"meta\">Exported on <time datetime=\"{}\">{}</time></p>\n</header>",
+            iso_timestamp(room.exported_at),
+            format_timestamp(room.exported_at)
+        )
+    }
+
+    fn message(&mut self, message: &ExportedMessage) -> io::Result<()> {
+        let time = format!(
+            "<time datetime=\"{}\">{}</time>",
+            iso_timestamp(message.created_at),
+            format_timestamp(message.created_at)
+        );
+
+        if let Some(text) = message.system_text() {
+            return writeln!(
+                self.out,
+                "<article id=\"msg-{}\" class=\"system\">{} <span class=\"meta\">{}</span></article>",
+                message.id,
+                escape(&text),
+                time
+            );
+        }
+
+        let class = match message.author.kind {
+            AuthorKind::Model => "message model",
+            _ => "message",
+        };
+        write!(
+            self.out,
+            "<article id=\"msg-{}\" class=\"{}\">\n<div class=\"meta\">\
+             <span class=\"author\">{}</span>{}",
+            message.id,
+            class,
+            escape(&message.author.display_name),
+            time
+        )?;
+        if message.pinned_at.is_some() {
+            write!(self.out, "<span class=\"pinned\">📌 Pinned</span>")?;
+        }
+        writeln!(self.out, "</div>")?;
+
+        if let Some(reply_to) = message.reply_to {
+            writeln!(
+                self.out,
+                "<div class=\"reply\">↪ In reply to <a href=\"#msg-{}\">this message</a></div>",
+                reply_to
+            )?;
+        }
+        if let Some(content) = &message.content {
+            writeln!(self.out, "<div class=\"content\">{}</div>", escape(content))?;
+        }
+        if let Some(attachment) = &message.attachment {
+            writeln!(
+                self.out,
+                "<div class=\"attachment\">📎 <a href=\"{}\">{}</a> ({}, {})</div>",
+                escape(&attachment.url),
+                escape(&attachment.file_name),
+                escape(&attachment.content_type),
+                format_size(attachment.size_bytes)
+            )?;
+        }
+        writeln!(self.out, "</article>")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        writeln!(self.out, "</main>\n</body>\n</html>")?;
+        self.out.flush()
+    }
+}
@@ -0,0 +1,435 @@
+//!
+//! Conversation imports.
+//!
+//! An archive is parsed into an [`ImportedConversation`], either from a Cadence JSON
+//! export or from an OpenAI-style chat transcript, then turned into an [`ImportPlan`]
+//! describing the room to create. `RoomService::import_room` applies the plan.
+//!
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::entities::room::message::MessageType;
+use crate::entities::room::room::Model as RoomModel;
+use crate::error::{CadenceError, InputError};
+use crate::export::{AuthorKind, ExportedMessage, ExportedRoom};
+use crate::types::Timestamp;
+
+/// Maximum number of messages of an import.
+pub const MAX_IMPORT_MESSAGES: usize = 10_000;
+
+/// Model tag of the assistant messages of a transcript that does not name its model.
+pub const UNKNOWN_MODEL_TAG: &str = "unknown";
+
+/// Display name of the user messages of a transcript that do not name their author.
+pub const DEFAULT_USER_NAME: &str = "User";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// A JSON export written by `JsonExportWriter`.
+    Cadence,
+    /// A list of `{"role": ..., "content": ...}` messages, bare or under `messages`.
+    OpenAi,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportedAuthor {
+    Member(String),
+    Model(String),
+    /// Events of the room, with the member they are about, if any.
+    System(Option<String>),
+}
+
+/// # Imported Message
+///
+/// - `source_id`: id of the message in the archive, only used to resolve replies.
+#[derive(Debug, Clone)]
+pub struct ImportedMessage {
+    pub source_id: Option<String>,
+    pub author: ImportedAuthor,
+    pub message_type: MessageType,
+    pub content: Option<String>,
+    pub reply_to: Option<String>,
+    pub pinned_at: Option<Timestamp>,
+    pub has_attachment: bool,
+    pub created_at: Timestamp,
+}
+
+/// # Imported Conversation
+///
+/// An archive in a format independent form.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedConversation {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model_tag: Option<String>,
+    pub system_prompt: Option<String>,
+    pub messages: Vec<ImportedMessage>,
+}
+
+fn invalid(message: impl Into<String>) -> CadenceError {
+    CadenceError::Input(InputError::InvalidFormat(message.into()))
+}
+
+/// Guesses the format of an archive: Cadence exports have a `room`, transcripts have
+/// messages with a `role`.
+pub fn detect_format(value: &Value) -> Option<ImportFormat> {
+    if value.get("room").is_some() && value.get("messages").is_some_and(Value::is_array) {
+        return Some(ImportFormat::Cadence);
+    }
+
+    let messages = match value {
+        Value::Array(messages) => messages,
+        _ => value.get("messages")?.as_array()?,
+    };
+    messages
+        .iter()
+        .all(|m| m.get("role").is_some_and(Value::is_string))
+        .then_some(ImportFormat::OpenAi)
+}
+
+/// Parses an archive, detecting its format when `format` is `None`. `now` dates the
+/// messages of transcripts without timestamps.
+pub fn parse_import(
+    data: &[u8],
+    format: Option<ImportFormat>,
+    now: Timestamp,
+) -> Result<ImportedConversation, CadenceError> {
+    let value: Value =
+        serde_json::from_slice(data).map_err(|e| invalid(format!("invalid JSON: {}", e)))?;
+    parse_import_value(value, format, now)
+}
+
+/// Like `parse_import`, for an archive already parsed as JSON.
+pub fn parse_import_value(
+    value: Value,
+    format: Option<ImportFormat>,
+    now: Timestamp,
+) -> Result<ImportedConversation, CadenceError> {
+    let format = match format {
+        Some(format) => format,
+        None => detect_format(&value).ok_or_else(|| invalid("unknown archive format"))?,
+    };
+
+    match format {
+        ImportFormat::Cadence => parse_cadence(value),
+        ImportFormat::OpenAi => parse_openai(value, now),
+    }
+}
+
+#[derive(Deserialize)]
+struct CadenceArchive {
+    room: ExportedRoom,
+    messages: Vec<ExportedMessage>,
+}
+
+fn parse_cadence(value: Value) -> Result<ImportedConversation, CadenceError> {
+    let archive: CadenceArchive = serde_json::from_value(value)
+        .map_err(|e| invalid(format!("invalid Cadence export: {}", e)))?;
+
+    let messages = archive
+        .messages
+        .into_iter()
+        .map(|m| ImportedMessage {
+            source_id: Some(m.id.to_string()),
+            author: match m.author.kind {
+                AuthorKind::Member => ImportedAuthor::Member(m.author.display_name),
+                AuthorKind::Model => ImportedAuthor::Model(m.author.display_name),
+                AuthorKind::System => ImportedAuthor::System(Some(m.author.display_name)),
+            },
+            message_type: m.message_type,
+            content: m.content,
+            reply_to: m.reply_to.map(|id| id.to_string()),
+            pinned_at: m.pinned_at,
+            has_attachment: m.attachment.is_some(),
+            created_at: m.created_at,
+        })
+        .collect();
+
+    Ok(ImportedConversation {
+        name: archive.room.name,
+        description: archive.room.description,
+        model_tag: archive.room.model_tag,
+        system_prompt: None,
+        messages,
+    })
+}
+
+/// Text of an OpenAI message content, either a string or a list of parts.
+fn openai_content(content: Option<&Value>) -> Option<String> {
+    match content? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(parts) => {
+            let text: Vec<&str> = parts
+                .iter()
+                .filter_map(|part| match part {
+                    Value::String(text) => Some(text.as_str()),
+                    _ => part.get("text").and_then(Value::as_str),
+                })
+                .collect();
+            (!text.is_empty()).then(|| text.join("\n"))
+        }
+        _ => None,
+    }
+}
+
+/// Timestamp of an OpenAI message, in seconds (possibly fractional) or milliseconds.
+fn openai_timestamp(message: &Value) -> Option<Timestamp> {
+    let value = ["created_at", "timestamp", "create_time", "created"]
+        .iter()
+        .find_map(|field| message.get(*field).and_then(Value::as_f64))?;
+    // anything below 10^11 is too early to be milliseconds
+    Some(if value < 1e11 {
+        (value * 1000.0) as Timestamp
+    } else {
+        value as Timestamp
+    })
+}
+
+fn parse_openai(value: Value, now: Timestamp) -> Result<ImportedConversation, CadenceError> {
+    let (header, messages) = match value {
+        Value::Array(messages) => (Value::Null, messages),
+        Value::Object(mut object) => match object.remove("messages") {
+            Some(Value::Array(messages)) => (Value::Object(object), messages),
+            _ => return Err(invalid("transcript has no messages")),
+        },
+        _ => return Err(invalid("transcript must be an object or an array")),
+    };
+
+    let model_tag = header
+        .get("model")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let name = header
+        .get("title")
+        .or_else(|| header.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    // messages without a timestamp follow the previous one by a millisecond
+    let mut created_at = openai_timestamp(&header).unwrap_or(now);
+
+    let mut system_prompts = Vec::new();
+    let mut imported = Vec::with_capacity(messages.len());
+    for (index, message) in messages.iter().enumerate() {
+        let role = message
+            .get("role")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid(format!("message {} has no role", index)))?;
+        let content = openai_content(message.get("content"));
+        created_at = openai_timestamp(message).unwrap_or(created_at + 1);
+
+        let author = match role {
+            "system" | "developer" => {
+                system_prompts.extend(content);
+                continue;
+            }
+            "user" => ImportedAuthor::Member(
+                message
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or(DEFAULT_USER_NAME)
+                    .to_string(),
+            ),
+            "assistant" => ImportedAuthor::Model(
+                model_tag
+                    .clone()
+                    .unwrap_or_else(|| UNKNOWN_MODEL_TAG.to_string()),
+            ),
+            // tool calls and results have no counterpart in a room
+            "tool" | "function" => continue,
+            other => {
+                return Err(invalid(format!(
+                    "message {} has unknown role {}",
+                    index, other
+                )));
+            }
+        };
+
+        if content.as_deref().is_none_or(|c| c.trim().is_empty()) {
+            continue;
+        }
+
+        imported.push(ImportedMessage {
+            source_id: None,
+            author,
+            message_type: MessageType::Default,
+            content,
+            reply_to: None,
+            pinned_at: None,
+            has_attachment: false,
+            created_at,
+        });
+    }
+
+    Ok(ImportedConversation {
+        name,
+        description: None,
+        model_tag,
+        system_prompt: (!system_prompts.is_empty()).then(|| system_prompts.join("\n\n")),
+        messages: imported,
+    })
+}
+
+/// Member a planned message is attributed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlannedMember {
+    /// The importing account.
+    Own,
+    /// A placeholder member, by index in `ImportPlan::placeholders`.
+    Placeholder(usize),
+}
+
+/// # Planned Message
+///
+/// A message as it will be inserted. `reply_to` is the index of the replied message in
+/// `ImportPlan::messages`, which always comes earlier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedMessage {
+    pub member: Option<PlannedMember>,
+    pub system: bool,
+    pub model_tag: Option<String>,
+    pub message_type: MessageType,
+    pub content: Option<String>,
+    pub reply_to: Option<usize>,
+    pub pinned_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+}
+
+/// # Import Plan
+///
+/// What an import creates: a room, a placeholder member per author of the archive other
+/// than `own_author`, and its messages in chronological order. Attachments cannot be
+/// imported, only counted.
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model_tag: Option<String>,
+    pub system_prompt: Option<String>,
+    pub placeholders: Vec<String>,
+    pub messages: Vec<PlannedMessage>,
+    pub skipped_attachments: u64,
+}
+
+impl ImportPlan {
+    /// Plans the import of a conversation. Messages of `own_author` are attributed to the
+    /// importing account, those of the other authors to placeholders named after them.
+    pub fn new(
+        conversation: ImportedConversation,
+        own_author: Option<&str>,
+    ) -> Result<Self, CadenceError> {
+        if conversation.messages.is_empty() {
+            return Err(CadenceError::Input(InputError::InvalidLength(
+                "archive has no messages".to_string(),
+            )));
+        }
+        if conversation.messages.len() > MAX_IMPORT_MESSAGES {
+            return Err(CadenceError::Input(InputError::InvalidLength(format!(
+                "archive has more than {} messages",
+                MAX_IMPORT_MESSAGES
+            ))));
+        }
+
+        let mut sources = conversation.messages;
+        // stable, so messages of the same millisecond keep their order
+        sources.sort_by_key(|m| m.created_at);
+
+        let mut placeholders: Vec<String> = Vec::new();
+        let mut member_for = |name: &str| {
+            if own_author == Some(name) {
+                return PlannedMember::Own;
+            }
+            let index = match placeholders.iter().position(|p| p == name) {
+                Some(index) => index,
+                None => {
+                    placeholders.push(name.to_string());
+                    placeholders.len() - 1
+                }
+            };
+            PlannedMember::Placeholder(index)
+        };
+
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut skipped_attachments = 0;
+        let mut messages = Vec::with_capacity(sources.len());
+        for (index, source) in sources.into_iter().enumerate() {
+            let (member, system, model_tag) = match &source.author {
+                ImportedAuthor::Member(name) => (Some(member_for(name)), false, None),
+                ImportedAuthor::Model(tag) => (None, false, Some(tag.clone())),
+                ImportedAuthor::System(name) => (name.as_deref().map(&mut member_for), true, None),
+            };
+
+            if source.has_attachment {
+                skipped_attachments += 1;
+            }
+            if let Some(source_id) = source.source_id {
+                positions.insert(source_id, index);
+            }
+
+            messages.push(PlannedMessage {
+                member,
+                system,
+                model_tag,
+                message_type: source.message_type,
+                content: source.content,
+                reply_to: source.reply_to.and_then(|id| positions.get(&id).copied()),
+                pinned_at: source.pinned_at,
+                created_at: source.created_at,
+            });
+        }
+
+        Ok(ImportPlan {
+            name: conversation.name,
+            description: conversation.description,
+            model_tag: conversation.model_tag,
+            system_prompt: conversation.system_prompt,
+            placeholders,
+            messages,
+            skipped_attachments,
+        })
+    }
+
+    /// Report of the plan, with the room created out of it unless it was a dry run.
+    pub fn report(&self, room: Option<RoomModel>) -> ImportReport {
+        let count =
+            |f: fn(&PlannedMessage) -> bool| self.messages.iter().filter(|m| f(m)).count() as u64;
+
+        ImportReport {
+            dry_run: room.is_none(),
+            room,
+            name: self.name.clone(),
+            model_tag: self.model_tag.clone(),
+            message_count: self.messages.len() as u64,
+            model_message_count: count(|m| m.model_tag.is_some()),
+            system_message_count: count(|m| m.system),
+            pinned_message_count: count(|m| m.pinned_at.is_some()),
+            placeholder_members: self.placeholders.clone(),
+            skipped_attachments: self.skipped_attachments,
+            first_message_at: self.messages.first().map(|m| m.created_at),
+            last_message_at: self.messages.last().map(|m| m.created_at),
+        }
+    }
+}
+
+/// # Import Report
+///
+/// What an import created, or would create for a dry run (`room` being `None`).
+/// `placeholder_members` are the names of the archive authors given a placeholder.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub room: Option<RoomModel>,
+    pub name: Option<String>,
+    pub model_tag: Option<String>,
+    pub message_count: u64,
+    pub model_message_count: u64,
+    pub system_message_count: u64,
+    pub pinned_message_count: u64,
+    pub placeholder_members: Vec<String>,
+    pub skipped_attachments: u64,
+    pub first_message_at: Option<Timestamp>,
+    pub last_message_at: Option<Timestamp>,
+}
@@ -0,0 +1,39 @@
+use std::io::{self, Write};
+
+use crate::export::{ExportWriter, ExportedMessage, ExportedRoom};
+
+/// # JSON Export Writer
+///
+/// Writes a single object `{"room": {...}, "messages": [...]}`, one message at a time.
+pub struct JsonExportWriter<W: Write> {
+    out: W,
+    first: bool,
+}
+
+impl<W: Write> JsonExportWriter<W> {
+    pub fn new(out: W) -> Self {
+        JsonExportWriter { out, first: true }
+    }
+}
+
+impl<W: Write + Send> ExportWriter for JsonExportWriter<W> {
+    fn begin(&mut self, room: &ExportedRoom) -> io::Result<()> {
+        self.out.write_all(b"{\"room\":")?;
+        serde_json::to_writer(&mut self.out, room)?;
+        self.out.write_all(b",\"messages\":[")
+    }
+
+    fn message(&mut self, message: &ExportedMessage) -> io::Result<()> {
+        if !self.first {
+            self.out.write_all(b",")?;
+        }
+        self.first = false;
+        serde_json::to_writer(&mut self.out, message)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.write_all(b"]}\n")?;
+        self.out.flush()
+    }
+}
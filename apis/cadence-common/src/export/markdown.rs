@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+
+use crate::export::{ExportWriter, ExportedMessage, ExportedRoom, format_size, format_timestamp};
+
+/// # Markdown Export Writer
+///
+/// Writes the conversation as a Markdown document, one section per message. The content
+/// of the messages is Markdown already and is kept as is; replies link to the anchor of
+/// the message they reply to.
+pub struct MarkdownExportWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> MarkdownExportWriter<W> {
+    pub fn new(out: W) -> Self {
+        MarkdownExportWriter { out }
+    }
+}
+
+/// Escapes the characters that would format a name.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '`' | '[' | ']' | '#' | '<' | '>' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl<W: Write + Send> ExportWriter for MarkdownExportWriter<W> {
+    fn begin(&mut self, room: &ExportedRoom) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "# {}\n",
+            escape(room.name.as_deref().unwrap_or("Untitled room"))
+        )?;
+        if let Some(description) = &room.description {
+            writeln!(self.out, "{}\n", description)?;
+        }
+        writeln!(
+            self.out,
+            "_Exported on {}_\n\n---\n",
+            format_timestamp(room.exported_at)
+        )
+    }
+
+    fn message(&mut self, message: &ExportedMessage) -> io::Result<()> {
+        writeln!(self.out, "<a id=\"msg-{}\"></a>", message.id)?;
+
+        if let Some(text) = message.system_text() {
+            return writeln!(
+                self.out,
+                "_{} · {}_\n",
+                escape(&text),
+                format_timestamp(message.created_at)
+            );
+        }
+
+        write!(
+            self.out,
+            "### {} · {}",
+            escape(&message.author.display_name),
+            format_timestamp(message.created_at)
+        )?;
+        if message.pinned_at.is_some() {
+            write!(self.out, " · 📌 Pinned")?;
+        }
+        writeln!(self.out, "\n")?;
+
+        if let Some(reply_to) = message.reply_to {
+            writeln!(
+                self.out,
+                "> ↪ In reply to [this message](#msg-{})\n",
+                reply_to
+            )?;
+        }
+        if let Some(content) = &message.content {
+            writeln!(self.out, "{}\n", content)?;
+        }
+        if let Some(attachment) = &message.attachment {
+            writeln!(
+                self.out,
+                "📎 [{}](<{}>) ({})\n",
+                escape(&attachment.file_name),
+                attachment.url,
+                format_size(attachment.size_bytes)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,152 @@
+//!
+//! Conversation exports.
+//!
+//! A room is exported by feeding its room and messages, oldest first, to an
+//! [`ExportWriter`] which streams them to any [`std::io::Write`] as JSON
+//! ([`json::JsonExportWriter`]), Markdown ([`markdown::MarkdownExportWriter`]) or a
+//! self-contained HTML page ([`html::HtmlExportWriter`]). Messages are read by
+//! `RoomService::export_room`, and large rooms are exported in the background by
+//! `ExportService`.
+//!
+//! [`import`] reads such JSON exports back, as well as OpenAI-style chat transcripts, to
+//! create new rooms out of them.
+//!
+
+pub mod html;
+pub mod import;
+pub mod json;
+pub mod markdown;
+#[cfg(test)]
+pub mod tests;
+
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::room::export::ExportFormat;
+use crate::entities::room::message::MessageType;
+use crate::types::{ID, Timestamp};
+
+/// # Exported Room
+///
+/// Header of an export.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedRoom {
+    pub id: ID,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model_tag: Option<String>,
+    pub exported_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorKind {
+    Member,
+    Model,
+    /// Events of the room, the display name being the member they are about, if any.
+    System,
+}
+
+/// # Exported Author
+///
+/// The display name of anonymized members is a placeholder, never their account name.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExportedAuthor {
+    pub kind: AuthorKind,
+    pub display_name: String,
+}
+
+/// # Exported Attachment
+///
+/// - `url`: link to the attachment. It is not signed, the reader downloads it through
+///   the application as a member of the room.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedAttachment {
+    pub id: ID,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub url: String,
+}
+
+/// # Exported Message
+///
+/// - `reply_to`: id of the message replied to, which may not be part of the export when
+///   it was deleted.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedMessage {
+    pub id: ID,
+    pub author: ExportedAuthor,
+    pub message_type: MessageType,
+    pub content: Option<String>,
+    pub reply_to: Option<ID>,
+    pub pinned_at: Option<Timestamp>,
+    pub attachment: Option<ExportedAttachment>,
+    pub created_at: Timestamp,
+}
+
+impl ExportedMessage {
+    /// Text of a system message, e.g. "Alice left the room", `None` for other messages.
+    pub fn system_text(&self) -> Option<String> {
+        if self.author.kind != AuthorKind::System {
+            return None;
+        }
+
+        let name = &self.author.display_name;
+        let text = match self.message_type {
+            MessageType::RecipientAdded => format!("{} joined the room", name),
+            MessageType::RecipientRemoved => format!("{} was removed from the room", name),
+            MessageType::RecipientBanned => format!("{} was banned from the room", name),
+            MessageType::RecipientUnbanned => format!("{} was unbanned from the room", name),
+            MessageType::RecipientLeft => format!("{} left the room", name),
+            _ => self.content.clone().unwrap_or_default(),
+        };
+        Some(text)
+    }
+}
+
+/// # Export Writer
+///
+/// Writes an export incrementally: `begin` once, `message` for every message in
+/// chronological order, then `finish` once.
+pub trait ExportWriter: Send {
+    fn begin(&mut self, room: &ExportedRoom) -> io::Result<()>;
+    fn message(&mut self, message: &ExportedMessage) -> io::Result<()>;
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+/// The writer of a format, writing to `out`.
+pub fn export_writer<'a, W: Write + Send + 'a>(
+    format: ExportFormat,
+    out: W,
+) -> Box<dyn ExportWriter + 'a> {
+    match format {
+        ExportFormat::Json => Box::new(json::JsonExportWriter::new(out)),
+        ExportFormat::Markdown => Box::new(markdown::MarkdownExportWriter::new(out)),
+        ExportFormat::Html => Box::new(html::HtmlExportWriter::new(out)),
+    }
+}
+
+/// Human readable UTC time, e.g. `2025-01-31 14:05 UTC`.
+pub fn format_timestamp(timestamp: Timestamp) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Human readable size, e.g. `1.5 MB`.
+pub fn format_size(size_bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if size_bytes < 1024 {
+        return format!("{} B", size_bytes);
+    }
+
+    let mut size = size_bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,217 @@
+#![cfg(test)] // Ensure this file is only compiled for tests
+
+use super::import::{
+    ImportFormat, ImportPlan, ImportedAuthor, PlannedMember, UNKNOWN_MODEL_TAG, detect_format,
+    parse_import,
+};
+use super::{
+    AuthorKind, ExportedAttachment, ExportedAuthor, ExportedMessage, ExportedRoom, export_writer,
+    format_size, format_timestamp,
+};
+use crate::entities::room::export::ExportFormat;
+use crate::entities::room::message::MessageType;
+
+fn room() -> ExportedRoom {
+    ExportedRoom {
+        id: uuid::Uuid::new_v4(),
+        name: Some("Launch <plan>".to_string()),
+        description: None,
+        model_tag: None,
+        exported_at: 1_735_689_600_000,
+    }
+}
+
+fn message(kind: AuthorKind, name: &str, content: Option<&str>) -> ExportedMessage {
+    ExportedMessage {
+        id: uuid::Uuid::new_v4(),
+        author: ExportedAuthor {
+            kind,
+            display_name: name.to_string(),
+        },
+        message_type: MessageType::Default,
+        content: content.map(str::to_string),
+        reply_to: None,
+        pinned_at: None,
+        attachment: None,
+        created_at: 1_735_689_600_000,
+    }
+}
+
+fn render(format: ExportFormat, messages: &[ExportedMessage]) -> String {
+    let mut out = Vec::new();
+    {
+        let mut writer = export_writer(format, &mut out);
+        writer.begin(&room()).unwrap();
+        for message in messages {
+            writer.message(message).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+    String::from_utf8(out).unwrap()
+}
+
+fn conversation() -> Vec<ExportedMessage> {
+    let first = message(
+        AuthorKind::Member,
+        "Anonymous 1",
+        Some("<script>alert(1)</script>"),
+    );
+    let mut reply = message(AuthorKind::Model, "gpt-4o", Some("Sure."));
+    reply.reply_to = Some(first.id);
+    reply.pinned_at = Some(1_735_689_700_000);
+    reply.attachment = Some(ExportedAttachment {
+        id: uuid::Uuid::new_v4(),
+        file_name: "plan.pdf".to_string(),
+        content_type: "application/pdf".to_string(),
+        size_bytes: 2048,
+        url: "https://cadence.example/attachments/1".to_string(),
+    });
+    let mut left = message(AuthorKind::System, "Bob", None);
+    left.message_type = MessageType::RecipientLeft;
+    vec![first, reply, left]
+}
+
+// --- Export Writer Tests ---
+
+#[test]
+fn test_json_export_is_valid_json() {
+    let messages = conversation();
+    let json: serde_json::Value =
+        serde_json::from_str(&render(ExportFormat::Json, &messages)).unwrap();
+
+    assert_eq!(json["room"]["name"], "Launch <plan>");
+    assert_eq!(json["messages"].as_array().unwrap().len(), 3);
+    assert_eq!(json["messages"][1]["author"]["kind"], "model");
+    assert_eq!(json["messages"][1]["reply_to"], messages[0].id.to_string());
+    assert_eq!(json["messages"][1]["attachment"]["file_name"], "plan.pdf");
+
+    let empty: serde_json::Value = serde_json::from_str(&render(ExportFormat::Json, &[])).unwrap();
+    assert_eq!(empty["messages"].as_array().unwrap().len(), 0);
+}
+
+#[test]
+fn test_markdown_export() {
+    let messages = conversation();
+    let markdown = render(ExportFormat::Markdown, &messages);
+
+    assert!(markdown.starts_with("# Launch \\<plan\\>\n"));
+    assert!(markdown.contains(&format!("<a id=\"msg-{}\"></a>", messages[0].id)));
+    assert!(markdown.contains(&format!("(#msg-{})", messages[0].id)));
+    assert!(markdown.contains("### gpt-4o · 2025-01-01 00:00 UTC · 📌 Pinned"));
+    assert!(markdown.contains("📎 [plan.pdf](<https://cadence.example/attachments/1>) (2.0 KB)"));
+    assert!(markdown.contains("_Bob left the room · 2025-01-01 00:00 UTC_"));
+}
+
+#[test]
+fn test_html_export_escapes_content() {
+    let messages = conversation();
+    let html = render(ExportFormat::Html, &messages);
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.trim_end().ends_with("</html>"));
+    assert!(html.contains("<title>Launch &lt;plan&gt;</title>"));
+    assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    assert!(!html.contains("<script>"));
+    assert!(html.contains(&format!("<a href=\"#msg-{}\">", messages[0].id)));
+    assert!(html.contains("Bob left the room"));
+    // self-contained
+    assert!(!html.contains("<link"));
+}
+
+#[test]
+fn test_export_formatting_helpers() {
+    assert_eq!(format_timestamp(1_735_689_600_000), "2025-01-01 00:00 UTC");
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(1536), "1.5 KB");
+    assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+}
+
+// --- Import Tests ---
+
+#[test]
+fn test_import_cadence_export_round_trip() {
+    let messages = conversation();
+    let json = render(ExportFormat::Json, &messages);
+
+    let imported = parse_import(json.as_bytes(), None, 0).unwrap();
+    assert_eq!(imported.name.as_deref(), Some("Launch <plan>"));
+    assert_eq!(imported.messages.len(), 3);
+    assert_eq!(
+        imported.messages[0].author,
+        ImportedAuthor::Member("Anonymous 1".to_string())
+    );
+    assert_eq!(
+        imported.messages[1].author,
+        ImportedAuthor::Model("gpt-4o".to_string())
+    );
+    assert_eq!(
+        imported.messages[2].author,
+        ImportedAuthor::System(Some("Bob".to_string()))
+    );
+
+    let plan = ImportPlan::new(imported, None).unwrap();
+    assert_eq!(plan.placeholders, vec!["Anonymous 1", "Bob"]);
+    assert_eq!(plan.messages[1].reply_to, Some(0));
+    assert_eq!(plan.messages[1].model_tag.as_deref(), Some("gpt-4o"));
+    assert!(plan.messages[2].system);
+    assert_eq!(plan.skipped_attachments, 1);
+
+    let report = plan.report(None);
+    assert!(report.dry_run);
+    assert_eq!(report.message_count, 3);
+    assert_eq!(report.model_message_count, 1);
+    assert_eq!(report.pinned_message_count, 1);
+}
+
+#[test]
+fn test_import_openai_transcript() {
+    let transcript = br#"{
+        "model": "gpt-4o",
+        "created": 1735689600,
+        "messages": [
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "name": "alice", "content": "Hello"},
+            {"role": "assistant", "content": [{"type": "text", "text": "Hi!"}]},
+            {"role": "tool", "content": "{}"},
+            {"role": "user", "content": "Bye", "created_at": 1735689700}
+        ]
+    }"#;
+
+    let imported = parse_import(transcript, None, 0).unwrap();
+    assert_eq!(imported.model_tag.as_deref(), Some("gpt-4o"));
+    assert_eq!(imported.system_prompt.as_deref(), Some("Be brief."));
+    assert_eq!(imported.messages.len(), 3);
+    assert_eq!(imported.messages[0].created_at, 1_735_689_600_002);
+    assert_eq!(imported.messages[1].content.as_deref(), Some("Hi!"));
+    assert_eq!(imported.messages[2].created_at, 1_735_689_700_000);
+
+    let plan = ImportPlan::new(imported, Some("alice")).unwrap();
+    assert_eq!(plan.messages[0].member, Some(PlannedMember::Own));
+    assert_eq!(plan.messages[2].member, Some(PlannedMember::Placeholder(0)));
+    assert_eq!(plan.placeholders, vec!["User"]);
+}
+
+#[test]
+fn test_import_bare_transcript_without_model() {
+    let transcript =
+        br#"[{"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hey"}]"#;
+    let value = serde_json::from_slice(transcript).unwrap();
+    assert_eq!(detect_format(&value), Some(ImportFormat::OpenAi));
+
+    let imported = parse_import(transcript, Some(ImportFormat::OpenAi), 1_000).unwrap();
+    assert_eq!(imported.messages[0].created_at, 1_001);
+    assert_eq!(
+        imported.messages[1].author,
+        ImportedAuthor::Model(UNKNOWN_MODEL_TAG.to_string())
+    );
+}
+
+#[test]
+fn test_import_rejects_invalid_archives() {
+    assert!(parse_import(b"not json", None, 0).is_err());
+    assert!(parse_import(br#"{"foo": 1}"#, None, 0).is_err());
+    assert!(parse_import(br#"[{"role": "robot", "content": "x"}]"#, None, 0).is_err());
+
+    let empty = parse_import(br#"[{"role": "system", "content": "x"}]"#, None, 0).unwrap();
+    assert!(ImportPlan::new(empty, None).is_err());
+}
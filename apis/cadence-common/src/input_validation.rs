@@ -1,5 +1,6 @@
 use bcrypt::{BcryptError, BcryptResult, verify};
 use bcrypt::{DEFAULT_COST, hash};
+use std::sync::LazyLock;
 
 pub fn is_valid_email(email: &str) -> bool {
     // Consider using a dedicated email validation crate for more robustness if needed
@@ -56,3 +57,22 @@ pub fn check_password(password_attempt: &str, stored_hash: &str) -> Result<bool,
 pub fn string_to_uuid(uuid_str: &str) -> Result<uuid::Uuid, uuid::Error> {
     uuid::Uuid::parse_str(uuid_str)
 }
+
+static REACTION_SHORTCODE: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"^:[a-z0-9_+-]{1,62}:$").unwrap());
+
+pub fn is_valid_reaction(reaction: &str) -> bool {
+    // Either a shortcode like :thumbs_up: or a short emoji sequence without whitespace,
+    // which can't be made of ASCII punctuation alone
+    if REACTION_SHORTCODE.is_match(reaction) {
+        return true;
+    }
+
+    let count = reaction.chars().count();
+    count > 0
+        && count <= 16
+        && !reaction.is_ascii()
+        && reaction
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_ascii_alphanumeric() && !c.is_control())
+}
@@ -15,4 +15,9 @@ pub mod error;
 pub mod env;
 pub mod token;
 pub mod time;
-pub mod util;
\ No newline at end of file
+pub mod util;
+pub mod llm;
+pub mod client;
+pub mod storage;
+pub mod export;
+pub mod moderation;
\ No newline at end of file
@@ -0,0 +1,82 @@
+use tokio::sync::mpsc;
+
+use crate::llm::{
+    ChatRole, Completion, CompletionRequest, CompletionUsage, ModelError, ModelProvider,
+    forward_chunk,
+};
+
+/// # Echo Provider
+///
+/// Deterministic local provider. It replies with the last user message prefixed with
+/// `prefix`, or with a fixed reply when one is configured, streamed word by word. Token
+/// usage is the number of whitespace separated words, so tests can assert on it.
+#[derive(Debug, Clone)]
+pub struct EchoProvider {
+    pub prefix: String,
+    pub reply: Option<String>,
+}
+
+impl Default for EchoProvider {
+    fn default() -> Self {
+        EchoProvider {
+            prefix: "echo: ".to_string(),
+            reply: None,
+        }
+    }
+}
+
+impl EchoProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A provider that always replies with `reply`, whatever the context.
+    pub fn with_reply(reply: impl Into<String>) -> Self {
+        EchoProvider {
+            prefix: String::new(),
+            reply: Some(reply.into()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ModelProvider for EchoProvider {
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        chunks: Option<mpsc::Sender<String>>,
+    ) -> Result<Completion, ModelError> {
+        let content = match &self.reply {
+            Some(reply) => reply.clone(),
+            None => {
+                let last_user_message = request
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|m| m.role == ChatRole::User)
+                    .map(|m| m.content.as_str())
+                    .unwrap_or_default();
+                format!("{}{}", self.prefix, last_user_message)
+            }
+        };
+
+        for chunk in content.split_inclusive(' ') {
+            forward_chunk(&chunks, chunk).await;
+        }
+
+        let prompt_tokens = request
+            .messages
+            .iter()
+            .map(|m| m.content.split_whitespace().count() as u32)
+            .sum();
+
+        Ok(Completion {
+            usage: Some(CompletionUsage {
+                prompt_tokens,
+                completion_tokens: content.split_whitespace().count() as u32,
+            }),
+            content,
+            finish_reason: Some("stop".to_string()),
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//!
+//! Language model integration.
+//!
+//! Rooms with a `model_tag` get their assistant replies from a [`ModelProvider`]. Providers
+//! are pluggable: [`openai::OpenAICompatibleProvider`] talks to any OpenAI-compatible
+//! `chat/completions` endpoint, and [`echo::EchoProvider`] is a deterministic local provider
+//! meant for tests and development. The context sent to them is built by the room
+//! `ContextBuilder`.
+//!
+
+pub mod echo;
+pub mod openai;
+#[cfg(test)]
+pub mod tests;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::error::{CadenceError, EntityError, ServerError};
+
+/// # Chat Role
+///
+/// Author of a message in the context sent to a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// # Chat Message
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        ChatMessage {
+            role,
+            content: content.into(),
+        }
+    }
+}
+
+/// # Completion Request
+///
+/// `model` is the `model_tag` of the room, passed as is to the provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+}
+
+/// # Completion Usage
+///
+/// Tokens consumed by a completion, as reported by the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CompletionUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// # Completion
+///
+/// The full reply of a model once the stream is over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Completion {
+    pub content: String,
+    pub usage: Option<CompletionUsage>,
+    pub finish_reason: Option<String>,
+}
+
+/// # Model Error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelError {
+    /// The provider could not be reached, or the connection dropped mid-stream.
+    Transport(String),
+    /// The provider answered with a non-success status.
+    Provider { status: u16, message: String },
+    /// The provider answered something that is not a valid completion.
+    InvalidResponse(String),
+    /// The provider does not serve the requested model.
+    UnsupportedModel(String),
+    /// The provider did not finish the completion in time.
+    Timeout,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Transport(message) => write!(f, "transport error: {}", message),
+            ModelError::Provider { status, message } => {
+                write!(f, "provider error ({}): {}", status, message)
+            }
+            ModelError::InvalidResponse(message) => write!(f, "invalid response: {}", message),
+            ModelError::UnsupportedModel(model) => write!(f, "unsupported model: {}", model),
+            ModelError::Timeout => write!(f, "completion timed out"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// # Model Provider
+///
+/// Generates a completion for a context. When `chunks` is given, the reply is streamed
+/// through it as it is generated; a dropped receiver does not cancel the completion, since
+/// the full reply is still returned and persisted by the caller.
+#[async_trait::async_trait]
+pub trait ModelProvider: Send + Sync + fmt::Debug {
+    /// Whether the provider serves the given `model_tag`.
+    fn supports(&self, _model_tag: &str) -> bool {
+        true
+    }
+
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        chunks: Option<mpsc::Sender<String>>,
+    ) -> Result<Completion, ModelError>;
+}
+
+/// Forwards a chunk of a streamed reply, ignoring a receiver that went away.
+pub(crate) async fn forward_chunk(chunks: &Option<mpsc::Sender<String>>, chunk: &str) {
+    if let Some(sender) = chunks
+        && !chunk.is_empty()
+    {
+        let _ = sender.send(chunk.to_string()).await;
+    }
+}
+
+impl From<ModelError> for CadenceError {
+    fn from(error: ModelError) -> Self {
+        match error {
+            ModelError::Timeout => {
+                CadenceError::ServerError(ServerError::GatewayTimeout(error.to_string()))
+            }
+            ModelError::UnsupportedModel(_) => {
+                CadenceError::Entity(EntityError::InvalidState(error.to_string()))
+            }
+            _ => CadenceError::ServerError(ServerError::ServiceUnavailable(error.to_string())),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HOST};
+use hyper::{Method, Request, Response, Uri};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::client::{load_tls_config, send_request};
+use crate::llm::{
+    ChatMessage, Completion, CompletionRequest, CompletionUsage, ModelError, ModelProvider,
+    forward_chunk,
+};
+
+/// # OpenAI Compatible Config
+///
+/// - `base_url`: root of the API, e.g. `https://api.openai.com/v1`. `chat/completions` is
+///   appended to it.
+/// - `api_key`: sent as a bearer token when set.
+/// - `ca_bundle_path`: PEM bundle of the root certificates trusted for `https` endpoints.
+/// - `models`: model tags served by the endpoint, every tag is accepted when empty.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OpenAICompatibleConfig {
+    pub base_url: String,
+    pub api_key: Option<String>,
+    #[serde(default = "default_ca_bundle_path")]
+    pub ca_bundle_path: String,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+fn default_ca_bundle_path() -> String {
+    "/etc/ssl/certs/ca-certificates.crt".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    120_000
+}
+
+/// # OpenAI Compatible Provider
+///
+/// Streams completions from an OpenAI-compatible `chat/completions` endpoint (OpenAI,
+/// vLLM, Ollama, LM Studio, ...) over server-sent events.
+#[derive(Debug, Clone)]
+pub struct OpenAICompatibleProvider {
+    pub config: OpenAICompatibleConfig,
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionBody<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    stream_options: StreamOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    content: Option<String>,
+}
+
+/// # Stream Event
+///
+/// A parsed `data:` line of the completion stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    Delta {
+        content: Option<String>,
+        finish_reason: Option<String>,
+        usage: Option<CompletionUsage>,
+    },
+    Done,
+}
+
+/// Parses a single line of a server-sent events stream. Blank lines, comments and
+/// fields other than `data` yield `None`.
+pub fn parse_stream_line(line: &str) -> Result<Option<StreamEvent>, ModelError> {
+    let Some(data) = line.trim_end_matches('\r').strip_prefix("data:") else {
+        return Ok(None);
+    };
+
+    let data = data.trim();
+    if data == "[DONE]" {
+        return Ok(Some(StreamEvent::Done));
+    }
+
+    let chunk: ChatCompletionChunk = serde_json::from_str(data)
+        .map_err(|e| ModelError::InvalidResponse(format!("invalid chunk: {}", e)))?;
+    let choice = chunk.choices.into_iter().next();
+
+    Ok(Some(StreamEvent::Delta {
+        content: choice.as_ref().and_then(|c| c.delta.content.clone()),
+        finish_reason: choice.and_then(|c| c.finish_reason),
+        usage: chunk.usage,
+    }))
+}
+
+impl OpenAICompatibleProvider {
+    /// Creates the provider, loading the CA bundle when the endpoint is served over `https`.
+    pub fn new(config: OpenAICompatibleConfig) -> Result<Self, ModelError> {
+        let uri = config
+            .base_url
+            .parse::<Uri>()
+            .map_err(|e| ModelError::Transport(format!("invalid base url: {}", e)))?;
+
+        let tls_config = match uri.scheme_str() {
+            Some("https") => {
+                Some(load_tls_config(&config.ca_bundle_path).map_err(ModelError::Transport)?)
+            }
+            Some("http") => None,
+            _ => {
+                return Err(ModelError::Transport(
+                    "base url must be http or https".to_string(),
+                ));
+            }
+        };
+
+        Ok(OpenAICompatibleProvider { config, tls_config })
+    }
+
+    fn completions_uri(&self) -> Result<Uri, ModelError> {
+        format!(
+            "{}/chat/completions",
+            self.config.base_url.trim_end_matches('/')
+        )
+        .parse::<Uri>()
+        .map_err(|e| ModelError::Transport(format!("invalid base url: {}", e)))
+    }
+
+    async fn send(&self, body: Vec<u8>) -> Result<Response<Incoming>, ModelError> {
+        let uri = self.completions_uri()?;
+        let host = uri
+            .host()
+            .ok_or_else(|| ModelError::Transport("base url has no host".to_string()))?
+            .to_string();
+        let authority = uri
+            .authority()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| host.clone());
+        let path = uri
+            .path_and_query()
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "/".to_string());
+
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(path)
+            .header(HOST, authority)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "text/event-stream");
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", api_key));
+        }
+        let request = request
+            .body(Full::new(Bytes::from(body)))
+            .map_err(|e| ModelError::Transport(format!("invalid request: {}", e)))?;
+
+        send_request(self.tls_config.as_ref(), &uri, request)
+            .await
+            .map_err(ModelError::Transport)
+    }
+
+    async fn stream_completion(
+        &self,
+        request: &CompletionRequest,
+        chunks: &Option<mpsc::Sender<String>>,
+    ) -> Result<Completion, ModelError> {
+        let body = serde_json::to_vec(&ChatCompletionBody {
+            model: &request.model,
+            messages: &request.messages,
+            stream: true,
+            stream_options: StreamOptions {
+                include_usage: true,
+            },
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+        })
+        .map_err(|e| ModelError::InvalidResponse(format!("invalid request body: {}", e)))?;
+
+        let response = self.send(body).await?;
+        let status = response.status();
+        let mut body = response.into_body();
+
+        if !status.is_success() {
+            let message = body
+                .collect()
+                .await
+                .map(|b| String::from_utf8_lossy(&b.to_bytes()).into_owned())
+                .unwrap_or_default();
+            return Err(ModelError::Provider {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        let mut completion = Completion {
+            content: String::new(),
+            usage: None,
+            finish_reason: None,
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+
+        'stream: while let Some(frame) = body.frame().await {
+            let frame = frame.map_err(|e| ModelError::Transport(e.to_string()))?;
+            let Ok(data) = frame.into_data() else {
+                continue;
+            };
+            buffer.extend_from_slice(&data);
+
+            // events can be split across frames, only consume complete lines
+            while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line[..line.len() - 1]).into_owned();
+
+                match parse_stream_line(&line)? {
+                    None => {}
+                    Some(StreamEvent::Done) => break 'stream,
+                    Some(StreamEvent::Delta {
+                        content,
+                        finish_reason,
+                        usage,
+                    }) => {
+                        if let Some(content) = content {
+                            forward_chunk(chunks, &content).await;
+                            completion.content.push_str(&content);
+                        }
+                        if finish_reason.is_some() {
+                            completion.finish_reason = finish_reason;
+                        }
+                        if usage.is_some() {
+                            completion.usage = usage;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(completion)
+    }
+}
+
+#[async_trait::async_trait]
+impl ModelProvider for OpenAICompatibleProvider {
+    fn supports(&self, model_tag: &str) -> bool {
+        self.config.models.is_empty() || self.config.models.iter().any(|m| m == model_tag)
+    }
+
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        chunks: Option<mpsc::Sender<String>>,
+    ) -> Result<Completion, ModelError> {
+        if !self.supports(&request.model) {
+            return Err(ModelError::UnsupportedModel(request.model));
+        }
+
+        tokio::time::timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            self.stream_completion(&request, &chunks),
+        )
+        .await
+        .map_err(|_| ModelError::Timeout)?
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(test)] // Ensure this file is only compiled for tests
+
+use tokio::sync::mpsc;
+
+use super::echo::EchoProvider;
+use super::openai::{StreamEvent, parse_stream_line};
+use super::{ChatMessage, ChatRole, CompletionRequest, ModelProvider};
+
+// --- Echo Provider Tests ---
+
+#[tokio::test]
+async fn test_echo_provider_streams_last_user_message() {
+    let (sender, mut receiver) = mpsc::channel(16);
+    let completion = EchoProvider::new()
+        .complete(
+            CompletionRequest {
+                model: "echo".to_string(),
+                messages: vec![
+                    ChatMessage::new(ChatRole::System, "be nice"),
+                    ChatMessage::new(ChatRole::User, "how are you"),
+                ],
+                max_tokens: None,
+                temperature: None,
+            },
+            Some(sender),
+        )
+        .await
+        .unwrap();
+
+    let mut streamed = String::new();
+    while let Some(chunk) = receiver.recv().await {
+        streamed.push_str(&chunk);
+    }
+
+    assert_eq!(completion.content, "echo: how are you");
+    assert_eq!(streamed, completion.content);
+    let usage = completion.usage.unwrap();
+    assert_eq!(usage.prompt_tokens, 5);
+    assert_eq!(usage.completion_tokens, 4);
+}
+
+// --- Stream Parsing Tests ---
+
+#[test]
+fn test_parse_stream_line_delta_and_done() {
+    let line = r#"data: {"choices":[{"delta":{"content":"Hel"},"finish_reason":null}]}"#;
+    assert_eq!(
+        parse_stream_line(line).unwrap(),
+        Some(StreamEvent::Delta {
+            content: Some("Hel".to_string()),
+            finish_reason: None,
+            usage: None,
+        })
+    );
+    assert_eq!(
+        parse_stream_line("data: [DONE]").unwrap(),
+        Some(StreamEvent::Done)
+    );
+    assert_eq!(parse_stream_line(": keep-alive").unwrap(), None);
+    assert!(parse_stream_line("data: {not json").is_err());
+}
@@ -0,0 +1,311 @@
+//!
+//! Moderation of the content posted in rooms.
+//!
+//! A [`ContentFilterPipeline`] runs in `RoomService::add_message` and can hide a message,
+//! queueing it for review, or reject it. Filters include word lists ([`WordListFilter`]),
+//! regular expressions ([`RegexFilter`]) and external classifiers ([`ClassifierFilter`]).
+//! Reports and the review queue are handled by `ModerationService`.
+//!
+
+#[cfg(test)]
+pub mod tests;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// # Filter Action
+///
+/// What happens to a message matched by a content filter.
+///
+/// - `Hide`: the message is posted hidden and queued for review.
+/// - `Reject`: the message is not posted at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    Hide,
+    Reject,
+}
+
+/// # Filter Match
+///
+/// Why a filter matched a message, e.g. the word or the label that was found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FilterMatch {
+    pub action: FilterAction,
+    pub reason: String,
+}
+
+/// # Filter Verdict
+///
+/// The match of a `ContentFilterPipeline`, with the name of the filter that produced it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FilterVerdict {
+    pub filter: String,
+    pub action: FilterAction,
+    pub reason: String,
+}
+
+impl fmt::Display for FilterVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.filter, self.reason)
+    }
+}
+
+/// # Content Filter
+///
+/// Checks the content of a message before it is posted. `None` lets it through.
+#[async_trait::async_trait]
+pub trait ContentFilter: Send + Sync + fmt::Debug {
+    /// Name of the filter, reported with its matches.
+    fn name(&self) -> &str;
+
+    async fn check(&self, content: &str) -> Option<FilterMatch>;
+}
+
+/// # Content Filter Pipeline
+///
+/// Runs the filters in order on the content of the messages posted by members. The most
+/// severe match wins: the pipeline stops at the first `Reject`, and otherwise reports the
+/// first `Hide`. An empty pipeline lets everything through.
+#[derive(Clone, Debug, Default)]
+pub struct ContentFilterPipeline {
+    pub filters: Vec<Arc<dyn ContentFilter>>,
+}
+
+impl ContentFilterPipeline {
+    pub fn new() -> Self {
+        ContentFilterPipeline::default()
+    }
+
+    pub fn with(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub async fn check(&self, content: &str) -> Option<FilterVerdict> {
+        let mut verdict: Option<FilterVerdict> = None;
+        for filter in &self.filters {
+            let Some(found) = filter.check(content).await else {
+                continue;
+            };
+            if verdict.is_none() || found.action == FilterAction::Reject {
+                verdict = Some(FilterVerdict {
+                    filter: filter.name().to_string(),
+                    action: found.action,
+                    reason: found.reason,
+                });
+            }
+            if found.action == FilterAction::Reject {
+                break;
+            }
+        }
+        verdict
+    }
+}
+
+/// # Word List Filter
+///
+/// Matches messages containing any of the listed words, as whole words and ignoring case.
+/// Entries of several words match when the words appear in a row.
+#[derive(Debug, Clone)]
+pub struct WordListFilter {
+    pub name: String,
+    pub action: FilterAction,
+    words: HashSet<Vec<String>>,
+}
+
+impl WordListFilter {
+    pub fn new(
+        name: impl Into<String>,
+        words: impl IntoIterator<Item = impl AsRef<str>>,
+        action: FilterAction,
+    ) -> Self {
+        WordListFilter {
+            name: name.into(),
+            action,
+            words: words
+                .into_iter()
+                .map(|entry| split_words(entry.as_ref()))
+                .filter(|entry| !entry.is_empty())
+                .collect(),
+        }
+    }
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl ContentFilter for WordListFilter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self, content: &str) -> Option<FilterMatch> {
+        let words = split_words(content);
+        let longest = self.words.iter().map(Vec::len).max()?;
+
+        for start in 0..words.len() {
+            for length in 1..=longest.min(words.len() - start) {
+                let candidate = &words[start..start + length];
+                if self.words.contains(candidate) {
+                    return Some(FilterMatch {
+                        action: self.action,
+                        reason: format!("contains \"{}\"", candidate.join(" ")),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// # Regex Filter
+///
+/// Matches messages in which any of the patterns is found, ignoring case.
+#[derive(Debug, Clone)]
+pub struct RegexFilter {
+    pub name: String,
+    pub action: FilterAction,
+    patterns: Vec<Regex>,
+}
+
+impl RegexFilter {
+    pub fn new(
+        name: impl Into<String>,
+        patterns: impl IntoIterator<Item = impl AsRef<str>>,
+        action: FilterAction,
+    ) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern.as_ref())
+                    .case_insensitive(true)
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RegexFilter {
+            name: name.into(),
+            action,
+            patterns,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ContentFilter for RegexFilter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self, content: &str) -> Option<FilterMatch> {
+        self.patterns
+            .iter()
+            .find(|pattern| pattern.is_match(content))
+            .map(|pattern| FilterMatch {
+                action: self.action,
+                reason: format!("matches /{}/", pattern.as_str()),
+            })
+    }
+}
+
+/// # Classification
+///
+/// Score, between 0 and 1, of a label such as `toxicity` or `spam` for a message.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Classification {
+    pub label: String,
+    pub score: f32,
+}
+
+/// # Classifier
+///
+/// An external service scoring the content of messages, e.g. a moderation API.
+#[async_trait::async_trait]
+pub trait Classifier: Send + Sync + fmt::Debug {
+    async fn classify(&self, content: &str) -> Result<Vec<Classification>, String>;
+}
+
+/// # Classifier Filter
+///
+/// Hides or rejects messages depending on the scores of a `Classifier`.
+///
+/// - `hide_threshold`: score from which a message is hidden.
+/// - `reject_threshold`: score from which a message is rejected.
+/// - `labels`: labels taken into account, all of them when empty.
+/// - `on_error`: what to do when the classifier fails. `None` lets the message through,
+///   so an outage of the classifier doesn't prevent members from talking.
+#[derive(Debug, Clone)]
+pub struct ClassifierFilter {
+    pub name: String,
+    pub classifier: Arc<dyn Classifier>,
+    pub hide_threshold: f32,
+    pub reject_threshold: f32,
+    pub labels: Vec<String>,
+    pub on_error: Option<FilterAction>,
+}
+
+impl ClassifierFilter {
+    pub fn new(name: impl Into<String>, classifier: Arc<dyn Classifier>) -> Self {
+        ClassifierFilter {
+            name: name.into(),
+            classifier,
+            hide_threshold: 0.7,
+            reject_threshold: 0.95,
+            labels: Vec::new(),
+            on_error: None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ContentFilter for ClassifierFilter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self, content: &str) -> Option<FilterMatch> {
+        let classifications = match self.classifier.classify(content).await {
+            Ok(classifications) => classifications,
+            Err(e) => {
+                warn!("Classifier {} failed: {}", self.name, e);
+                return self.on_error.map(|action| FilterMatch {
+                    action,
+                    reason: "classifier unavailable".to_string(),
+                });
+            }
+        };
+
+        let top = classifications
+            .into_iter()
+            .filter(|c| self.labels.is_empty() || self.labels.contains(&c.label))
+            .max_by(|a, b| a.score.total_cmp(&b.score))?;
+
+        let action = if top.score >= self.reject_threshold {
+            FilterAction::Reject
+        } else if top.score >= self.hide_threshold {
+            FilterAction::Hide
+        } else {
+            return None;
+        };
+
+        Some(FilterMatch {
+            action,
+            reason: format!("{} ({:.2})", top.label, top.score),
+        })
+    }
+}
@@ -0,0 +1,96 @@
+#![cfg(test)] // Ensure this file is only compiled for tests
+
+use std::sync::Arc;
+
+use super::{
+    Classification, Classifier, ClassifierFilter, ContentFilterPipeline, FilterAction, RegexFilter,
+    WordListFilter,
+};
+use crate::moderation::ContentFilter;
+
+#[derive(Debug)]
+struct FixedClassifier(Result<Vec<Classification>, String>);
+
+#[async_trait::async_trait]
+impl Classifier for FixedClassifier {
+    async fn classify(&self, _content: &str) -> Result<Vec<Classification>, String> {
+        self.0.clone()
+    }
+}
+
+fn classifier(scores: &[(&str, f32)]) -> Arc<dyn Classifier> {
+    Arc::new(FixedClassifier(Ok(scores
+        .iter()
+        .map(|(label, score)| Classification {
+            label: label.to_string(),
+            score: *score,
+        })
+        .collect())))
+}
+
+#[tokio::test]
+async fn test_word_list_filter_matches_whole_words() {
+    let filter = WordListFilter::new("words", ["darn", "free money"], FilterAction::Hide);
+
+    let found = filter.check("Well, DARN it!").await.unwrap();
+    assert_eq!(found.action, FilterAction::Hide);
+    assert_eq!(found.reason, "contains \"darn\"");
+
+    assert!(filter.check("get FREE   money now").await.is_some());
+    assert!(filter.check("darned money is free").await.is_none());
+}
+
+#[tokio::test]
+async fn test_regex_filter() {
+    let filter = RegexFilter::new("links", [r"https?://\S+\.ru\b"], FilterAction::Reject).unwrap();
+
+    assert!(filter.check("see HTTP://spam.ru now").await.is_some());
+    assert!(filter.check("see https://example.com").await.is_none());
+    assert!(RegexFilter::new("broken", ["("], FilterAction::Hide).is_err());
+}
+
+#[tokio::test]
+async fn test_classifier_filter_thresholds() {
+    let mut filter =
+        ClassifierFilter::new("toxicity", classifier(&[("toxic", 0.8), ("spam", 0.1)]));
+    let found = filter.check("anything").await.unwrap();
+    assert_eq!(found.action, FilterAction::Hide);
+    assert_eq!(found.reason, "toxic (0.80)");
+
+    filter.labels = vec!["spam".to_string()];
+    assert!(filter.check("anything").await.is_none());
+
+    filter.classifier = classifier(&[("spam", 0.99)]);
+    assert_eq!(
+        filter.check("anything").await.unwrap().action,
+        FilterAction::Reject
+    );
+
+    // failures let messages through unless told otherwise
+    filter.classifier = Arc::new(FixedClassifier(Err("timeout".to_string())));
+    assert!(filter.check("anything").await.is_none());
+    filter.on_error = Some(FilterAction::Hide);
+    assert_eq!(
+        filter.check("anything").await.unwrap().action,
+        FilterAction::Hide
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_keeps_the_most_severe_match() {
+    let pipeline = ContentFilterPipeline::new()
+        .with(WordListFilter::new("words", ["darn"], FilterAction::Hide))
+        .with(RegexFilter::new("links", [r"spam\.ru"], FilterAction::Reject).unwrap());
+
+    let verdict = pipeline.check("darn").await.unwrap();
+    assert_eq!(verdict.filter, "words");
+    assert_eq!(verdict.action, FilterAction::Hide);
+
+    let verdict = pipeline.check("darn, see spam.ru").await.unwrap();
+    assert_eq!(verdict.filter, "links");
+    assert_eq!(verdict.action, FilterAction::Reject);
+    assert_eq!(verdict.to_string(), "links: matches /spam\\.ru/");
+
+    assert!(pipeline.check("hello").await.is_none());
+    assert!(ContentFilterPipeline::new().check("darn").await.is_none());
+}
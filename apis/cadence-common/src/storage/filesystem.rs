@@ -0,0 +1,66 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use hyper::body::Bytes;
+
+use crate::storage::{StorageBackend, StorageError, validate_key};
+
+/// # Filesystem Storage
+///
+/// Stores every object as a file under `root`, the key being its relative path. The
+/// content type is not kept, it is part of the attachment metadata.
+#[derive(Debug, Clone)]
+pub struct FilesystemStorage {
+    pub root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FilesystemStorage { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for FilesystemStorage {
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| StorageError::Io(format!("failed to create directory: {}", e)))?;
+        }
+
+        // written aside then renamed, so a reader never sees a partial object
+        let partial = path.with_file_name(format!(".{}.partial", uuid::Uuid::new_v4()));
+        if let Err(e) = tokio::fs::write(&partial, &data).await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(StorageError::Io(format!("failed to write object: {}", e)));
+        }
+        tokio::fs::rename(&partial, &path)
+            .await
+            .map_err(|e| StorageError::Io(format!("failed to write object: {}", e)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Bytes::from(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(StorageError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(StorageError::Io(format!("failed to read object: {}", e))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageError::Io(format!("failed to delete object: {}", e))),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//!
+//! Object storage for message attachments.
+//!
+//! Attachments are stored through a [`StorageBackend`]: [`filesystem::FilesystemStorage`]
+//! keeps them in a local directory and [`s3::S3CompatibleStorage`] in a bucket of any
+//! S3-compatible service (AWS S3, MinIO, ...). Their metadata lives in the `attachment`
+//! table, and they are downloaded through URLs signed by [`signed_url::UrlSigner`].
+//!
+
+pub mod filesystem;
+pub mod s3;
+pub mod signed_url;
+pub mod sigv4;
+#[cfg(test)]
+pub mod tests;
+
+use std::fmt;
+
+use hyper::body::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CadenceError, EntityError, InputError, ServerError};
+
+/// # Storage Error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// No object is stored under the key.
+    NotFound(String),
+    /// The key is not a valid object key.
+    InvalidKey(String),
+    /// The object could not be read or written.
+    Io(String),
+    /// The storage service answered with a non-success status.
+    Backend { status: u16, message: String },
+    /// The storage service did not answer in time.
+    Timeout,
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound(key) => write!(f, "object not found: {}", key),
+            StorageError::InvalidKey(key) => write!(f, "invalid object key: {}", key),
+            StorageError::Io(message) => write!(f, "storage error: {}", message),
+            StorageError::Backend { status, message } => {
+                write!(f, "storage backend error ({}): {}", status, message)
+            }
+            StorageError::Timeout => write!(f, "storage request timed out"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<StorageError> for CadenceError {
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::NotFound(_) => {
+                CadenceError::Entity(EntityError::NotFound(error.to_string()))
+            }
+            StorageError::InvalidKey(_) => {
+                CadenceError::ServerError(ServerError::InternalError(error.to_string()))
+            }
+            StorageError::Timeout => {
+                CadenceError::ServerError(ServerError::GatewayTimeout(error.to_string()))
+            }
+            _ => CadenceError::ServerError(ServerError::ServiceUnavailable(error.to_string())),
+        }
+    }
+}
+
+/// # Storage Backend
+///
+/// Stores objects under keys made of `/` separated segments of ASCII letters, digits,
+/// `-`, `_` and `.`, see [`validate_key`].
+#[async_trait::async_trait]
+pub trait StorageBackend: Send + Sync + fmt::Debug {
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<(), StorageError>;
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError>;
+
+    /// Deletes an object. Deleting a missing object is not an error.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Fails with `InvalidKey` unless the key is made of non-empty `/` separated segments of
+/// ASCII letters, digits, `-`, `_` and `.`, none of them being `.` or `..`.
+pub fn validate_key(key: &str) -> Result<(), StorageError> {
+    let valid = !key.is_empty()
+        && key.len() <= 512
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(StorageError::InvalidKey(key.to_string()))
+    }
+}
+
+/// # Upload Policy
+///
+/// - `max_size_bytes`: maximum size of an attachment.
+/// - `allowed_content_types`: accepted media types, `type/*` accepts every subtype.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UploadPolicy {
+    pub max_size_bytes: usize,
+    pub allowed_content_types: Vec<String>,
+}
+
+impl Default for UploadPolicy {
+    fn default() -> Self {
+        UploadPolicy {
+            max_size_bytes: 10 * 1024 * 1024,
+            allowed_content_types: [
+                "image/png",
+                "image/jpeg",
+                "image/gif",
+                "image/webp",
+                "application/pdf",
+                "application/json",
+                "text/plain",
+                "text/markdown",
+                "text/csv",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+}
+
+/// Room left for the multipart boundaries and headers around the uploaded file.
+const MULTIPART_OVERHEAD_BYTES: usize = 16 * 1024;
+
+impl UploadPolicy {
+    /// Maximum size of an upload request body, to be set on the upload route in place of
+    /// the global request body limit.
+    pub fn body_limit(&self) -> usize {
+        self.max_size_bytes + MULTIPART_OVERHEAD_BYTES
+    }
+
+    /// Checks an upload against the policy, returning its normalized media type: lowercase
+    /// and without parameters.
+    pub fn validate(&self, content_type: &str, size: usize) -> Result<String, CadenceError> {
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        let allowed =
+            self.allowed_content_types
+                .iter()
+                .any(|allowed| match allowed.strip_suffix("/*") {
+                    Some(prefix) => media_type
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.len() > 1 && rest.starts_with('/')),
+                    None => allowed.eq_ignore_ascii_case(&media_type),
+                });
+        if media_type.is_empty() || !allowed {
+            return Err(CadenceError::Input(InputError::InvalidType(format!(
+                "content type {} is not allowed",
+                content_type
+            ))));
+        }
+
+        if size == 0 {
+            return Err(CadenceError::Input(InputError::InvalidLength(
+                "attachment is empty".to_string(),
+            )));
+        }
+        if size > self.max_size_bytes {
+            return Err(CadenceError::Input(InputError::InvalidLength(format!(
+                "attachment is {} bytes, the maximum is {} bytes",
+                size, self.max_size_bytes
+            ))));
+        }
+
+        Ok(media_type)
+    }
+}
+
+/// Keeps the last path component of an uploaded file name, without control characters,
+/// quotes or backslashes, and at most 255 characters long. Empty names become `file`.
+pub fn sanitize_file_name(file_name: &str) -> String {
+    let name: String = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(255)
+        .collect();
+
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        "file".to_string()
+    } else {
+        name.to_string()
+    }
+}
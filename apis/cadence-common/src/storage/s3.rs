@@ -0,0 +1,238 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, HOST};
+use hyper::{Method, Request, Response, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{load_tls_config, send_request};
+use crate::storage::sigv4::{self, EMPTY_PAYLOAD_SHA256, SigningParams};
+use crate::storage::{StorageBackend, StorageError, validate_key};
+
+/// # S3 Compatible Config
+///
+/// - `endpoint`: root of the service, e.g. `https://s3.eu-west-1.amazonaws.com` or
+///   `http://localhost:9000` for a local MinIO.
+/// - `path_style`: address the bucket as the first segment of the path rather than as a
+///   subdomain of the endpoint. MinIO only supports path style addressing by default.
+/// - `ca_bundle_path`: PEM bundle of the root certificates trusted for `https` endpoints.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct S3CompatibleConfig {
+    pub endpoint: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    #[serde(default = "default_path_style")]
+    pub path_style: bool,
+    #[serde(default = "default_ca_bundle_path")]
+    pub ca_bundle_path: String,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_path_style() -> bool {
+    true
+}
+
+fn default_ca_bundle_path() -> String {
+    "/etc/ssl/certs/ca-certificates.crt".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    30_000
+}
+
+/// # S3 Compatible Storage
+///
+/// Stores objects in a bucket of an S3-compatible service, authenticating every request
+/// with AWS Signature Version 4.
+#[derive(Debug, Clone)]
+pub struct S3CompatibleStorage {
+    pub config: S3CompatibleConfig,
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+}
+
+impl S3CompatibleStorage {
+    /// Creates the storage, loading the CA bundle when the endpoint is served over `https`.
+    pub fn new(config: S3CompatibleConfig) -> Result<Self, StorageError> {
+        let uri = config
+            .endpoint
+            .parse::<Uri>()
+            .map_err(|e| StorageError::Io(format!("invalid endpoint: {}", e)))?;
+
+        let tls_config = match uri.scheme_str() {
+            Some("https") => {
+                Some(load_tls_config(&config.ca_bundle_path).map_err(StorageError::Io)?)
+            }
+            Some("http") => None,
+            _ => {
+                return Err(StorageError::Io(
+                    "endpoint must be http or https".to_string(),
+                ));
+            }
+        };
+
+        Ok(S3CompatibleStorage { config, tls_config })
+    }
+
+    /// URL of an object, and its path as signed.
+    fn object_uri(&self, key: &str) -> Result<(Uri, String), StorageError> {
+        validate_key(key)?;
+
+        let endpoint = self
+            .config
+            .endpoint
+            .parse::<Uri>()
+            .map_err(|e| StorageError::Io(format!("invalid endpoint: {}", e)))?;
+        let scheme = endpoint.scheme_str().unwrap_or("http");
+        let authority = endpoint
+            .authority()
+            .ok_or_else(|| StorageError::Io("endpoint has no host".to_string()))?;
+
+        let (authority, path) = if self.config.path_style {
+            (
+                authority.to_string(),
+                format!(
+                    "/{}/{}",
+                    sigv4::uri_encode(&self.config.bucket, true),
+                    sigv4::uri_encode(key, false)
+                ),
+            )
+        } else {
+            (
+                format!("{}.{}", self.config.bucket, authority),
+                format!("/{}", sigv4::uri_encode(key, false)),
+            )
+        };
+
+        let uri = format!("{}://{}{}", scheme, authority, path)
+            .parse::<Uri>()
+            .map_err(|e| StorageError::Io(format!("invalid object url: {}", e)))?;
+        Ok((uri, path))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Bytes,
+        content_type: Option<&str>,
+    ) -> Result<Response<Incoming>, StorageError> {
+        let (uri, path) = self.object_uri(key)?;
+        let authority = uri
+            .authority()
+            .map(|a| a.to_string())
+            .ok_or_else(|| StorageError::Io("object url has no host".to_string()))?;
+
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_sha256 = if body.is_empty() {
+            EMPTY_PAYLOAD_SHA256.to_string()
+        } else {
+            sigv4::sha256_hex(&body)
+        };
+
+        let mut headers = vec![
+            ("host", authority.as_str()),
+            ("x-amz-content-sha256", payload_sha256.as_str()),
+            ("x-amz-date", amz_date.as_str()),
+        ];
+        if let Some(content_type) = content_type {
+            headers.push(("content-type", content_type));
+        }
+
+        let authorization = sigv4::authorization(
+            &SigningParams {
+                access_key_id: &self.config.access_key_id,
+                secret_access_key: &self.config.secret_access_key,
+                region: &self.config.region,
+                service: "s3",
+                amz_date: &amz_date,
+            },
+            method.as_str(),
+            &path,
+            &[],
+            &headers,
+            &payload_sha256,
+        );
+
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path.as_str())
+            .header(HOST, authority.as_str())
+            .header("x-amz-content-sha256", payload_sha256.as_str())
+            .header("x-amz-date", amz_date.as_str())
+            .header(AUTHORIZATION, authorization);
+        if let Some(content_type) = content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        let request = request
+            .body(Full::new(body))
+            .map_err(|e| StorageError::Io(format!("invalid request: {}", e)))?;
+
+        tokio::time::timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            send_request(self.tls_config.as_ref(), &uri, request),
+        )
+        .await
+        .map_err(|_| StorageError::Timeout)?
+        .map_err(StorageError::Io)
+    }
+}
+
+/// Reads the whole body of a response.
+async fn read_body(response: Response<Incoming>) -> Result<Bytes, StorageError> {
+    response
+        .into_body()
+        .collect()
+        .await
+        .map(|b| b.to_bytes())
+        .map_err(|e| StorageError::Io(format!("failed to read response: {}", e)))
+}
+
+async fn backend_error(response: Response<Incoming>) -> StorageError {
+    let status = response.status().as_u16();
+    let message = read_body(response)
+        .await
+        .map(|b| String::from_utf8_lossy(&b).into_owned())
+        .unwrap_or_default();
+    StorageError::Backend { status, message }
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for S3CompatibleStorage {
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<(), StorageError> {
+        let response = self
+            .send(Method::PUT, key, data, Some(content_type))
+            .await?;
+        if !response.status().is_success() {
+            return Err(backend_error(response).await);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError> {
+        let response = self.send(Method::GET, key, Bytes::new(), None).await?;
+        match response.status() {
+            status if status.is_success() => read_body(response).await,
+            StatusCode::NOT_FOUND => Err(StorageError::NotFound(key.to_string())),
+            _ => Err(backend_error(response).await),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let response = self.send(Method::DELETE, key, Bytes::new(), None).await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Ok(()),
+            _ => Err(backend_error(response).await),
+        }
+    }
+}